@@ -0,0 +1,167 @@
+use crate::CliError;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy_pen_tool_model::formats::{DxfCurves, GeoJsonProjection, GeoProjection};
+use bevy_pen_tool_model::model::{Globals, SaveEncoding};
+
+use std::path::PathBuf;
+
+/// Largest distance between the points of an imported look-up table and the fitted curves,
+/// the same as when a `.lut` file is dropped in the editor.
+const DEFAULT_MAX_ERROR: f64 = 1.0;
+/// Distance between the points of exported Tiled objects, in canvas units.
+const DEFAULT_SPACING: f32 = 8.0;
+const DEFAULT_TILE_SIZE: u32 = 32;
+
+/// Positional arguments and options shared by every command.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub paths: Vec<PathBuf>,
+    /// Number of points of the look-up tables, when given on the command line.
+    pub points: Option<u32>,
+    pub encoding: SaveEncoding,
+    pub color: Option<Color>,
+    pub road: bool,
+    pub road_width: f32,
+    pub texture: Option<PathBuf>,
+    pub max_error: f64,
+    /// Duration in seconds of the animation along the paths of exported Lottie files.
+    pub animate: Option<f32>,
+    /// Entities written for each group of exported DXF files.
+    pub dxf_curves: DxfCurves,
+    /// Projection of the coordinates of GeoJSON files.
+    pub geojson: GeoJsonProjection,
+    /// Whether the segments of imported GeoJSON lines and Tiled objects stay straight.
+    pub straight: bool,
+    /// Distance between the points of the objects of exported Tiled maps.
+    pub spacing: f32,
+    pub tile_size: UVec2,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, CliError> {
+        let globals = Globals::default();
+        let mut options = Self {
+            paths: Vec::new(),
+            points: None,
+            encoding: SaveEncoding::default(),
+            color: None,
+            road: false,
+            road_width: globals.road_width,
+            texture: None,
+            max_error: DEFAULT_MAX_ERROR,
+            animate: None,
+            dxf_curves: DxfCurves::default(),
+            geojson: GeoJsonProjection::default(),
+            straight: false,
+            spacing: DEFAULT_SPACING,
+            tile_size: UVec2::splat(DEFAULT_TILE_SIZE),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| CliError::Usage(format!("{} needs a value", arg)))
+            };
+
+            match arg.as_str() {
+                "--points" => {
+                    let points = number::<u32>(arg, value()?)?;
+                    if points < 2 {
+                        return Err(CliError::Usage(
+                            "a look-up table needs at least 2 points".to_string(),
+                        ));
+                    }
+                    options.points = Some(points);
+                }
+                "--encoding" => {
+                    options.encoding = match value()?.as_str() {
+                        "json" => SaveEncoding::Json,
+                        "ron" => SaveEncoding::Ron,
+                        "bincode" => SaveEncoding::Bincode,
+                        other => {
+                            return Err(CliError::Usage(format!("unknown encoding {}", other)))
+                        }
+                    }
+                }
+                "--color" => {
+                    let hex = value()?;
+                    let color = Color::hex(hex.trim_start_matches('#'))
+                        .map_err(|_| CliError::Usage(format!("invalid color {}", hex)))?;
+                    options.color = Some(color);
+                }
+                "--road" => options.road = true,
+                "--width" => options.road_width = number::<f32>(arg, value()?)?,
+                "--texture" => options.texture = Some(PathBuf::from(value()?)),
+                "--max-error" => options.max_error = number::<f64>(arg, value()?)?,
+                "--animate" => options.animate = Some(number::<f32>(arg, value()?)?),
+                "--polylines" => options.dxf_curves = DxfCurves::Polylines,
+                "--projection" => {
+                    options.geojson.projection = match value()?.as_str() {
+                        "planar" => GeoProjection::Planar,
+                        "equirectangular" => GeoProjection::Equirectangular,
+                        "mercator" => GeoProjection::WebMercator,
+                        other => {
+                            return Err(CliError::Usage(format!("unknown projection {}", other)))
+                        }
+                    }
+                }
+                "--origin" => {
+                    let origin = value()?;
+                    let (x, y) = origin.split_once(',').ok_or_else(|| {
+                        CliError::Usage(format!("invalid origin {}, expected x,y", origin))
+                    })?;
+                    options.geojson.origin =
+                        Some(DVec2::new(number(arg, x.trim())?, number(arg, y.trim())?));
+                }
+                "--scale" => options.geojson.scale = number::<f64>(arg, value()?)?,
+                "--straight" => options.straight = true,
+                "--spacing" => options.spacing = number::<f32>(arg, value()?)?,
+                "--tile-size" => {
+                    options.tile_size = UVec2::splat(number::<u32>(arg, value()?)?.max(1))
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(CliError::Usage(format!("unknown option {}", arg)))
+                }
+                _ => options.paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        options.geojson.fit_max_error = options.fit_max_error();
+        Ok(options)
+    }
+
+    /// Largest distance between the points of imported lines and the fitted curves, or `None`
+    /// when their segments stay straight.
+    pub fn fit_max_error(&self) -> Option<f64> {
+        if self.straight {
+            None
+        } else {
+            Some(self.max_error)
+        }
+    }
+
+    /// Number of points of the look-up tables, the editor's default when not given.
+    pub fn num_points(&self) -> u32 {
+        self.points
+            .unwrap_or_else(|| Globals::default().group_lut_num_points)
+    }
+
+    /// The input and output paths of a command that converts one file.
+    pub fn input_output(&self) -> Result<(&PathBuf, &PathBuf), CliError> {
+        match self.paths.as_slice() {
+            [input, output] => Ok((input, output)),
+            _ => Err(CliError::Usage(
+                "expected an input and an output file".to_string(),
+            )),
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse::<T>()
+        .map_err(|_| CliError::Usage(format!("invalid value {} for {}", value, option)))
+}
@@ -0,0 +1,418 @@
+use crate::args::Options;
+use crate::{extension, read, read_to_string, write, CliError};
+
+use bevy::prelude::*;
+
+use bevy_pen_tool_model::formats::*;
+use bevy_pen_tool_model::mesh::MeshMeta;
+use bevy_pen_tool_model::model::*;
+
+use std::path::{Path, PathBuf};
+
+/// Texture of the road meshes when none is given, the same as in the editor.
+const ROAD_TEXTURE: &[u8] = include_bytes!("../../../assets/textures/single_lane_road.png");
+const ROAD_TEXTURE_NAME: &str = "single_lane_road.png";
+
+/// `lut <input> [output.lut]`: writes the look-up table of every group of the input, computed
+/// with `--points` points. The output defaults to the input with a `.lut` extension.
+pub fn regenerate_luts(options: &Options) -> Result<(), CliError> {
+    let (input, output) = match options.paths.as_slice() {
+        [input] => (input, input.with_extension("lut")),
+        [input, output] => (input, output.clone()),
+        _ => {
+            return Err(CliError::Usage(
+                "expected an input file and an optional output file".to_string(),
+            ))
+        }
+    };
+
+    let mut groups = read_groups(input, options)?;
+    compute_luts(&mut groups, options.num_points(), input)?;
+    write_luts(&groups, &output, options)
+}
+
+/// `convert <input> <output>`: the conversion is chosen from the extensions. The look-up
+/// tables of `.group` inputs are only computed again when `--points` is given.
+pub fn convert(options: &Options) -> Result<(), CliError> {
+    let (input, output) = options.input_output()?;
+
+    let mut groups = read_groups(input, options)?;
+    if let Some(num_points) = options.points {
+        compute_luts(&mut groups, num_points, input)?;
+    }
+
+    match extension(output).as_str() {
+        "group" => {
+            let saved = groups
+                .iter()
+                .map(|group| group.to_save())
+                .collect::<Vec<GroupSaveLoad>>();
+            let bytes = saved
+                .to_versioned_bytes(options.encoding)
+                .map_err(|e| CliError::Format(output.clone(), e))?;
+            write(output, &bytes)
+        }
+        "lut" => write_luts(&groups, output, options),
+        "svg" => {
+            let paths = groups
+                .iter()
+                .map(|group| SvgExportPath::from_group(&group.to_save(), None))
+                .collect::<Vec<SvgExportPath>>();
+            write(output, write_svg(&paths).as_bytes())
+        }
+        "json" => {
+            let paths = groups
+                .iter()
+                .map(|group| LottieExportPath::from_group(&group.to_save(), options.color))
+                .collect::<Vec<LottieExportPath>>();
+            let animation = options.animate.map(|duration| LottieAnimation {
+                duration,
+                ..Default::default()
+            });
+            write(output, write_lottie(&paths, animation).as_bytes())
+        }
+        "dxf" => {
+            let paths = groups
+                .iter()
+                .enumerate()
+                .map(|(k, group)| {
+                    DxfExportPath::from_group(&group.to_save(), &format!("group{}", k))
+                })
+                .collect::<Vec<DxfExportPath>>();
+            write(output, write_dxf(&paths, options.dxf_curves).as_bytes())
+        }
+        "geojson" => {
+            let paths = groups
+                .iter()
+                .map(|group| GeoJsonExportPath::from_group(&group.to_save()))
+                .collect::<Vec<GeoJsonExportPath>>();
+            write(output, write_geojson(&paths, &options.geojson).as_bytes())
+        }
+        "tmj" | "tmx" => {
+            let paths = groups
+                .iter()
+                .enumerate()
+                .map(|(k, group)| TiledExportPath::from_group(&group.to_save(), k, options.spacing))
+                .collect::<Vec<TiledExportPath>>();
+            let format = if extension(output) == "tmx" {
+                TiledFormat::Tmx
+            } else {
+                TiledFormat::Json
+            };
+            write(
+                output,
+                write_tiled(&paths, format, options.tile_size).as_bytes(),
+            )
+        }
+        "obj" | "gltf" | "glb" => write_meshes(&mut groups, output, options),
+        _ => Err(CliError::Usage(format!(
+            "cannot convert to {} (expected .group, .dxf, .geojson, .json, .lut, .svg, .tmj, .tmx, .obj, .gltf or .glb)",
+            output.display()
+        ))),
+    }
+}
+
+/// `mesh <input> <output>`: writes a fill mesh, or a road mesh with `--road`, for every group.
+pub fn make_meshes(options: &Options) -> Result<(), CliError> {
+    let (input, output) = options.input_output()?;
+
+    match extension(output).as_str() {
+        "obj" | "gltf" | "glb" => {
+            let mut groups = read_groups(input, options)?;
+            write_meshes(&mut groups, output, options)
+        }
+        _ => Err(CliError::Usage(format!(
+            "cannot write a mesh to {} (expected .obj, .gltf or .glb)",
+            output.display()
+        ))),
+    }
+}
+
+// the traversal stops at the first curve that is not latched, so the look-up table of such a
+// group would leave curves out
+fn compute_luts(groups: &mut [OfflineGroup], num_points: u32, path: &Path) -> Result<(), CliError> {
+    for (k, group) in groups.iter_mut().enumerate() {
+        group.compute_lut(num_points);
+        if !group.is_connected() {
+            return Err(not_connected(path, k));
+        }
+    }
+    Ok(())
+}
+
+fn not_connected(path: &Path, index: usize) -> CliError {
+    CliError::Invalid(format!(
+        "{}: the curves of group {} are not all latched together",
+        path.display(),
+        index
+    ))
+}
+
+/// Reads the groups of any file that the editor can import.
+pub fn read_groups(path: &Path, options: &Options) -> Result<Vec<OfflineGroup>, CliError> {
+    let conversion_error = |e: ConversionError| CliError::Conversion(path.to_path_buf(), e);
+    let format_error = |e: SaveFormatError| CliError::Format(path.to_path_buf(), e);
+
+    let groups = match extension(path).as_str() {
+        "group" => {
+            Vec::<GroupSaveLoad>::from_versioned_bytes(&read(path)?).map_err(format_error)?
+        }
+        "lut" => {
+            let lut = StandaloneLut::from_versioned_bytes(&read(path)?).map_err(format_error)?;
+            vec![fit_group(&lut, options.color, options).map_err(conversion_error)?]
+        }
+        "svg" => {
+            svg_to_groups(&read_to_string(path)?, options.num_points()).map_err(conversion_error)?
+        }
+        "json" => lottie_to_groups(&read_to_string(path)?, options.num_points())
+            .map_err(conversion_error)?,
+        "dxf" => {
+            dxf_to_groups(&read_to_string(path)?, options.num_points()).map_err(conversion_error)?
+        }
+        "geojson" => geojson_to_groups(
+            &read_to_string(path)?,
+            &options.geojson,
+            options.num_points(),
+        )
+        .map_err(conversion_error)?,
+        "tmj" | "tmx" => tiled_to_groups(
+            &read_to_string(path)?,
+            options.fit_max_error(),
+            options.num_points(),
+        )
+        .map_err(conversion_error)?,
+        "obj" => {
+            let obj = parse_obj(&read_to_string(path)?).map_err(conversion_error)?;
+            let color = options.color.or_else(|| obj_color(path, &obj));
+
+            mesh_outlines(&obj.mesh)
+                .map_err(conversion_error)?
+                .into_iter()
+                .map(|outline| {
+                    let lut = StandaloneLut {
+                        path_length: 0.0,
+                        lut: outline,
+                    };
+                    fit_group(&lut, color, options)
+                })
+                .collect::<Result<Vec<GroupSaveLoad>, ConversionError>>()
+                .map_err(conversion_error)?
+        }
+        _ => {
+            return Err(CliError::Usage(format!(
+                "cannot read {} (expected .group, .json, .lut, .svg or .obj)",
+                path.display()
+            )))
+        }
+    };
+
+    if groups.is_empty() {
+        return Err(conversion_error(ConversionError::Empty));
+    }
+    Ok(groups.iter().map(OfflineGroup::from_save).collect())
+}
+
+// the same fitting as when a .lut file is dropped in the editor
+fn fit_group(
+    lut: &StandaloneLut,
+    color: Option<Color>,
+    options: &Options,
+) -> Result<GroupSaveLoad, ConversionError> {
+    let curves = lut.fit_curves(options.max_error);
+    if curves.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    GroupSaveLoad::from_chain(&curves, color, options.num_points())
+}
+
+// color of the material of an .obj file, when its material library is next to it
+fn obj_color(path: &Path, obj: &ObjFile) -> Option<Color> {
+    let library = obj.material_library.as_ref()?;
+    let contents = std::fs::read_to_string(path.with_file_name(library)).ok()?;
+    let materials = parse_mtl(&contents).ok()?;
+    let material = match &obj.material_name {
+        Some(name) => materials.get(name)?,
+        None => materials.values().next()?,
+    };
+    Some(material.color)
+}
+
+fn write_luts(groups: &[OfflineGroup], path: &Path, options: &Options) -> Result<(), CliError> {
+    for (k, group) in groups.iter().enumerate() {
+        let path = numbered_path(path, k, groups.len());
+        let bytes = group
+            .group
+            .standalone_lut
+            .to_versioned_bytes(options.encoding)
+            .map_err(|e| CliError::Format(path.clone(), e))?;
+        write(&path, &bytes)?;
+    }
+    Ok(())
+}
+
+/// Writes one `.obj` file per group, each with its `.mtl` and `.meta` files, or all the meshes
+/// as the nodes of a single glTF file.
+fn write_meshes(
+    groups: &mut [OfflineGroup],
+    path: &Path,
+    options: &Options,
+) -> Result<(), CliError> {
+    let texture = match (&options.texture, options.road) {
+        (Some(texture), true) => Some((
+            texture
+                .file_name()
+                .map_or(ROAD_TEXTURE_NAME.to_string(), |name| {
+                    name.to_string_lossy().to_string()
+                }),
+            read(texture)?,
+        )),
+        (None, true) => Some((ROAD_TEXTURE_NAME.to_string(), ROAD_TEXTURE.to_vec())),
+        (_, false) => None,
+    };
+
+    let mut gltf_meshes = Vec::new();
+    let num_groups = groups.len();
+
+    for (k, group) in groups.iter_mut().enumerate() {
+        group.compute_lut(options.num_points());
+        if !group.is_connected() {
+            return Err(not_connected(path, k));
+        }
+
+        let color = options
+            .color
+            .or_else(|| group.curves.values().next().and_then(|bezier| bezier.color))
+            .unwrap_or(Color::WHITE);
+
+        let (mesh, bounding_box) = if options.road {
+            group.road_mesh(options.num_points(), options.road_width, color)
+        } else {
+            group.fill_mesh(color).ok_or_else(|| {
+                CliError::Invalid(format!(
+                    "{}: group {} does not enclose an area",
+                    path.display(),
+                    k
+                ))
+            })?
+        };
+
+        let center_of_mass = group.center_of_mass();
+        let mesh_path = numbered_path(path, k, num_groups);
+        let name = mesh_path.file_stem().map_or("my_mesh".to_string(), |stem| {
+            stem.to_string_lossy().to_string()
+        });
+
+        match extension(path).as_str() {
+            "obj" => {
+                let material = ObjMaterial {
+                    name: name.clone(),
+                    color: if texture.is_some() {
+                        Color::WHITE
+                    } else {
+                        color
+                    },
+                    texture: texture
+                        .as_ref()
+                        .map(|(texture_name, _)| texture_name.clone()),
+                };
+                write_obj_mesh(&mesh, &name, &material, &mesh_path)?;
+
+                // the same side-car file as the one saved by the editor
+                let meta = MeshMeta {
+                    center_of_mass,
+                    position: center_of_mass,
+                    bounding_box,
+                    color: if texture.is_some() {
+                        Vec4::ONE
+                    } else {
+                        color.into()
+                    },
+                };
+                let meta_path = mesh_path.with_extension("meta");
+                let bytes = meta
+                    .to_versioned_string()
+                    .map_err(|e| CliError::Format(meta_path.clone(), e))?;
+                write(&meta_path, bytes.as_bytes())?;
+            }
+            _ => {
+                let material = match &texture {
+                    Some((texture_name, bytes)) => {
+                        let mime_type = Path::new(texture_name)
+                            .extension()
+                            .and_then(|extension| image_mime_type(&extension.to_string_lossy()))
+                            .ok_or_else(|| {
+                                CliError::Invalid(format!(
+                                    "{} cannot be used as a glTF texture",
+                                    texture_name
+                                ))
+                            })?;
+                        GltfMaterial::Texture {
+                            mime_type: mime_type.to_string(),
+                            bytes: bytes.clone(),
+                        }
+                    }
+                    None => GltfMaterial::Color(color.into()),
+                };
+                let transform = Transform::from_translation(center_of_mass.extend(0.0));
+                gltf_meshes.push(
+                    GltfMesh::from_mesh(&name, &mesh, transform, material)
+                        .map_err(|e| CliError::Conversion(path.to_path_buf(), e))?,
+                );
+            }
+        }
+    }
+
+    match extension(path).as_str() {
+        "obj" => {
+            if let Some((texture_name, bytes)) = &texture {
+                write(&path.with_file_name(texture_name), bytes)?;
+            }
+            Ok(())
+        }
+        "glb" => {
+            let bytes =
+                write_glb(&gltf_meshes).map_err(|e| CliError::Conversion(path.to_path_buf(), e))?;
+            write(path, &bytes)
+        }
+        _ => {
+            let contents = write_gltf(&gltf_meshes)
+                .map_err(|e| CliError::Conversion(path.to_path_buf(), e))?;
+            write(path, contents.as_bytes())
+        }
+    }
+}
+
+// same as the editor: an .obj file and its .mtl file
+fn write_obj_mesh(
+    mesh: &Mesh,
+    name: &str,
+    material: &ObjMaterial,
+    path: &Path,
+) -> Result<(), CliError> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+
+    let obj = write_obj(mesh, name, &mtl_name, material)
+        .map_err(|e| CliError::Conversion(path.to_path_buf(), e))?;
+    write(path, &obj)?;
+    write(&mtl_path, write_mtl(material).as_bytes())
+}
+
+/// `my_path.lut` becomes `my_path0.lut`, `my_path1.lut`, ... when there is more than one file
+/// to write.
+fn numbered_path(path: &Path, index: usize, count: usize) -> PathBuf {
+    if count < 2 {
+        return path.to_path_buf();
+    }
+
+    let stem = path
+        .file_stem()
+        .map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}{}", stem, index),
+    };
+    path.with_file_name(file_name)
+}
@@ -0,0 +1,129 @@
+//! `pen_tool` converts the files of bevy_pen_tool without opening a window, so that they can
+//! be processed by an asset build pipeline. Look-up tables and meshes are computed by the same
+//! code as in the editor.
+
+mod args;
+mod convert;
+mod validate;
+
+use args::Options;
+
+use bevy_pen_tool_model::formats::ConversionError;
+use bevy_pen_tool_model::model::SaveFormatError;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: pen_tool <command> [options]
+
+Commands:
+  lut <input> [output.lut]       compute the look-up table of every group of the input
+  convert <input> <output>       convert between .group, .lut, .svg, .obj, .dxf, .geojson,
+                                 Tiled .tmj and .tmx, and Lottie .json files
+  mesh <input> <output>          make a fill or road mesh for every group of the input,
+                                 as .obj, .gltf or .glb
+  validate <file>...             check that files can be loaded by the editor
+
+Inputs can be .group, .lut, .svg, .obj, .dxf, .geojson, .tmj, .tmx or Lottie .json files.
+A file is written for every group when the output holds one group per file: my_path.lut
+becomes my_path0.lut, my_path1.lut, ...
+
+Options:
+  --points <n>                   number of points of the look-up tables
+  --encoding <json|ron|bincode>  encoding of the .group and .lut files that are written
+  --color <rrggbb>               color of the imported curves and of the fill meshes
+  --road                         make road meshes instead of fill meshes
+  --width <w>                    half width of the road meshes
+  --texture <file>               road texture, the editor's texture by default
+  --animate <seconds>            add a dot moving along each path of an exported Lottie file
+  --polylines                    write exported DXF paths as R12 polylines through the
+                                 look-up tables instead of splines
+  --projection <planar|equirectangular|mercator>
+                                 projection of GeoJSON coordinates, equirectangular by
+                                 default
+  --origin <x,y>                 GeoJSON coordinates at the canvas origin, the center of
+                                 the imported file by default
+  --scale <s>                    canvas units per meter, or per unit of planar coordinates
+  --straight                     keep the segments of GeoJSON lines and Tiled objects
+                                 straight instead of fitting curves through their vertices
+  --spacing <d>                  distance between the points of exported Tiled objects
+  --tile-size <n>                width and height of the tiles of exported Tiled maps
+  --max-error <e>                largest distance between the points of an imported
+                                 look-up table, mesh outline or line and the fitted curves";
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    File(PathBuf, std::io::Error),
+    Format(PathBuf, SaveFormatError),
+    Conversion(PathBuf, ConversionError),
+    Invalid(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(reason) => write!(f, "{}", reason),
+            Self::File(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Format(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Conversion(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        if let CliError::Usage(_) = e {
+            eprintln!("\n{}", USAGE);
+            exit(2);
+        }
+        exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+    let (command, rest) = args
+        .split_first()
+        .ok_or_else(|| CliError::Usage("missing command".to_string()))?;
+
+    match command.as_str() {
+        "lut" => convert::regenerate_luts(&Options::parse(rest)?),
+        "convert" => convert::convert(&Options::parse(rest)?),
+        "mesh" => convert::make_meshes(&Options::parse(rest)?),
+        "validate" => validate::validate(&Options::parse(rest)?),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("unknown command {}", other))),
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<u8>, CliError> {
+    std::fs::read(path).map_err(|e| CliError::File(path.to_path_buf(), e))
+}
+
+pub fn read_to_string(path: &Path) -> Result<String, CliError> {
+    std::fs::read_to_string(path).map_err(|e| CliError::File(path.to_path_buf(), e))
+}
+
+pub fn write(path: &Path, contents: &[u8]) -> Result<(), CliError> {
+    std::fs::write(path, contents).map_err(|e| CliError::File(path.to_path_buf(), e))?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+/// Lower-case extension of the file, or an empty string.
+pub fn extension(path: &Path) -> String {
+    path.extension().map_or(String::new(), |extension| {
+        extension.to_string_lossy().to_lowercase()
+    })
+}
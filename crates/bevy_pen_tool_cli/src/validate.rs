@@ -0,0 +1,194 @@
+use crate::args::Options;
+use crate::{extension, read, read_to_string, CliError};
+
+use bevy::prelude::*;
+
+use bevy_pen_tool_model::formats::*;
+use bevy_pen_tool_model::mesh::MeshMeta;
+use bevy_pen_tool_model::model::*;
+
+use std::collections::HashSet;
+use std::path::Path;
+
+/// `validate <file>...`: checks every file and prints a line for each of them. Fails when
+/// any file is invalid.
+pub fn validate(options: &Options) -> Result<(), CliError> {
+    if options.paths.is_empty() {
+        return Err(CliError::Usage("expected at least one file".to_string()));
+    }
+
+    let mut num_invalid = 0;
+    for path in options.paths.iter() {
+        match validate_file(path, options) {
+            Ok(summary) => println!("ok      {}: {}", path.display(), summary),
+            Err(e) => {
+                println!("invalid {}", e);
+                num_invalid += 1;
+            }
+        }
+    }
+
+    if num_invalid > 0 {
+        return Err(CliError::Invalid(format!(
+            "{} of {} files are invalid",
+            num_invalid,
+            options.paths.len()
+        )));
+    }
+    Ok(())
+}
+
+fn validate_file(path: &Path, options: &Options) -> Result<String, CliError> {
+    let invalid = |reason: String| CliError::Invalid(format!("{}: {}", path.display(), reason));
+    let format_error = |e: SaveFormatError| CliError::Format(path.to_path_buf(), e);
+    let conversion_error = |e: ConversionError| CliError::Conversion(path.to_path_buf(), e);
+
+    match extension(path).as_str() {
+        "group" => {
+            let groups =
+                Vec::<GroupSaveLoad>::from_versioned_bytes(&read(path)?).map_err(format_error)?;
+            if groups.is_empty() {
+                return Err(invalid("the file holds no group".to_string()));
+            }
+            for (k, group) in groups.iter().enumerate() {
+                check_group(group, options)
+                    .map_err(|reason| invalid(format!("group {}: {}", k, reason)))?;
+            }
+            let num_curves = groups.iter().map(|group| group.lut.len()).sum::<usize>();
+            Ok(format!("{} groups, {} curves", groups.len(), num_curves))
+        }
+        "lut" => {
+            let lut = StandaloneLut::from_versioned_bytes(&read(path)?).map_err(format_error)?;
+            check_lut(&lut).map_err(invalid)?;
+            Ok(format!(
+                "{} points, length {}",
+                lut.lut.len(),
+                lut.path_length
+            ))
+        }
+        "meta" => {
+            let meta = MeshMeta::from_versioned_bytes(&read(path)?).map_err(format_error)?;
+            let (min, max) = meta.bounding_box;
+            if !(min.is_finite() && max.is_finite() && meta.position.is_finite()) {
+                return Err(invalid("a position is not a finite number".to_string()));
+            }
+            Ok(format!("mesh at {}", meta.position))
+        }
+        "svg" => {
+            let groups = svg_to_groups(&read_to_string(path)?, options.num_points())
+                .map_err(conversion_error)?;
+            Ok(format!("{} paths", groups.len()))
+        }
+        "json" => {
+            let groups = lottie_to_groups(&read_to_string(path)?, options.num_points())
+                .map_err(conversion_error)?;
+            Ok(format!("{} paths", groups.len()))
+        }
+        "dxf" => {
+            let groups = dxf_to_groups(&read_to_string(path)?, options.num_points())
+                .map_err(conversion_error)?;
+            Ok(format!("{} paths", groups.len()))
+        }
+        "geojson" => {
+            let groups = geojson_to_groups(
+                &read_to_string(path)?,
+                &options.geojson,
+                options.num_points(),
+            )
+            .map_err(conversion_error)?;
+            Ok(format!("{} paths", groups.len()))
+        }
+        "tmj" | "tmx" => {
+            let groups = tiled_to_groups(&read_to_string(path)?, None, options.num_points())
+                .map_err(conversion_error)?;
+            Ok(format!("{} objects", groups.len()))
+        }
+        "obj" => {
+            let obj = parse_obj(&read_to_string(path)?).map_err(conversion_error)?;
+            if let Some(library) = &obj.material_library {
+                let mtl_path = path.with_file_name(library);
+                let materials = parse_mtl(&read_to_string(&mtl_path)?)
+                    .map_err(|e| CliError::Conversion(mtl_path.clone(), e))?;
+                if let Some(name) = &obj.material_name {
+                    if !materials.contains_key(name) {
+                        return Err(invalid(format!("material {} is not in {}", name, library)));
+                    }
+                }
+            }
+            Ok(format!("{} vertices", obj.mesh.count_vertices()))
+        }
+        _ => Err(CliError::Usage(format!(
+            "cannot validate {} (expected .group, .dxf, .geojson, .json, .lut, .meta, .svg, .tmj, .tmx or .obj)",
+            path.display()
+        ))),
+    }
+}
+
+// the editor assumes that the curves are latched into a single chain
+fn check_group(group: &GroupSaveLoad, options: &Options) -> Result<(), String> {
+    if group.lut.is_empty() {
+        return Err("the group holds no curve".to_string());
+    }
+
+    let ids = group
+        .lut
+        .iter()
+        .map(|(bezier, _, _, _)| bezier.id)
+        .collect::<HashSet<BezierId>>();
+    if ids.len() != group.lut.len() {
+        return Err("two curves share the same id".to_string());
+    }
+
+    for (k, (bezier, _, (t_min, t_max), _)) in group.lut.iter().enumerate() {
+        let positions = bezier.positions;
+        let is_finite = [
+            positions.start,
+            positions.end,
+            positions.control_start,
+            positions.control_end,
+        ]
+        .iter()
+        .all(|position| position.is_finite());
+        if !is_finite {
+            return Err(format!("curve {} has a position that is not finite", k));
+        }
+
+        if !(0.0..=1.0 + 1e-6).contains(t_min) || t_max < t_min || *t_max > 1.0 + 1e-6 {
+            return Err(format!("curve {} covers an invalid range of t", k));
+        }
+
+        if bezier
+            .latches
+            .values()
+            .any(|latch| !ids.contains(&latch.latched_to_id))
+        {
+            return Err(format!(
+                "curve {} is latched to a curve outside of the group",
+                k
+            ));
+        }
+    }
+
+    check_lut(&group.standalone_lut)?;
+
+    let mut offline = OfflineGroup::from_save(group);
+    offline.compute_lut(options.num_points());
+    if !offline.is_connected() {
+        return Err("the curves are not all latched together".to_string());
+    }
+
+    Ok(())
+}
+
+fn check_lut(lut: &StandaloneLut) -> Result<(), String> {
+    if lut.lut.len() < 2 {
+        return Err("the look-up table has fewer than 2 points".to_string());
+    }
+    if !lut.path_length.is_finite() || lut.path_length < 0.0 {
+        return Err(format!("invalid path length {}", lut.path_length));
+    }
+    if !lut.lut.iter().all(|point: &Vec2| point.is_finite()) {
+        return Err("a point of the look-up table is not finite".to_string());
+    }
+    Ok(())
+}
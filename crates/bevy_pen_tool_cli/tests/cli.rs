@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn pen_tool(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pen_tool"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+// a folder of its own for each test, removed at the end
+fn temp_root(name: &str) -> PathBuf {
+    let mut root = std::env::temp_dir();
+    root.push(format!("pen_tool_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    root
+}
+
+// a group of three latched curves saved by the editor
+fn saved_group() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../bevy_pen_tool_plugin/crates/bevy_pen_tool_model/tests/fixtures/v1/my_group.group")
+}
+
+// the same curves without their latches
+fn unlatched_group() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unlatched.group")
+}
+
+fn assert_usage_error(args: &[&str], message: &str) {
+    let output = pen_tool(args);
+    assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    assert!(stderr(&output).contains(message), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Usage: pen_tool"));
+}
+
+#[test]
+fn parses_arguments() {
+    assert_usage_error(&[], "missing command");
+    assert_usage_error(&["frobnicate"], "unknown command frobnicate");
+    assert_usage_error(
+        &["convert", "in.group"],
+        "expected an input and an output file",
+    );
+    assert_usage_error(
+        &["lut"],
+        "expected an input file and an optional output file",
+    );
+    assert_usage_error(&["lut", "in.group", "--points"], "--points needs a value");
+    assert_usage_error(
+        &["lut", "in.group", "--points", "1"],
+        "a look-up table needs at least 2 points",
+    );
+    assert_usage_error(
+        &["lut", "in.group", "--points", "many"],
+        "invalid value many",
+    );
+    assert_usage_error(
+        &["lut", "in.group", "--encoding", "xml"],
+        "unknown encoding xml",
+    );
+    assert_usage_error(
+        &["convert", "--color", "purple", "a", "b"],
+        "invalid color purple",
+    );
+    assert_usage_error(
+        &["convert", "a.geojson", "b.svg", "--projection", "polar"],
+        "unknown projection polar",
+    );
+    assert_usage_error(&["convert", "a", "b", "--origin", "1"], "invalid origin 1");
+    assert_usage_error(&["convert", "a", "b", "--fast"], "unknown option --fast");
+    assert_usage_error(&["validate"], "expected at least one file");
+
+    let help = pen_tool(&["help"]);
+    assert!(help.status.success());
+    assert!(stdout(&help).contains("Commands:"));
+}
+
+#[test]
+fn converts_saved_groups() {
+    let root = temp_root("convert");
+    let path = |name: &str| root.join(name).to_string_lossy().to_string();
+    let group = saved_group().to_string_lossy().to_string();
+    let (lut, svg, binary) = (path("track.lut"), path("track.svg"), path("track.group"));
+
+    let output = pen_tool(&["lut", &group, &lut, "--points", "50"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("wrote"));
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&lut).unwrap()).unwrap();
+    assert!(saved["version"].is_u64());
+    assert_eq!(saved["data"]["lut"].as_array().unwrap().len(), 50);
+
+    assert!(pen_tool(&["convert", &group, &svg]).status.success());
+    let contents = std::fs::read_to_string(&svg).unwrap();
+    assert_eq!(contents.matches("<path").count(), 1);
+    assert_eq!(contents.matches(" C ").count(), 3);
+
+    // binary files are read back by every command
+    let output = pen_tool(&["convert", &group, &binary, "--encoding", "bincode"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(std::fs::read(&binary).unwrap().starts_with(b"PENB"));
+
+    let validated = pen_tool(&["validate", &binary, &lut]);
+    assert!(validated.status.success(), "{}", stdout(&validated));
+    assert!(stdout(&validated).contains("1 groups, 3 curves"));
+
+    let output = pen_tool(&["convert", &group, &path("track.xyz")]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("cannot convert to"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn rejects_groups_that_are_not_latched() {
+    let root = temp_root("unlatched");
+    let path = |name: &str| root.join(name).to_string_lossy().to_string();
+    let group = unlatched_group().to_string_lossy().to_string();
+    let lut = path("track.lut");
+
+    let output = pen_tool(&["lut", &group, &lut]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("not all latched together"));
+    assert!(!Path::new(&lut).exists());
+
+    // the same check as when making meshes and validating
+    let output = pen_tool(&["convert", &group, &lut, "--points", "50"]);
+    assert!(stderr(&output).contains("not all latched together"));
+    let output = pen_tool(&["mesh", &group, &path("track.obj")]);
+    assert!(stderr(&output).contains("not all latched together"));
+    let output = pen_tool(&["validate", &group]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("not all latched together"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,736 @@
+//! DXF import and export, for interchange with CAD tools. On export, every group is put on its
+//! own layer, either as a SPLINE of degree 3 that goes exactly through the curves, or as an
+//! R12 POLYLINE sampled from the look-up table for readers without SPLINE support. On import,
+//! SPLINE, LWPOLYLINE, POLYLINE and LINE entities become chains of latched curves, and the
+//! entities of a layer that touch end to start are joined into one group.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+/// Number of points sampled along a spline that cannot be converted exactly, for each span
+/// between two knots.
+const SAMPLES_PER_SPAN: usize = 16;
+/// Largest distance between the sampled points of such a spline and the fitted curves.
+const FIT_MAX_ERROR: f64 = 0.1;
+
+/// Entities written for each group by [`write_dxf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DxfCurves {
+    /// One SPLINE of degree 3 whose control points are the anchors and control points of the
+    /// curves. Exact, but SPLINE entities were only added to DXF after R12.
+    Splines,
+    /// One R12 POLYLINE through the points of the look-up table.
+    Polylines,
+}
+
+impl Default for DxfCurves {
+    fn default() -> Self {
+        Self::Splines
+    }
+}
+
+/// A group written by [`write_dxf`], in canvas coordinates.
+#[derive(Debug, Clone)]
+pub struct DxfExportPath {
+    pub layer: String,
+    pub curves: Vec<BezierPositions>,
+    pub closed: bool,
+    pub color: Option<Color>,
+    /// Points of the polyline fallback, evenly spaced along the path.
+    pub lut: Vec<Vec2>,
+}
+
+impl DxfExportPath {
+    /// The curves of a group in their direction of travel, on the given layer.
+    pub fn from_group(group: &GroupSaveLoad, layer: &str) -> Self {
+        Self {
+            layer: layer_name(layer),
+            curves: group.path_curves(),
+            closed: group.is_closed(),
+            color: group.lut.first().and_then(|(bezier, _, _, _)| bezier.color),
+            lut: group.standalone_lut.lut.clone(),
+        }
+    }
+}
+
+/// Writes an ASCII DXF file with a layer per path and one entity on each layer. The file is an
+/// R2000 file, with handles and subclass markers, when it holds splines, and a plain R12 file
+/// otherwise. R12 has no true colors, so polylines get the nearest of the first seven colors
+/// of the AutoCAD palette.
+pub fn write_dxf(paths: &[DxfExportPath], curves: DxfCurves) -> String {
+    let has_splines =
+        curves == DxfCurves::Splines && paths.iter().any(|path| !path.curves.is_empty());
+    let mut body = DxfWriter {
+        dxf: String::new(),
+        next_handle: if has_splines { Some(1) } else { None },
+    };
+
+    let mut layers: Vec<&str> = Vec::new();
+    for path in paths.iter() {
+        if !layers.contains(&path.layer.as_str()) {
+            layers.push(&path.layer);
+        }
+    }
+
+    body.pair(0, "SECTION");
+    body.pair(2, "TABLES");
+    body.pair(0, "TABLE");
+    body.pair(2, "LAYER");
+    body.handle();
+    body.subclass("AcDbSymbolTable");
+    body.pair(70, layers.len());
+    for layer in layers {
+        body.pair(0, "LAYER");
+        body.handle();
+        body.subclass("AcDbSymbolTableRecord");
+        body.subclass("AcDbLayerTableRecord");
+        body.pair(2, layer);
+        body.pair(70, 0);
+        body.pair(62, 7);
+        body.pair(6, "CONTINUOUS");
+    }
+    body.pair(0, "ENDTAB");
+    body.pair(0, "ENDSEC");
+
+    body.pair(0, "SECTION");
+    body.pair(2, "ENTITIES");
+    for path in paths.iter() {
+        match curves {
+            DxfCurves::Splines if !path.curves.is_empty() => body.spline(path),
+            _ if path.lut.len() > 1 => body.polyline(path),
+            _ => (),
+        }
+    }
+    body.pair(0, "ENDSEC");
+    body.pair(0, "EOF");
+
+    // the header holds the first free handle, known once the rest of the file is written
+    let mut dxf = String::new();
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "HEADER");
+    pair(&mut dxf, 9, "$ACADVER");
+    match body.next_handle {
+        Some(next_handle) => {
+            pair(&mut dxf, 1, "AC1015");
+            pair(&mut dxf, 9, "$HANDSEED");
+            pair(&mut dxf, 5, format!("{:X}", next_handle));
+        }
+        None => pair(&mut dxf, 1, "AC1009"),
+    }
+    pair(&mut dxf, 0, "ENDSEC");
+
+    dxf + &body.dxf
+}
+
+// handles and subclass markers are only written in R2000 files, where next_handle is set
+struct DxfWriter {
+    dxf: String,
+    next_handle: Option<u32>,
+}
+
+impl DxfWriter {
+    fn pair(&mut self, code: i32, value: impl std::fmt::Display) {
+        pair(&mut self.dxf, code, value);
+    }
+
+    fn handle(&mut self) {
+        if let Some(handle) = self.next_handle {
+            self.pair(5, format!("{:X}", handle));
+            self.next_handle = Some(handle + 1);
+        }
+    }
+
+    fn subclass(&mut self, name: &str) {
+        if self.next_handle.is_some() {
+            self.pair(100, name);
+        }
+    }
+
+    fn point(&mut self, code: i32, point: Vec2) {
+        self.pair(code, number(point.x));
+        self.pair(code + 10, number(point.y));
+        self.pair(code + 20, "0.0");
+    }
+
+    // a clamped knot vector where every inner knot is repeated three times, so that each span
+    // is exactly one of the cubic curves
+    fn spline(&mut self, path: &DxfExportPath) {
+        let mut control_points = vec![path.curves[0].start];
+        for curve in path.curves.iter() {
+            control_points.extend([curve.control_start, curve.control_end, curve.end]);
+        }
+
+        let num_curves = path.curves.len();
+        let mut knots = vec![0.0; 4];
+        for k in 1..num_curves {
+            knots.extend([k as f32; 3]);
+        }
+        knots.extend([num_curves as f32; 4]);
+
+        self.pair(0, "SPLINE");
+        self.entity_header(path);
+        self.subclass("AcDbSpline");
+        self.pair(210, 0.0);
+        self.pair(220, 0.0);
+        self.pair(230, 1.0);
+        // planar, and closed when the ends meet
+        self.pair(70, if path.closed { 9 } else { 8 });
+        self.pair(71, 3);
+        self.pair(72, knots.len());
+        self.pair(73, control_points.len());
+        self.pair(74, 0);
+        self.pair(42, "0.0000001");
+        self.pair(43, "0.0000001");
+        for knot in knots {
+            self.pair(40, number(knot));
+        }
+        for point in control_points {
+            self.point(10, point);
+        }
+    }
+
+    fn polyline(&mut self, path: &DxfExportPath) {
+        // the last point of a closed look-up table is on top of the first one
+        let mut points = path.lut.as_slice();
+        if path.closed && points.len() > 2 && points[0].distance(points[points.len() - 1]) < 1e-3 {
+            points = &points[..points.len() - 1];
+        }
+
+        self.pair(0, "POLYLINE");
+        self.entity_header(path);
+        self.subclass("AcDb2dPolyline");
+        self.pair(66, 1);
+        self.point(10, Vec2::ZERO);
+        self.pair(70, if path.closed { 1 } else { 0 });
+        for point in points {
+            self.pair(0, "VERTEX");
+            self.handle();
+            self.subclass("AcDbEntity");
+            self.pair(8, &path.layer);
+            self.subclass("AcDbVertex");
+            self.subclass("AcDb2dVertex");
+            self.point(10, *point);
+        }
+        self.pair(0, "SEQEND");
+        self.handle();
+        self.subclass("AcDbEntity");
+        self.pair(8, &path.layer);
+    }
+
+    fn entity_header(&mut self, path: &DxfExportPath) {
+        self.handle();
+        self.subclass("AcDbEntity");
+        self.pair(8, &path.layer);
+
+        let color = match path.color {
+            Some(color) => color,
+            None => return,
+        };
+        if self.next_handle.is_some() {
+            let [r, g, b, _] = color.as_rgba_f32();
+            let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+            self.pair(420, (channel(r) << 16) | (channel(g) << 8) | channel(b));
+        } else {
+            self.pair(62, palette_color(color));
+        }
+    }
+}
+
+// the nearest of the first seven colors of the AutoCAD palette, which R12 files are limited to
+fn palette_color(color: Color) -> u32 {
+    let palette = [
+        Color::RED,
+        Color::YELLOW,
+        Color::GREEN,
+        Color::CYAN,
+        Color::BLUE,
+        Color::FUCHSIA,
+        Color::WHITE,
+    ];
+    let rgb = |color: Color| Vec4::from(color.as_rgba_f32()).truncate();
+    (1..)
+        .zip(palette)
+        .min_by(|(_, a), (_, b)| {
+            let a = rgb(*a).distance_squared(rgb(color));
+            let b = rgb(*b).distance_squared(rgb(color));
+            a.total_cmp(&b)
+        })
+        .map(|(index, _)| index)
+        .unwrap()
+}
+
+fn pair(dxf: &mut String, code: i32, value: impl std::fmt::Display) {
+    writeln!(dxf, "{:>3}\n{}", code, value).unwrap();
+}
+
+fn number(value: f32) -> String {
+    if value == 0.0 {
+        "0.0".to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+// layer names cannot hold some characters in DXF files
+fn layer_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '$' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if name.is_empty() {
+        "0".to_string()
+    } else {
+        name
+    }
+}
+
+/// Turns the entities of a DXF file into groups. Each layer becomes one group, or several
+/// when its entities do not touch end to start.
+pub fn dxf_to_groups(
+    contents: &str,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let pairs = read_pairs(contents)?;
+
+    let entities_start = pairs
+        .windows(2)
+        .position(|window| {
+            window[0] == (0, "SECTION".to_string()) && window[1] == (2, "ENTITIES".to_string())
+        })
+        .ok_or_else(|| ConversionError::Syntax("the file has no ENTITIES section".to_string()))?;
+
+    let mut entities: Vec<Entity> = Vec::new();
+    for (code, value) in pairs[entities_start + 2..].iter() {
+        if *code == 0 {
+            if value == "ENDSEC" {
+                break;
+            }
+            entities.push(Entity {
+                kind: value.to_string(),
+                codes: Vec::new(),
+            });
+        } else if let Some(entity) = entities.last_mut() {
+            entity.codes.push((*code, value.clone()));
+        }
+    }
+
+    // (layer, curves, color) of each entity, in the order of the file
+    let mut chains: Vec<(String, Vec<BezierPositions>, Option<Color>)> = Vec::new();
+    let mut k = 0;
+    while k < entities.len() {
+        let entity = &entities[k];
+        let curves = match entity.kind.as_str() {
+            "SPLINE" => spline_curves(entity)?,
+            "LWPOLYLINE" => polyline_curves(&entity.codes, entity.flag(70) & 1 == 1),
+            "POLYLINE" => {
+                // the vertices follow the polyline, up to the SEQEND entity
+                let mut vertex_codes = Vec::new();
+                while k + 1 < entities.len() && entities[k + 1].kind == "VERTEX" {
+                    k += 1;
+                    let vertex = &entities[k];
+                    vertex_codes.extend(
+                        vertex
+                            .codes
+                            .iter()
+                            .filter(|(code, _)| [10, 20, 42].contains(code))
+                            .cloned(),
+                    );
+                }
+                polyline_curves(&vertex_codes, entity.flag(70) & 1 == 1)
+            }
+            "LINE" => match (entity.point(10), entity.point(11)) {
+                (Some(start), Some(end)) => vec![line(start, end)],
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        k += 1;
+
+        if curves.is_empty() {
+            continue;
+        }
+
+        let layer = entity.value(8).unwrap_or("0").to_string();
+        let color = entity.color();
+
+        // entities of the same layer that continue the previous one are joined to it
+        match chains.last_mut() {
+            Some((previous_layer, previous_curves, _))
+                if previous_layer == &layer
+                    && previous_curves.last().map_or(false, |last| {
+                        last.end.distance(curves[0].start) < ANCHOR_MERGE_DISTANCE
+                    })
+                    && previous_curves[0]
+                        .start
+                        .distance(previous_curves.last().unwrap().end)
+                        >= ANCHOR_MERGE_DISTANCE =>
+            {
+                previous_curves.extend(curves)
+            }
+            _ => chains.push((layer, curves, color)),
+        }
+    }
+
+    if chains.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+
+    chains
+        .iter()
+        .map(|(_, curves, color)| GroupSaveLoad::from_chain(curves, *color, num_points))
+        .collect()
+}
+
+struct Entity {
+    kind: String,
+    codes: Vec<(i32, String)>,
+}
+
+impl Entity {
+    fn value(&self, code: i32) -> Option<&str> {
+        self.codes
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn flag(&self, code: i32) -> i64 {
+        self.value(code)
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(0)
+    }
+
+    fn point(&self, code: i32) -> Option<Vec2> {
+        let x = self.value(code)?.parse::<f32>().ok()?;
+        let y = self.value(code + 10)?.parse::<f32>().ok()?;
+        Some(Vec2::new(x, y))
+    }
+
+    // true color, or the first seven colors of the AutoCAD palette
+    fn color(&self) -> Option<Color> {
+        if let Some(rgb) = self.value(420).and_then(|value| value.parse::<u32>().ok()) {
+            let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.0;
+            return Some(Color::rgb(channel(16), channel(8), channel(0)));
+        }
+
+        match self.flag(62) {
+            1 => Some(Color::RED),
+            2 => Some(Color::YELLOW),
+            3 => Some(Color::GREEN),
+            4 => Some(Color::CYAN),
+            5 => Some(Color::BLUE),
+            6 => Some(Color::FUCHSIA),
+            7 => Some(Color::WHITE),
+            _ => None,
+        }
+    }
+}
+
+fn read_pairs(contents: &str) -> Result<Vec<(i32, String)>, ConversionError> {
+    let lines = contents.lines().collect::<Vec<&str>>();
+
+    lines
+        .chunks(2)
+        .enumerate()
+        .filter(|(_, chunk)| chunk.len() == 2)
+        .map(|(k, chunk)| {
+            let code = chunk[0].trim().parse::<i32>().map_err(|_| {
+                ConversionError::Syntax(format!(
+                    "line {}: invalid group code {}",
+                    2 * k + 1,
+                    chunk[0].trim()
+                ))
+            })?;
+            Ok((code, chunk[1].trim().to_string()))
+        })
+        .collect()
+}
+
+// the values of a code that repeats, such as the knots or the coordinates of control points
+fn numbers(codes: &[(i32, String)], code: i32) -> Vec<f64> {
+    codes
+        .iter()
+        .filter(|(c, _)| *c == code)
+        .filter_map(|(_, value)| value.parse::<f64>().ok())
+        .collect()
+}
+
+fn points(codes: &[(i32, String)], x_code: i32) -> Vec<DVec2> {
+    numbers(codes, x_code)
+        .into_iter()
+        .zip(numbers(codes, x_code + 10))
+        .map(|(x, y)| DVec2::new(x, y))
+        .collect()
+}
+
+// every segment is a straight line, or an arc when the bulge of its first vertex is not zero
+fn polyline_curves(codes: &[(i32, String)], closed: bool) -> Vec<BezierPositions> {
+    // each vertex starts with its x coordinate, and its bulge comes after it
+    let mut vertices: Vec<(DVec2, f64)> = Vec::new();
+    for (code, value) in codes.iter() {
+        let value = match value.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        match code {
+            10 => vertices.push((DVec2::new(value, 0.0), 0.0)),
+            20 => {
+                if let Some((point, _)) = vertices.last_mut() {
+                    point.y = value;
+                }
+            }
+            42 => {
+                if let Some((_, bulge)) = vertices.last_mut() {
+                    *bulge = value;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let num_vertices = vertices.len();
+    let num_segments = if closed {
+        num_vertices
+    } else {
+        num_vertices.saturating_sub(1)
+    };
+
+    let mut curves = Vec::new();
+    for k in 0..num_segments {
+        let (start, bulge) = vertices[k];
+        let (end, _) = vertices[(k + 1) % num_vertices];
+        if start.distance(end) < 1e-9 {
+            continue;
+        }
+
+        if bulge.abs() < 1e-9 {
+            curves.push(line(start.as_vec2(), end.as_vec2()));
+        } else {
+            curves.extend(arc(start, end, bulge));
+        }
+    }
+    curves
+}
+
+fn line(start: Vec2, end: Vec2) -> BezierPositions {
+    BezierPositions {
+        start,
+        end,
+        control_start: start + (end - start) / 3.0,
+        control_end: end - (end - start) / 3.0,
+    }
+}
+
+// the bulge is the tangent of a quarter of the angle of the arc, positive when counterclockwise
+fn arc(start: DVec2, end: DVec2, bulge: f64) -> Vec<BezierPositions> {
+    let angle = 4.0 * bulge.atan();
+    let chord = end - start;
+    let radius = chord.length() / (2.0 * (angle / 2.0).sin());
+    let middle = (start + end) / 2.0;
+    let normal = DVec2::new(-chord.y, chord.x).normalize();
+    let center = middle + normal * radius * (angle / 2.0).cos();
+
+    let start_angle = (start - center).y.atan2((start - center).x);
+    let num_pieces = (angle.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+    let step = angle / num_pieces as f64;
+    let handle = 4.0 / 3.0 * (step / 4.0).tan();
+    let radius = radius.abs();
+
+    (0..num_pieces)
+        .map(|k| {
+            let a0 = start_angle + step * k as f64;
+            let a1 = a0 + step;
+            let p0 = center + DVec2::new(a0.cos(), a0.sin()) * radius;
+            let p1 = center + DVec2::new(a1.cos(), a1.sin()) * radius;
+            let t0 = DVec2::new(-a0.sin(), a0.cos()) * radius * handle;
+            let t1 = DVec2::new(-a1.sin(), a1.cos()) * radius * handle;
+            BezierPositions {
+                start: p0.as_vec2(),
+                control_start: (p0 + t0).as_vec2(),
+                control_end: (p1 - t1).as_vec2(),
+                end: p1.as_vec2(),
+            }
+        })
+        .collect()
+}
+
+fn spline_curves(entity: &Entity) -> Result<Vec<BezierPositions>, ConversionError> {
+    let degree = entity.flag(71) as usize;
+    let knots = numbers(&entity.codes, 40);
+    let control_points = points(&entity.codes, 10);
+
+    // splines defined by their fit points only are fitted again
+    if control_points.is_empty() {
+        let fit_points = points(&entity.codes, 11);
+        return Ok(fit_through(&fit_points));
+    }
+
+    if !(1..=3).contains(&degree) {
+        return Err(ConversionError::Unsupported(format!(
+            "spline of degree {}",
+            degree
+        )));
+    }
+    if knots.len() != control_points.len() + degree + 1 {
+        return Err(ConversionError::Syntax(format!(
+            "a spline with {} control points needs {} knots, not {}",
+            control_points.len(),
+            control_points.len() + degree + 1,
+            knots.len()
+        )));
+    }
+
+    let is_clamped = knots[..=degree].iter().all(|knot| *knot == knots[0])
+        && knots[knots.len() - degree - 1..]
+            .iter()
+            .all(|knot| *knot == knots[knots.len() - 1]);
+
+    if is_clamped {
+        Ok(bezier_segments(knots, control_points, degree)
+            .iter()
+            .map(|segment| elevate(segment))
+            .collect())
+    } else {
+        // the ends of an unclamped spline are not on control points, it is sampled instead
+        let start = knots[degree];
+        let end = knots[knots.len() - degree - 1];
+        let num_samples = SAMPLES_PER_SPAN * (control_points.len() - degree).max(1);
+        let samples = (0..=num_samples)
+            .map(|k| {
+                let t = start + (end - start) * k as f64 / num_samples as f64;
+                de_boor(&knots, &control_points, degree, t)
+            })
+            .collect::<Vec<DVec2>>();
+        Ok(fit_through(&samples))
+    }
+}
+
+fn fit_through(points: &[DVec2]) -> Vec<BezierPositions> {
+    let lut = StandaloneLut {
+        path_length: 0.0,
+        lut: points.iter().map(|p| p.as_vec2()).collect(),
+    };
+    lut.fit_curves(FIT_MAX_ERROR)
+}
+
+// inserts every inner knot until it is repeated `degree` times, after which every span of
+// the clamped spline is a Bezier curve whose control points are consecutive
+fn bezier_segments(
+    mut knots: Vec<f64>,
+    mut control_points: Vec<DVec2>,
+    degree: usize,
+) -> Vec<Vec<DVec2>> {
+    let first = knots[0];
+    let last = knots[knots.len() - 1];
+
+    let mut inner_knots = knots
+        .iter()
+        .copied()
+        .filter(|knot| *knot > first && *knot < last)
+        .collect::<Vec<f64>>();
+    inner_knots.dedup();
+
+    for knot in inner_knots {
+        let multiplicity = knots.iter().filter(|k| **k == knot).count();
+        for _ in multiplicity..degree {
+            insert_knot(&mut knots, &mut control_points, degree, knot);
+        }
+    }
+
+    control_points
+        .windows(degree + 1)
+        .step_by(degree)
+        .map(|window| window.to_vec())
+        .collect()
+}
+
+// Boehm's algorithm
+fn insert_knot(knots: &mut Vec<f64>, control_points: &mut Vec<DVec2>, degree: usize, knot: f64) {
+    // span such that knots[span] <= knot < knots[span + 1]
+    let span = match knots.iter().rposition(|k| *k <= knot) {
+        Some(span) => span,
+        None => return,
+    };
+    let multiplicity = knots.iter().filter(|k| **k == knot).count();
+
+    let mut new_points = Vec::with_capacity(control_points.len() + 1);
+    for i in 0..=control_points.len() {
+        let point = if i + degree <= span {
+            control_points[i]
+        } else if i + multiplicity <= span {
+            let alpha = (knot - knots[i]) / (knots[i + degree] - knots[i]);
+            control_points[i - 1] * (1.0 - alpha) + control_points[i] * alpha
+        } else {
+            control_points[i - 1]
+        };
+        new_points.push(point);
+    }
+
+    knots.insert(span + 1, knot);
+    *control_points = new_points;
+}
+
+fn de_boor(knots: &[f64], control_points: &[DVec2], degree: usize, t: f64) -> DVec2 {
+    let last_span = knots.len() - degree - 2;
+    let span = (degree..=last_span)
+        .rev()
+        .find(|k| knots[*k] <= t)
+        .unwrap_or(degree);
+
+    let mut d = (0..=degree)
+        .map(|j| control_points[j + span - degree])
+        .collect::<Vec<DVec2>>();
+
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + span - degree;
+            let denominator = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if denominator == 0.0 {
+                0.0
+            } else {
+                (t - knots[i]) / denominator
+            };
+            d[j] = d[j - 1] * (1.0 - alpha) + d[j] * alpha;
+        }
+    }
+    d[degree]
+}
+
+// lines and quadratic curves are turned into cubic curves
+fn elevate(segment: &[DVec2]) -> BezierPositions {
+    let [start, control_start, control_end, end] = match segment {
+        [start, end] => [
+            *start,
+            *start + (*end - *start) / 3.0,
+            *end - (*end - *start) / 3.0,
+            *end,
+        ],
+        [start, control, end] => [
+            *start,
+            *start + (*control - *start) * 2.0 / 3.0,
+            *end + (*control - *end) * 2.0 / 3.0,
+            *end,
+        ],
+        [start, control_start, control_end, end] => [*start, *control_start, *control_end, *end],
+        _ => [DVec2::ZERO; 4],
+    };
+
+    BezierPositions {
+        start: start.as_vec2(),
+        control_start: control_start.as_vec2(),
+        control_end: control_end.as_vec2(),
+        end: end.as_vec2(),
+    }
+}
@@ -0,0 +1,293 @@
+//! Text to curves. The glyphs of a TrueType or OpenType font are laid out line by line, with
+//! the kerning of the font, and every contour of their outlines becomes a closed group.
+//! Quadratic outlines are elevated to cubic curves. Outer contours run counter-clockwise and
+//! holes run clockwise whatever the convention of the font, so that a fill mesh of an outer
+//! contour cuts out the holes that lie inside it.
+
+use crate::formats::ConversionError;
+use crate::mesh::polygon_contains;
+use crate::model::*;
+
+use bevy::prelude::*;
+
+use std::collections::BTreeMap;
+
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+/// Segments shorter than this, in font units, are dropped.
+const EPSILON: f32 = 0.001;
+/// Points sampled on each curve of a contour to find its orientation and what it contains.
+const SAMPLES_PER_CURVE: usize = 8;
+
+/// Horizontal alignment of the lines of a text, relative to the origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for TextAlign {
+    fn default() -> Self {
+        Self::Left
+    }
+}
+
+/// How a text is laid out before being turned into curves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextLayout {
+    /// Height of the em square, in canvas units.
+    pub size: f32,
+    /// Distance between two baselines, as a multiple of the line height of the font.
+    pub line_height: f32,
+    /// Space added after every glyph, in canvas units.
+    pub letter_spacing: f32,
+    pub align: TextAlign,
+    /// Adjust the space between pairs of glyphs with the `kern` table of the font.
+    pub kerning: bool,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            size: 100.0,
+            line_height: 1.0,
+            letter_spacing: 0.0,
+            align: TextAlign::Left,
+            kerning: true,
+        }
+    }
+}
+
+/// Turns a text into closed groups, one per contour of every glyph. The baseline of the first
+/// line is at y = 0, and the lines are aligned on x = 0. Each group keeps the character it
+/// comes from as its "glyph" metadata, and the contours of a glyph share an
+/// [`OUTLINE_METADATA`] value so that its fill keeps the counters empty.
+pub fn text_to_groups(
+    font: &[u8],
+    text: &str,
+    layout: &TextLayout,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let face = Face::from_slice(font, 0).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+
+    let scale = layout.size / face.units_per_em() as f32;
+    let line_advance = (face.ascender() as f32 - face.descender() as f32 + face.line_gap() as f32)
+        * scale
+        * layout.line_height;
+
+    let mut groups = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let glyphs = layout_line(&face, line, layout, scale);
+        let width = glyphs.last().map_or(0.0, |(_, _, x, advance)| x + advance);
+        let offset = match layout.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => -width / 2.0,
+            TextAlign::Right => -width,
+        };
+        let origin_y = -(line_index as f32) * line_advance;
+
+        for (character, glyph_id, x, _) in glyphs {
+            let mut builder = GlyphBuilder::default();
+            if face.outline_glyph(glyph_id, &mut builder).is_none() {
+                // spaces have no outline
+                continue;
+            }
+            builder.finish();
+
+            let origin = Vec2::new(x + offset, origin_y);
+            let metadata = BTreeMap::from([
+                ("glyph".to_string(), character.to_string()),
+                (OUTLINE_METADATA.to_string(), new_outline_id()),
+            ]);
+            for contour in orient_contours(builder.contours) {
+                let curves = contour
+                    .iter()
+                    .map(|[start, control_start, control_end, end]| BezierPositions {
+                        start: origin + *start * scale,
+                        end: origin + *end * scale,
+                        control_start: origin + *control_start * scale,
+                        control_end: origin + *control_end * scale,
+                    })
+                    .collect::<Vec<BezierPositions>>();
+
+                let mut group = GroupSaveLoad::from_chain(&curves, None, num_points)?;
+                group.set_metadata(&metadata);
+                groups.push(group);
+            }
+        }
+    }
+
+    if groups.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    Ok(groups)
+}
+
+// the glyphs of a line, with the position of their origin and their advance, in canvas units
+fn layout_line(
+    face: &Face,
+    line: &str,
+    layout: &TextLayout,
+    scale: f32,
+) -> Vec<(char, GlyphId, f32, f32)> {
+    let mut glyphs = Vec::new();
+    let mut x = 0.0;
+    let mut previous: Option<GlyphId> = None;
+
+    for character in line.chars() {
+        // missing characters are drawn with the .notdef glyph
+        let glyph_id = face.glyph_index(character).unwrap_or(GlyphId(0));
+
+        if let (Some(left), true) = (previous, layout.kerning) {
+            x += kerning(face, left, glyph_id) * scale;
+        }
+
+        let advance = face.glyph_hor_advance(glyph_id).unwrap_or(0) as f32 * scale;
+        glyphs.push((character, glyph_id, x, advance));
+        x += advance + layout.letter_spacing;
+        previous = Some(glyph_id);
+    }
+    glyphs
+}
+
+// sum of the horizontal kerning subtables, in font units
+fn kerning(face: &Face, left: GlyphId, right: GlyphId) -> f32 {
+    face.tables().kern.map_or(0.0, |kern| {
+        kern.subtables
+            .into_iter()
+            .filter(|subtable| subtable.horizontal && !subtable.variable)
+            .filter_map(|subtable| subtable.glyphs_kerning(left, right))
+            .map(|value| value as f32)
+            .sum()
+    })
+}
+
+type Cubic = [Vec2; 4];
+
+#[derive(Default)]
+struct GlyphBuilder {
+    contours: Vec<Vec<Cubic>>,
+    curves: Vec<Cubic>,
+    start: Vec2,
+    point: Vec2,
+}
+
+impl GlyphBuilder {
+    fn cubic(&mut self, control_start: Vec2, control_end: Vec2, p: Vec2) {
+        let a = self.point;
+        let is_degenerate = [control_start, control_end, p]
+            .iter()
+            .all(|q| q.distance(a) < EPSILON);
+
+        if !is_degenerate {
+            self.curves.push([a, control_start, control_end, p]);
+        }
+        self.point = p;
+    }
+
+    // the contour is closed with a line if its last point is not back at the start
+    fn finish(&mut self) {
+        if self.curves.is_empty() {
+            return;
+        }
+        if self.point.distance(self.start) >= EPSILON {
+            self.line_to(self.start.x, self.start.y);
+        }
+        if let Some(last) = self.curves.last_mut() {
+            last[3] = self.start;
+        }
+        self.contours.push(std::mem::take(&mut self.curves));
+    }
+}
+
+impl OutlineBuilder for GlyphBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.finish();
+        self.start = Vec2::new(x, y);
+        self.point = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (a, p) = (self.point, Vec2::new(x, y));
+        self.cubic(a.lerp(p, 1.0 / 3.0), a.lerp(p, 2.0 / 3.0), p);
+    }
+
+    // degree elevation of a quadratic curve
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (a, control, p) = (self.point, Vec2::new(x1, y1), Vec2::new(x, y));
+        self.cubic(
+            a + (control - a) * 2.0 / 3.0,
+            p + (control - p) * 2.0 / 3.0,
+            p,
+        );
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.cubic(Vec2::new(x1, y1), Vec2::new(x2, y2), Vec2::new(x, y));
+    }
+
+    fn close(&mut self) {
+        self.finish();
+    }
+}
+
+/// Reverses the contours that run the wrong way. A contour that lies inside an odd number of
+/// other contours of the glyph is a hole, and runs clockwise.
+fn orient_contours(contours: Vec<Vec<Cubic>>) -> Vec<Vec<Cubic>> {
+    let polygons = contours
+        .iter()
+        .map(|contour| sample_contour(contour))
+        .collect::<Vec<Vec<Vec2>>>();
+
+    contours
+        .into_iter()
+        .enumerate()
+        .map(|(k, contour)| {
+            let depth = polygons
+                .iter()
+                .enumerate()
+                .filter(|(other, polygon)| *other != k && polygon_contains(polygon, polygons[k][0]))
+                .count();
+            let is_hole = depth % 2 == 1;
+            let is_counter_clockwise = signed_area(&polygons[k]) > 0.0;
+
+            if is_hole == is_counter_clockwise {
+                contour
+                    .iter()
+                    .rev()
+                    .map(|[start, control_start, control_end, end]| {
+                        [*end, *control_end, *control_start, *start]
+                    })
+                    .collect()
+            } else {
+                contour
+            }
+        })
+        .collect()
+}
+
+fn sample_contour(contour: &[Cubic]) -> Vec<Vec2> {
+    contour
+        .iter()
+        .flat_map(|[p0, p1, p2, p3]| {
+            (0..SAMPLES_PER_CURVE).map(move |k| {
+                let t = k as f32 / SAMPLES_PER_CURVE as f32;
+                let u = 1.0 - t;
+                *p0 * (u * u * u)
+                    + *p1 * (3.0 * u * u * t)
+                    + *p2 * (3.0 * u * t * t)
+                    + *p3 * (t * t * t)
+            })
+        })
+        .collect()
+}
+
+// positive for counter-clockwise polygons, with y pointing up
+fn signed_area(polygon: &[Vec2]) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .map(|k| polygon[k].perp_dot(polygon[(k + 1) % n]))
+        .sum::<f32>()
+        / 2.0
+}
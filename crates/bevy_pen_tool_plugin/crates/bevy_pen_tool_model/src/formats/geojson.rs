@@ -0,0 +1,420 @@
+//! GeoJSON import and export, for roads and areas taken from map data. Coordinates are
+//! projected into canvas space by a [`GeoJsonProjection`]. On import, LineString features
+//! become open groups and every ring of a Polygon feature becomes a closed group, with the
+//! properties of the feature kept as the metadata of the curves. On export, the look-up table
+//! of every group is written back as a LineString, or as a Polygon when the group is closed.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use serde_json::{json, Map, Value};
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+/// Radius of the sphere used by the geographic projections, in meters.
+const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// How coordinates of a GeoJSON file map to canvas space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoProjection {
+    /// The coordinates are already planar, only the origin and scale are applied.
+    Planar,
+    /// Longitude and latitude are turned into meters around the origin, with the east-west
+    /// distances measured at the latitude of the origin. Accurate over a city.
+    Equirectangular,
+    /// Longitude and latitude are turned into Web Mercator meters, as in web map tiles.
+    WebMercator,
+}
+
+/// Projection and scaling of GeoJSON coordinates, used both on import and export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoJsonProjection {
+    pub projection: GeoProjection,
+    /// Coordinates, in the units of the file, that land on the canvas origin. When `None`,
+    /// imports use the center of the bounding box of the file, and exports use zero.
+    pub origin: Option<DVec2>,
+    /// Canvas units per projected unit, that is per meter for geographic projections.
+    pub scale: f64,
+    /// Largest distance between the vertices of an imported line and the curves fitted
+    /// through them, in canvas units. When `None`, each segment becomes a straight curve.
+    pub fit_max_error: Option<f64>,
+}
+
+impl Default for GeoJsonProjection {
+    fn default() -> Self {
+        Self {
+            projection: GeoProjection::Equirectangular,
+            origin: None,
+            scale: 1.0,
+            fit_max_error: Some(1.0),
+        }
+    }
+}
+
+impl GeoJsonProjection {
+    /// Position on the canvas of a point of the file.
+    pub fn project(&self, point: DVec2) -> Vec2 {
+        let origin = self.origin.unwrap_or(DVec2::ZERO);
+        let projected = match self.projection {
+            GeoProjection::Planar => point - origin,
+            GeoProjection::Equirectangular => {
+                let (lon, lat) = (point.x.to_radians(), point.y.to_radians());
+                let (lon0, lat0) = (origin.x.to_radians(), origin.y.to_radians());
+                DVec2::new((lon - lon0) * lat0.cos(), lat - lat0) * EARTH_RADIUS
+            }
+            GeoProjection::WebMercator => mercator(point) - mercator(origin),
+        };
+        (projected * self.scale).as_vec2()
+    }
+
+    /// Point of the file at a position on the canvas.
+    pub fn unproject(&self, position: Vec2) -> DVec2 {
+        let origin = self.origin.unwrap_or(DVec2::ZERO);
+        let projected = position.as_dvec2() / self.scale;
+        match self.projection {
+            GeoProjection::Planar => projected + origin,
+            GeoProjection::Equirectangular => {
+                let lat0 = origin.y.to_radians();
+                let lon = projected.x / EARTH_RADIUS / lat0.cos();
+                let lat = projected.y / EARTH_RADIUS;
+                origin + DVec2::new(lon.to_degrees(), lat.to_degrees())
+            }
+            GeoProjection::WebMercator => {
+                let meters = projected + mercator(origin);
+                let lon = meters.x / EARTH_RADIUS;
+                let lat = 2.0 * (meters.y / EARTH_RADIUS).exp().atan() - PI / 2.0;
+                DVec2::new(lon.to_degrees(), lat.to_degrees())
+            }
+        }
+    }
+}
+
+fn mercator(point: DVec2) -> DVec2 {
+    let lat = point.y.to_radians().clamp(-1.484_422, 1.484_422);
+    DVec2::new(point.x.to_radians(), (PI / 4.0 + lat / 2.0).tan().ln()) * EARTH_RADIUS
+}
+
+// a line or ring of a feature, in the coordinates of the file
+struct GeoLine {
+    points: Vec<DVec2>,
+    closed: bool,
+    properties: BTreeMap<String, String>,
+    color: Option<Color>,
+}
+
+/// Turns the LineString and Polygon features of a GeoJSON file into groups. Multi-part
+/// geometries and geometry collections give a group per part, and holes of polygons give
+/// closed groups of their own. The `stroke` and `fill` properties, as used by many map
+/// tools, give the color of the curves.
+pub fn geojson_to_groups(
+    contents: &str,
+    projection: &GeoJsonProjection,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let document: Value =
+        serde_json::from_str(contents).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+
+    let mut lines = Vec::new();
+    read_object(&document, &BTreeMap::new(), &mut lines)?;
+    lines.retain(|line| line.points.len() > 1);
+
+    if lines.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+
+    // the file is centered on the canvas unless told otherwise
+    let mut projection = *projection;
+    if projection.origin.is_none() {
+        let (min, max) = lines.iter().flat_map(|line| line.points.iter()).fold(
+            (DVec2::splat(f64::MAX), DVec2::splat(f64::MIN)),
+            |(min, max), point| (min.min(*point), max.max(*point)),
+        );
+        projection.origin = Some((min + max) / 2.0);
+    }
+
+    lines
+        .iter()
+        .filter_map(|line| {
+            let points = line
+                .points
+                .iter()
+                .map(|point| projection.project(*point))
+                .collect::<Vec<Vec2>>();
+
+            let curves = line_curves(&points, line.closed, projection.fit_max_error);
+            if curves.is_empty() {
+                return None;
+            }
+
+            Some(
+                GroupSaveLoad::from_chain(&curves, line.color, num_points).map(|mut group| {
+                    group.set_metadata(&line.properties);
+                    group
+                }),
+            )
+        })
+        .collect()
+}
+
+fn read_object(
+    object: &Value,
+    properties: &BTreeMap<String, String>,
+    lines: &mut Vec<GeoLine>,
+) -> Result<(), ConversionError> {
+    let kind = object
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ConversionError::Syntax("an object has no type".to_string()))?;
+
+    match kind {
+        "FeatureCollection" => {
+            for feature in array(object, "features")? {
+                read_object(feature, properties, lines)?;
+            }
+        }
+        "Feature" => {
+            let mut properties = BTreeMap::new();
+            if let Some(id) = object.get("id") {
+                properties.insert("id".to_string(), property_string(id));
+            }
+            let mut stroke = None;
+            let mut fill = None;
+            if let Some(Value::Object(values)) = object.get("properties") {
+                for (key, value) in values.iter() {
+                    match key.as_str() {
+                        "stroke" => stroke = value.as_str().and_then(hex_color),
+                        "fill" => fill = value.as_str().and_then(hex_color),
+                        _ => (),
+                    }
+                    properties.insert(key.clone(), property_string(value));
+                }
+            }
+
+            // features without geometry are allowed
+            if let Some(geometry) = object.get("geometry").filter(|g| !g.is_null()) {
+                let first = lines.len();
+                read_object(geometry, &properties, lines)?;
+                for line in lines[first..].iter_mut() {
+                    line.color = if line.closed { fill.or(stroke) } else { stroke };
+                }
+            }
+        }
+        "GeometryCollection" => {
+            for geometry in array(object, "geometries")? {
+                read_object(geometry, properties, lines)?;
+            }
+        }
+        "LineString" => {
+            lines.push(line(coordinates(object)?, false, properties)?);
+        }
+        "MultiLineString" => {
+            for part in coordinate_list(object)? {
+                lines.push(line(part, false, properties)?);
+            }
+        }
+        "Polygon" => {
+            for ring in coordinate_list(object)? {
+                lines.push(line(ring, true, properties)?);
+            }
+        }
+        "MultiPolygon" => {
+            for polygon in coordinate_list(object)? {
+                let rings = polygon.as_array().ok_or_else(|| {
+                    ConversionError::Syntax("a polygon is not a list of rings".to_string())
+                })?;
+                for ring in rings {
+                    lines.push(line(ring, true, properties)?);
+                }
+            }
+        }
+        // points have no length
+        "Point" | "MultiPoint" => (),
+        other => {
+            return Err(ConversionError::Unsupported(format!(
+                "GeoJSON type {}",
+                other
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+fn array<'a>(object: &'a Value, key: &str) -> Result<&'a Vec<Value>, ConversionError> {
+    object
+        .get(key)
+        .and_then(Value::as_array)
+        .ok_or_else(|| ConversionError::Syntax(format!("expected a list of {}", key)))
+}
+
+fn coordinates(object: &Value) -> Result<&Value, ConversionError> {
+    object
+        .get("coordinates")
+        .ok_or_else(|| ConversionError::Syntax("a geometry has no coordinates".to_string()))
+}
+
+fn coordinate_list(object: &Value) -> Result<&Vec<Value>, ConversionError> {
+    coordinates(object)?
+        .as_array()
+        .ok_or_else(|| ConversionError::Syntax("expected a list of coordinates".to_string()))
+}
+
+fn line(
+    positions: &Value,
+    closed: bool,
+    properties: &BTreeMap<String, String>,
+) -> Result<GeoLine, ConversionError> {
+    let mut points = positions
+        .as_array()
+        .ok_or_else(|| ConversionError::Syntax("expected a list of positions".to_string()))?
+        .iter()
+        .map(|position| match position.as_array().map(Vec::as_slice) {
+            // the altitude, if any, is dropped
+            Some([x, y, ..]) => match (x.as_f64(), y.as_f64()) {
+                (Some(x), Some(y)) => Ok(DVec2::new(x, y)),
+                _ => Err(ConversionError::Syntax(format!(
+                    "invalid position {}",
+                    position
+                ))),
+            },
+            _ => Err(ConversionError::Syntax(format!(
+                "invalid position {}",
+                position
+            ))),
+        })
+        .collect::<Result<Vec<DVec2>, ConversionError>>()?;
+    points.dedup();
+
+    // the first and last positions of a ring are the same
+    if closed && points.len() > 1 && points[0] == points[points.len() - 1] {
+        points.pop();
+    }
+
+    Ok(GeoLine {
+        points,
+        closed,
+        properties: properties.clone(),
+        color: None,
+    })
+}
+
+fn line_curves(points: &[Vec2], closed: bool, fit_max_error: Option<f64>) -> Vec<BezierPositions> {
+    let mut points = points.to_vec();
+    if closed {
+        points.push(points[0]);
+    }
+
+    match fit_max_error {
+        Some(max_error) => {
+            let lut = StandaloneLut {
+                path_length: 0.0,
+                lut: points,
+            };
+            lut.fit_curves(max_error)
+        }
+        None => points
+            .windows(2)
+            .filter(|pair| pair[0].distance(pair[1]) > ANCHOR_MERGE_DISTANCE)
+            .map(|pair| BezierPositions {
+                start: pair[0],
+                end: pair[1],
+                control_start: pair[0] + (pair[1] - pair[0]) / 3.0,
+                control_end: pair[1] - (pair[1] - pair[0]) / 3.0,
+            })
+            .collect(),
+    }
+}
+
+// strings are kept as they are, other values as json
+fn property_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn hex_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim_start_matches('#');
+    // #rgb is a shorthand for #rrggbb
+    if hex.len() == 3 {
+        let long = hex.chars().flat_map(|c| [c, c]).collect::<String>();
+        return Color::hex(long).ok();
+    }
+    Color::hex(hex).ok()
+}
+
+/// A group written by [`write_geojson`], as the points of its look-up table.
+#[derive(Debug, Clone)]
+pub struct GeoJsonExportPath {
+    pub points: Vec<Vec2>,
+    pub closed: bool,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl GeoJsonExportPath {
+    /// The points of the look-up table of a group, with its metadata as properties.
+    pub fn from_group(group: &GroupSaveLoad) -> Self {
+        Self {
+            points: group.standalone_lut.lut.clone(),
+            closed: group.is_closed(),
+            properties: group.metadata(),
+        }
+    }
+}
+
+/// Writes a FeatureCollection with a LineString for every open path and a Polygon for every
+/// closed one. Property values that read as numbers or booleans are written as such.
+pub fn write_geojson(paths: &[GeoJsonExportPath], projection: &GeoJsonProjection) -> String {
+    let features = paths
+        .iter()
+        .filter(|path| path.points.len() > 1)
+        .map(|path| {
+            let mut positions = path
+                .points
+                .iter()
+                .map(|point| {
+                    let position = projection.unproject(*point);
+                    json!([position.x, position.y])
+                })
+                .collect::<Vec<Value>>();
+
+            let geometry = if path.closed {
+                // rings end where they start
+                if positions.first() != positions.last() {
+                    positions.push(positions[0].clone());
+                }
+                json!({ "type": "Polygon", "coordinates": [positions] })
+            } else {
+                json!({ "type": "LineString", "coordinates": positions })
+            };
+
+            let properties = path
+                .properties
+                .iter()
+                .map(|(key, value)| {
+                    let value = match serde_json::from_str::<Value>(value) {
+                        Ok(typed @ (Value::Number(_) | Value::Bool(_))) => typed,
+                        _ => Value::String(value.clone()),
+                    };
+                    (key.clone(), value)
+                })
+                .collect::<Map<String, Value>>();
+
+            json!({
+                "type": "Feature",
+                "properties": properties,
+                "geometry": geometry,
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    serde_json::to_string_pretty(&json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+    .unwrap()
+}
@@ -0,0 +1,398 @@
+//! glTF 2.0 export of fill and road meshes, either as a `.gltf` file with an embedded buffer or
+//! as a binary `.glb` file. Every mesh becomes a node of the scene, with its own material:
+//! fill meshes keep their color, road meshes embed their texture.
+
+use crate::formats::ConversionError;
+
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+
+use serde_json::{json, Value};
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const REPEAT: u32 = 10497;
+const LINEAR: u32 = 9729;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GltfMaterial {
+    /// Linear RGBA color of a fill mesh. When the mesh has vertex colors, they carry the color
+    /// instead, and the base color factor is white.
+    Color(Vec4),
+    /// Encoded image file (PNG or JPEG) used as the base color of a road mesh.
+    Texture { mime_type: String, bytes: Vec<u8> },
+}
+
+/// A triangle mesh ready to be written as one node of a glTF scene.
+#[derive(Debug, Clone)]
+pub struct GltfMesh {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    /// Vertex colors, written as `COLOR_0` when the source mesh has them.
+    pub colors: Option<Vec<[f32; 4]>>,
+    pub indices: Vec<u32>,
+    pub transform: Transform,
+    pub material: GltfMaterial,
+}
+
+impl GltfMesh {
+    /// Reads the attributes of a triangle list. Missing normals and uvs are filled with
+    /// defaults, so that every primitive has the same layout.
+    pub fn from_mesh(
+        name: &str,
+        mesh: &Mesh,
+        transform: Transform,
+        material: GltfMaterial,
+    ) -> Result<Self, ConversionError> {
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+            Some(VertexAttributeValues::Float32x2(positions)) => {
+                positions.iter().map(|[x, y]| [*x, *y, 0.0]).collect()
+            }
+            Some(_) => {
+                return Err(ConversionError::Unsupported(
+                    "position attribute that is not made of floats".to_string(),
+                ))
+            }
+            None => return Err(ConversionError::Empty),
+        };
+        let num_vertices = positions.len();
+
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => normals.clone(),
+            _ => vec![[0.0, 0.0, 1.0]; num_vertices],
+        };
+
+        let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => uvs.clone(),
+            _ => vec![[0.0, 0.0]; num_vertices],
+        };
+
+        let colors = match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => Some(colors.clone()),
+            Some(VertexAttributeValues::Float32x3(colors)) => Some(
+                colors
+                    .iter()
+                    .map(|[r, g, b]| [*r, *g, *b, 1.0])
+                    .collect::<Vec<[f32; 4]>>(),
+            ),
+            Some(_) => {
+                return Err(ConversionError::Unsupported(
+                    "color attribute that is not made of floats".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        let indices = match mesh.indices() {
+            Some(indices) => indices.iter().map(|i| i as u32).collect(),
+            None => (0..num_vertices as u32).collect(),
+        };
+
+        if num_vertices == 0 {
+            return Err(ConversionError::Empty);
+        }
+
+        if normals.len() != num_vertices
+            || uvs.len() != num_vertices
+            || colors
+                .as_ref()
+                .map_or(false, |colors| colors.len() != num_vertices)
+        {
+            return Err(ConversionError::Syntax(format!(
+                "{} has attributes of different lengths",
+                name
+            )));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            positions,
+            normals,
+            uvs,
+            colors,
+            indices,
+            transform,
+            material,
+        })
+    }
+}
+
+/// Writes a `.gltf` file. The binary buffer is embedded as a base64 data uri.
+pub fn write_gltf(meshes: &[GltfMesh]) -> Result<String, ConversionError> {
+    let (mut document, buffer) = build_document(meshes)?;
+    document["buffers"] = json!([{
+        "byteLength": buffer.len(),
+        "uri": format!("data:application/octet-stream;base64,{}", base64::encode(&buffer)),
+    }]);
+
+    serde_json::to_string_pretty(&document).map_err(|e| ConversionError::Syntax(e.to_string()))
+}
+
+/// Writes a binary `.glb` file: a json chunk followed by the binary buffer.
+pub fn write_glb(meshes: &[GltfMesh]) -> Result<Vec<u8>, ConversionError> {
+    let (mut document, mut buffer) = build_document(meshes)?;
+    document["buffers"] = json!([{ "byteLength": buffer.len() }]);
+
+    let mut json_chunk =
+        serde_json::to_vec(&document).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+    // chunks are 4-byte aligned, json with spaces and the binary chunk with zeros
+    pad(&mut json_chunk, b' ');
+    pad(&mut buffer, 0);
+
+    let total_length = 12 + 8 + json_chunk.len() + 8 + buffer.len();
+    let mut glb = Vec::with_capacity(total_length);
+
+    glb.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(total_length as u32).to_le_bytes());
+
+    glb.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_JSON_CHUNK.to_le_bytes());
+    glb.extend_from_slice(&json_chunk);
+
+    glb.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_BIN_CHUNK.to_le_bytes());
+    glb.extend_from_slice(&buffer);
+
+    Ok(glb)
+}
+
+// everything but the "buffers" entry, which depends on the container
+fn build_document(meshes: &[GltfMesh]) -> Result<(Value, Vec<u8>), ConversionError> {
+    if meshes.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+
+    let mut builder = DocumentBuilder::default();
+    for mesh in meshes {
+        builder.add_mesh(mesh);
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "bevy_pen_tool" },
+        "extensionsUsed": ["KHR_materials_unlit"],
+        "scene": 0,
+        "scenes": [{ "nodes": (0..meshes.len()).collect::<Vec<usize>>() }],
+        "nodes": builder.nodes,
+        "meshes": builder.meshes,
+        "materials": builder.materials,
+        "textures": builder.textures,
+        "images": builder.images,
+        "samplers": [{
+            "magFilter": LINEAR,
+            "minFilter": LINEAR,
+            "wrapS": REPEAT,
+            "wrapT": REPEAT,
+        }],
+        "accessors": builder.accessors,
+        "bufferViews": builder.buffer_views,
+    });
+
+    // the spec does not allow empty arrays, as for fill meshes without textures
+    if let Some(object) = document.as_object_mut() {
+        let empty = object
+            .iter()
+            .filter(|(_, value)| value.as_array().map_or(false, |array| array.is_empty()))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<String>>();
+        for key in empty {
+            object.remove(&key);
+        }
+        if !object.contains_key("textures") {
+            object.remove("samplers");
+        }
+    }
+
+    Ok((document, builder.buffer))
+}
+
+#[derive(Default)]
+struct DocumentBuilder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    textures: Vec<Value>,
+    materials: Vec<Value>,
+    meshes: Vec<Value>,
+    nodes: Vec<Value>,
+}
+
+impl DocumentBuilder {
+    fn add_mesh(&mut self, mesh: &GltfMesh) {
+        let (min, max) = mesh.positions.iter().fold(
+            ([f32::MAX; 3], [f32::MIN; 3]),
+            |(mut min, mut max), position| {
+                for k in 0..3 {
+                    min[k] = min[k].min(position[k]);
+                    max[k] = max[k].max(position[k]);
+                }
+                (min, max)
+            },
+        );
+
+        let positions = self.add_accessor(
+            &mesh.positions.concat(),
+            "VEC3",
+            mesh.positions.len(),
+            Some((&min, &max)),
+        );
+        let normals = self.add_accessor(&mesh.normals.concat(), "VEC3", mesh.normals.len(), None);
+        let uvs = self.add_accessor(&mesh.uvs.concat(), "VEC2", mesh.uvs.len(), None);
+        let mut attributes = json!({
+            "POSITION": positions,
+            "NORMAL": normals,
+            "TEXCOORD_0": uvs,
+        });
+        if let Some(colors) = &mesh.colors {
+            attributes["COLOR_0"] =
+                json!(self.add_accessor(&colors.concat(), "VEC4", colors.len(), None));
+        }
+        let indices = self.add_indices(&mesh.indices);
+        let material = self.add_material(&mesh.name, &mesh.material, mesh.colors.is_some());
+
+        self.meshes.push(json!({
+            "name": mesh.name,
+            "primitives": [{
+                "attributes": attributes,
+                "indices": indices,
+                "material": material,
+            }],
+        }));
+
+        let Transform {
+            translation,
+            rotation,
+            scale,
+        } = mesh.transform;
+        self.nodes.push(json!({
+            "name": mesh.name,
+            "mesh": self.meshes.len() - 1,
+            "translation": translation.to_array(),
+            "rotation": rotation.to_array(),
+            "scale": scale.to_array(),
+        }));
+    }
+
+    fn add_buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        // accessors of floats and u32s need 4-byte aligned offsets
+        pad(&mut self.buffer, 0);
+
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn add_accessor(
+        &mut self,
+        values: &[f32],
+        kind: &str,
+        count: usize,
+        bounds: Option<(&[f32; 3], &[f32; 3])>,
+    ) -> usize {
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let view = self.add_buffer_view(&bytes, Some(ARRAY_BUFFER));
+
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": count,
+            "type": kind,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let view = self.add_buffer_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    // viewers multiply the vertex colors by the base color factor, so it is white when the
+    // vertex colors already hold the color of a fill mesh
+    fn add_material(&mut self, name: &str, material: &GltfMaterial, vertex_colors: bool) -> usize {
+        let pbr = match material {
+            GltfMaterial::Color(color) => json!({
+                "baseColorFactor": if vertex_colors { [1.0; 4] } else { color.to_array() },
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            }),
+            GltfMaterial::Texture { mime_type, bytes } => {
+                let view = self.add_buffer_view(bytes, None);
+                self.images
+                    .push(json!({ "bufferView": view, "mimeType": mime_type }));
+                self.textures.push(json!({
+                    "source": self.images.len() - 1,
+                    "sampler": 0,
+                }));
+                json!({
+                    "baseColorTexture": { "index": self.textures.len() - 1 },
+                    "metallicFactor": 0.0,
+                    "roughnessFactor": 1.0,
+                })
+            }
+        };
+
+        // the meshes are flat and lit by nothing in the editor
+        self.materials.push(json!({
+            "name": name,
+            "pbrMetallicRoughness": pbr,
+            "doubleSided": true,
+            "alphaMode": "BLEND",
+            "extensions": { "KHR_materials_unlit": {} },
+        }));
+        self.materials.len() - 1
+    }
+}
+
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while bytes.len() % 4 != 0 {
+        bytes.push(with);
+    }
+}
+
+/// Mime type of an image file used as a glTF texture, from its extension.
+pub fn image_mime_type(extension: &str) -> Option<&'static str> {
+    match extension.to_lowercase().as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        _ => None,
+    }
+}
@@ -0,0 +1,640 @@
+//! Lottie import and export. On import, the paths of the shape layers become chains of latched
+//! curves, with the static transforms of their layers and shape groups applied. A vertex of a
+//! Lottie path holds its in and out tangents relative to its position, so each pair of
+//! consecutive vertices is one cubic curve. On export, every group becomes a shape layer, and
+//! an optional follower layer moves along its look-up table.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::math::Affine2;
+use bevy::prelude::*;
+
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+
+/// Version of the Lottie format written on export.
+const LOTTIE_VERSION: &str = "5.7.4";
+/// Width of the exported strokes, in canvas units.
+const STROKE_WIDTH: f32 = 2.0;
+/// Space left around the exported paths.
+const MARGIN: f32 = 10.0;
+/// Diameter of the dot that follows the path of an animated group.
+const FOLLOWER_SIZE: f32 = 10.0;
+/// Precompositions that nest deeper than this are ignored, in case they reference each other.
+const MAX_DEPTH: usize = 16;
+
+/// Turns the paths of every shape layer of a Lottie file into groups. The fill color of a
+/// path becomes the color of its curves, or the stroke color if the path is not filled.
+/// Animated properties are read at their first keyframe.
+pub fn lottie_to_groups(
+    contents: &str,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let document: Value =
+        serde_json::from_str(contents).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+
+    let layers = document
+        .get("layers")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ConversionError::Syntax("the file has no layers".to_string()))?;
+
+    let assets = document
+        .get("assets")
+        .and_then(Value::as_array)
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| {
+                    Some((asset.get("id")?.as_str()?, asset.get("layers")?.as_array()?))
+                })
+                .collect::<HashMap<&str, &Vec<Value>>>()
+        })
+        .unwrap_or_default();
+
+    let mut paths = Vec::new();
+    read_layers(layers, Affine2::IDENTITY, &assets, 0, &mut paths)?;
+
+    let groups = paths
+        .iter()
+        .filter(|path| !path.curves.is_empty())
+        .map(|path| GroupSaveLoad::from_chain(&path.curves, path.color, num_points))
+        .collect::<Result<Vec<GroupSaveLoad>, ConversionError>>()?;
+
+    if groups.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    Ok(groups)
+}
+
+/// A path read from a shape layer, in canvas coordinates.
+struct LottiePath {
+    curves: Vec<BezierPositions>,
+    color: Option<Color>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Paint {
+    fill: Option<Color>,
+    stroke: Option<Color>,
+}
+
+fn read_layers(
+    layers: &[Value],
+    parent_transform: Affine2,
+    assets: &HashMap<&str, &Vec<Value>>,
+    depth: usize,
+    paths: &mut Vec<LottiePath>,
+) -> Result<(), ConversionError> {
+    if depth > MAX_DEPTH {
+        return Ok(());
+    }
+
+    // layers are moved along with the layer whose index is their "parent"
+    let by_index = layers
+        .iter()
+        .filter_map(|layer| Some((layer.get("ind")?.as_i64()?, layer)))
+        .collect::<HashMap<i64, &Value>>();
+
+    for layer in layers.iter() {
+        if is_hidden(layer) {
+            continue;
+        }
+
+        let mut transform = layer.get("ks").map_or(Affine2::IDENTITY, read_transform);
+        let mut parent = layer.get("parent").and_then(Value::as_i64);
+        let mut num_parents = 0;
+        while let Some(parent_layer) = parent.and_then(|index| by_index.get(&index)) {
+            num_parents += 1;
+            if num_parents > MAX_DEPTH {
+                break;
+            }
+            transform = parent_layer
+                .get("ks")
+                .map_or(Affine2::IDENTITY, read_transform)
+                * transform;
+            parent = parent_layer.get("parent").and_then(Value::as_i64);
+        }
+        let transform = parent_transform * transform;
+
+        match layer.get("ty").and_then(Value::as_i64) {
+            // shape layer
+            Some(4) => {
+                if let Some(shapes) = layer.get("shapes").and_then(Value::as_array) {
+                    read_shapes(shapes, transform, Paint::default(), paths)?;
+                }
+            }
+            // precomposition
+            Some(0) => {
+                if let Some(layers) = layer
+                    .get("refId")
+                    .and_then(Value::as_str)
+                    .and_then(|id| assets.get(id))
+                {
+                    read_layers(layers, transform, assets, depth + 1, paths)?;
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+// the paint of a shape group applies to all its paths, including the ones of nested groups
+fn read_shapes(
+    items: &[Value],
+    parent_transform: Affine2,
+    parent_paint: Paint,
+    paths: &mut Vec<LottiePath>,
+) -> Result<(), ConversionError> {
+    let mut paint = parent_paint;
+    for item in items.iter().filter(|item| !is_hidden(item)) {
+        match item.get("ty").and_then(Value::as_str) {
+            Some("fl") if paint.fill == parent_paint.fill => paint.fill = read_color(item),
+            Some("st") if paint.stroke == parent_paint.stroke => paint.stroke = read_color(item),
+            _ => (),
+        }
+    }
+
+    let transform = items
+        .iter()
+        .find(|item| item.get("ty").and_then(Value::as_str) == Some("tr"))
+        .map_or(parent_transform, |item| {
+            parent_transform * read_transform(item)
+        });
+
+    for item in items.iter().filter(|item| !is_hidden(item)) {
+        match item.get("ty").and_then(Value::as_str) {
+            Some("gr") => {
+                if let Some(items) = item.get("it").and_then(Value::as_array) {
+                    read_shapes(items, transform, paint, paths)?;
+                }
+            }
+            Some("sh") => {
+                let shape = item
+                    .get("ks")
+                    .and_then(static_value)
+                    .ok_or_else(|| ConversionError::Syntax("a path has no vertices".to_string()))?;
+                let closed = shape
+                    .get("c")
+                    .or_else(|| item.get("closed"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+
+                paths.push(LottiePath {
+                    curves: read_path(shape, closed, transform)?,
+                    color: paint.fill.or(paint.stroke),
+                });
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn read_path(
+    shape: &Value,
+    closed: bool,
+    transform: Affine2,
+) -> Result<Vec<BezierPositions>, ConversionError> {
+    let points = |name: &str| -> Result<Vec<Vec2>, ConversionError> {
+        shape
+            .get(name)
+            .and_then(Value::as_array)
+            .ok_or_else(|| ConversionError::Syntax(format!("a path has no \"{}\" array", name)))?
+            .iter()
+            .map(|point| {
+                read_vec2(point).ok_or_else(|| {
+                    ConversionError::Syntax(format!("invalid point in \"{}\"", name))
+                })
+            })
+            .collect()
+    };
+
+    let vertices = points("v")?;
+    let in_tangents = points("i")?;
+    let out_tangents = points("o")?;
+    if in_tangents.len() != vertices.len() || out_tangents.len() != vertices.len() {
+        return Err(ConversionError::Syntax(
+            "a path does not have as many tangents as vertices".to_string(),
+        ));
+    }
+
+    let num_vertices = vertices.len();
+    let num_curves = if closed {
+        num_vertices
+    } else {
+        num_vertices.saturating_sub(1)
+    };
+
+    let to_canvas = |p: Vec2| {
+        let p = transform.transform_point2(p);
+        Vec2::new(p.x, -p.y)
+    };
+
+    Ok((0..num_curves)
+        .map(|k| {
+            let next = (k + 1) % num_vertices;
+            BezierPositions {
+                start: to_canvas(vertices[k]),
+                control_start: to_canvas(vertices[k] + out_tangents[k]),
+                control_end: to_canvas(vertices[next] + in_tangents[next]),
+                end: to_canvas(vertices[next]),
+            }
+        })
+        // a closed path may repeat its first vertex at the end
+        .filter(|curve| {
+            curve.start != curve.end
+                || curve.control_start != curve.start
+                || curve.control_end != curve.end
+        })
+        .collect())
+}
+
+// anchor, position, scale and rotation, applied in the order used by Lottie players
+fn read_transform(transform: &Value) -> Affine2 {
+    let vec2 = |name: &str, default: Vec2| {
+        transform
+            .get(name)
+            .and_then(|property| {
+                // positions can be split into separate x and y properties
+                if property.get("s").and_then(Value::as_bool) == Some(true) {
+                    let x = property.get("x").and_then(static_value)?.as_f64()?;
+                    let y = property.get("y").and_then(static_value)?.as_f64()?;
+                    Some(Vec2::new(x as f32, y as f32))
+                } else {
+                    read_vec2(static_value(property)?)
+                }
+            })
+            .unwrap_or(default)
+    };
+
+    let anchor = vec2("a", Vec2::ZERO);
+    let position = vec2("p", Vec2::ZERO);
+    let scale = vec2("s", Vec2::splat(100.0)) / 100.0;
+    let rotation = transform
+        .get("r")
+        .and_then(static_value)
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0) as f32;
+
+    Affine2::from_translation(position)
+        * Affine2::from_angle(rotation.to_radians())
+        * Affine2::from_scale(scale)
+        * Affine2::from_translation(-anchor)
+}
+
+// the value of a static property, or the start value of the first keyframe of an animated one
+fn static_value(property: &Value) -> Option<&Value> {
+    let value = property.get("k")?;
+    let is_animated = property.get("a").and_then(Value::as_i64) == Some(1);
+
+    if is_animated {
+        let start = value.as_array()?.first()?.get("s")?;
+        // shapes are wrapped in an array of one element in keyframes
+        match start.as_array() {
+            Some(shapes) if shapes.first().map_or(false, Value::is_object) => shapes.first(),
+            _ => Some(start),
+        }
+    } else {
+        Some(value)
+    }
+}
+
+fn read_vec2(value: &Value) -> Option<Vec2> {
+    let values = value.as_array()?;
+    let x = values.get(0)?.as_f64()?;
+    let y = values.get(1)?.as_f64()?;
+    Some(Vec2::new(x as f32, y as f32))
+}
+
+// channels are between 0 and 1, except in files written by old exporters
+fn read_color(item: &Value) -> Option<Color> {
+    let channels = item
+        .get("c")
+        .and_then(static_value)?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_f64)
+        .map(|c| c as f32)
+        .collect::<Vec<f32>>();
+    if channels.len() < 3 {
+        return None;
+    }
+
+    let scale = if channels.iter().any(|c| *c > 1.0) {
+        255.0
+    } else {
+        1.0
+    };
+    let opacity = item
+        .get("o")
+        .and_then(static_value)
+        .and_then(Value::as_f64)
+        .map_or(1.0, |o| o as f32 / 100.0);
+
+    Some(Color::rgba(
+        channels[0] / scale,
+        channels[1] / scale,
+        channels[2] / scale,
+        channels.get(3).map_or(1.0, |a| a / scale) * opacity,
+    ))
+}
+
+fn is_hidden(item: &Value) -> bool {
+    item.get("hd").and_then(Value::as_bool) == Some(true)
+}
+
+/// Timing of the follower layers written by [`write_lottie`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LottieAnimation {
+    /// Time taken to go once along each path, in seconds.
+    pub duration: f32,
+    pub frame_rate: f32,
+}
+
+impl Default for LottieAnimation {
+    fn default() -> Self {
+        Self {
+            duration: 3.0,
+            frame_rate: 60.0,
+        }
+    }
+}
+
+/// A shape layer written by [`write_lottie`], in canvas coordinates.
+#[derive(Debug, Clone)]
+pub struct LottieExportPath {
+    pub curves: Vec<BezierPositions>,
+    pub closed: bool,
+    pub stroke: Option<Color>,
+    pub fill: Option<Color>,
+    /// Points followed by the animated layer, evenly spaced along the path.
+    pub lut: Vec<Vec2>,
+}
+
+impl LottieExportPath {
+    /// The curves of a group in their direction of travel, stroked with the curve color.
+    pub fn from_group(group: &GroupSaveLoad, fill: Option<Color>) -> Self {
+        Self {
+            curves: group.path_curves(),
+            closed: group.is_closed(),
+            stroke: group.lut.first().and_then(|(bezier, _, _, _)| bezier.color),
+            fill,
+            lut: group.standalone_lut.lut.clone(),
+        }
+    }
+}
+
+/// Writes a Lottie file with one shape layer per path. With an `animation`, each path also
+/// gets a layer holding a dot that moves along it at constant speed, from keyframes placed on
+/// the points of its look-up table.
+pub fn write_lottie(paths: &[LottieExportPath], animation: Option<LottieAnimation>) -> String {
+    let bounds = paths
+        .iter()
+        .flat_map(|path| path.curves.iter())
+        .flat_map(|curve| {
+            [
+                curve.start,
+                curve.control_start,
+                curve.control_end,
+                curve.end,
+            ]
+        })
+        .fold(None, |bounds: Option<(Vec2, Vec2)>, p| match bounds {
+            Some((min, max)) => Some((min.min(p), max.max(p))),
+            None => Some((p, p)),
+        });
+    let (min, max) = bounds.unwrap_or((Vec2::ZERO, Vec2::ZERO));
+
+    // the y axis of Lottie points down, with the origin at the top left corner
+    let origin = Vec2::new(min.x - MARGIN, max.y + MARGIN);
+    let size = max - min + Vec2::splat(2.0 * MARGIN);
+    let to_lottie = |p: Vec2| Vec2::new(p.x - origin.x, origin.y - p.y);
+
+    let has_followers = animation.is_some();
+    let animation = animation.unwrap_or_default();
+    let frame_rate = animation.frame_rate.max(1.0);
+    let num_frames = (animation.duration * frame_rate).round().max(1.0);
+
+    let mut layers = Vec::new();
+    let mut index = 1;
+
+    // the first layers are drawn on top, so the followers come first
+    for (k, path) in paths.iter().enumerate() {
+        if !has_followers || path.lut.len() < 2 {
+            continue;
+        }
+        let last = (path.lut.len() - 1) as f32;
+        let keyframes = path
+            .lut
+            .iter()
+            .enumerate()
+            .map(|(j, point)| {
+                let p = to_lottie(*point);
+                json!({
+                    "t": number(j as f32 / last * num_frames),
+                    "s": [number(p.x), number(p.y), 0],
+                    "i": { "x": 1, "y": 1 },
+                    "o": { "x": 0, "y": 0 },
+                })
+            })
+            .collect::<Vec<Value>>();
+
+        let color = path.stroke.or(path.fill).unwrap_or(Color::BLACK);
+        let dot = json!({
+            "ty": "gr",
+            "nm": "dot",
+            "it": [
+                {
+                    "ty": "el",
+                    "p": static_property(json!([0, 0])),
+                    "s": static_property(json!([FOLLOWER_SIZE, FOLLOWER_SIZE])),
+                },
+                fill_item(color),
+                transform_item(),
+            ],
+        });
+
+        layers.push(layer(
+            index,
+            &format!("follower{}", k),
+            num_frames,
+            json!({ "a": 1, "k": keyframes }),
+            vec![dot],
+        ));
+        index += 1;
+    }
+
+    for (k, path) in paths.iter().enumerate() {
+        let mut items = path_items(path, &to_lottie);
+        if let Some(fill) = path.fill {
+            items.push(fill_item(fill));
+        }
+        if let Some(stroke) = path.stroke {
+            items.push(json!({
+                "ty": "st",
+                "c": static_property(json!(color_channels(stroke))),
+                "o": static_property(json!(100)),
+                "w": static_property(json!(STROKE_WIDTH)),
+                "lc": 2,
+                "lj": 2,
+            }));
+        }
+        items.push(transform_item());
+
+        let shape = json!({ "ty": "gr", "nm": format!("group{}", k), "it": items });
+        layers.push(layer(
+            index,
+            &format!("group{}", k),
+            num_frames,
+            static_property(json!([0, 0, 0])),
+            vec![shape],
+        ));
+        index += 1;
+    }
+
+    json!({
+        "v": LOTTIE_VERSION,
+        "fr": number(frame_rate),
+        "ip": 0,
+        "op": number(num_frames),
+        "w": size.x.ceil() as i64,
+        "h": size.y.ceil() as i64,
+        "nm": "bevy_pen_tool",
+        "ddd": 0,
+        "assets": [],
+        "layers": layers,
+    })
+    .to_string()
+}
+
+// one "sh" item per run of touching curves
+fn path_items(path: &LottieExportPath, to_lottie: &impl Fn(Vec2) -> Vec2) -> Vec<Value> {
+    let mut subpaths: Vec<Vec<BezierPositions>> = Vec::new();
+    for curve in path.curves.iter() {
+        match subpaths.last_mut() {
+            Some(subpath)
+                if subpath.last().map_or(false, |previous| {
+                    previous.end.distance(curve.start) <= ANCHOR_MERGE_DISTANCE
+                }) =>
+            {
+                subpath.push(*curve)
+            }
+            _ => subpaths.push(vec![*curve]),
+        }
+    }
+
+    let num_subpaths = subpaths.len();
+    subpaths
+        .iter()
+        .map(|curves| {
+            let first = curves[0];
+            let last = curves[curves.len() - 1];
+            let closed = path.closed
+                && num_subpaths == 1
+                && last.end.distance(first.start) <= ANCHOR_MERGE_DISTANCE;
+
+            // tangents are relative to their vertex
+            let mut vertices = vec![first.start];
+            let mut in_tangents = vec![Vec2::ZERO];
+            let mut out_tangents = Vec::new();
+            for curve in curves.iter() {
+                out_tangents.push(curve.control_start - curve.start);
+                vertices.push(curve.end);
+                in_tangents.push(curve.control_end - curve.end);
+            }
+            out_tangents.push(Vec2::ZERO);
+
+            // the last vertex of a closed path is the first one
+            if closed {
+                vertices.pop();
+                out_tangents.pop();
+                in_tangents[0] = in_tangents.pop().unwrap_or(Vec2::ZERO);
+            }
+
+            let points = |points: &[Vec2]| {
+                points
+                    .iter()
+                    .map(|p| json!([number(p.x), number(p.y)]))
+                    .collect::<Vec<Value>>()
+            };
+            // tangents are flipped with the y axis, without the translation
+            let tangents = |tangents: &[Vec2]| {
+                points(
+                    &tangents
+                        .iter()
+                        .map(|t| Vec2::new(t.x, -t.y))
+                        .collect::<Vec<Vec2>>(),
+                )
+            };
+
+            json!({
+                "ty": "sh",
+                "ks": static_property(json!({
+                    "i": tangents(&in_tangents),
+                    "o": tangents(&out_tangents),
+                    "v": points(&vertices.iter().map(|p| to_lottie(*p)).collect::<Vec<Vec2>>()),
+                    "c": closed,
+                })),
+            })
+        })
+        .collect()
+}
+
+fn layer(index: usize, name: &str, num_frames: f32, position: Value, shapes: Vec<Value>) -> Value {
+    json!({
+        "ddd": 0,
+        "ind": index,
+        "ty": 4,
+        "nm": name,
+        "sr": 1,
+        "ks": {
+            "o": static_property(json!(100)),
+            "r": static_property(json!(0)),
+            "p": position,
+            "a": static_property(json!([0, 0, 0])),
+            "s": static_property(json!([100, 100, 100])),
+        },
+        "ao": 0,
+        "shapes": shapes,
+        "ip": 0,
+        "op": number(num_frames),
+        "st": 0,
+        "bm": 0,
+    })
+}
+
+fn fill_item(color: Color) -> Value {
+    json!({
+        "ty": "fl",
+        "c": static_property(json!(color_channels(color))),
+        "o": static_property(json!(100)),
+        "r": 1,
+    })
+}
+
+fn transform_item() -> Value {
+    json!({
+        "ty": "tr",
+        "p": static_property(json!([0, 0])),
+        "a": static_property(json!([0, 0])),
+        "s": static_property(json!([100, 100])),
+        "r": static_property(json!(0)),
+        "o": static_property(json!(100)),
+    })
+}
+
+fn static_property(value: Value) -> Value {
+    json!({ "a": 0, "k": value })
+}
+
+fn color_channels(color: Color) -> [f32; 4] {
+    color.as_rgba_f32()
+}
+
+// keeps the file short, Lottie players do not need more precision
+fn number(value: f32) -> f64 {
+    (value as f64 * 1000.0).round() / 1000.0
+}
@@ -0,0 +1,50 @@
+//! Conversions between groups of curves and file formats used by other tools.
+
+mod dxf;
+mod font;
+mod geojson;
+mod gltf;
+mod lottie;
+mod obj;
+mod scene;
+mod svg;
+mod tiled;
+mod trace;
+
+pub use dxf::*;
+pub use font::*;
+pub use geojson::*;
+pub use gltf::*;
+pub use lottie::*;
+pub use obj::*;
+pub use scene::*;
+pub use svg::*;
+pub use tiled::*;
+pub use trace::*;
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ConversionError {
+    /// The file could not be parsed.
+    Syntax(String),
+    /// The file is valid, but uses a feature that cannot be converted.
+    Unsupported(String),
+    /// The file holds nothing that can be turned into curves.
+    Empty,
+    /// A chain of curves has no length, or its look-up table would have fewer than two points.
+    Degenerate(String),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(reason) => write!(f, "syntax error: {}", reason),
+            Self::Unsupported(feature) => write!(f, "unsupported feature: {}", feature),
+            Self::Empty => write!(f, "the file holds no curves"),
+            Self::Degenerate(reason) => write!(f, "degenerate chain: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
@@ -0,0 +1,460 @@
+//! Wavefront `.obj` meshes and their `.mtl` material libraries. Faces reference per-vertex
+//! uvs and normals, and the material holds the color of a fill mesh or the texture of a road.
+//! The v axis of the uvs points up in `.obj` files, so it is flipped both ways.
+
+use crate::formats::ConversionError;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    /// Diffuse color, `Kd` and `d` in the `.mtl` file.
+    pub color: Color,
+    /// Diffuse texture, `map_Kd` in the `.mtl` file, relative to the `.mtl` file.
+    pub texture: Option<String>,
+}
+
+/// A mesh read from an `.obj` file, along with the material it refers to.
+#[derive(Debug, Clone)]
+pub struct ObjFile {
+    pub mesh: Mesh,
+    pub material_library: Option<String>,
+    pub material_name: Option<String>,
+}
+
+/// Writes a triangle list as an `.obj` file that uses `material` from `material_library`.
+/// Meshes without normals or uvs get a normal facing the camera and zero uvs.
+pub fn write_obj(
+    mesh: &Mesh,
+    name: &str,
+    material_library: &str,
+    material: &ObjMaterial,
+) -> Result<Vec<u8>, ConversionError> {
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+        Some(VertexAttributeValues::Float32x2(positions)) => {
+            positions.iter().map(|[x, y]| [*x, *y, 0.0]).collect()
+        }
+        Some(_) => {
+            return Err(ConversionError::Unsupported(
+                "position attribute that is not made of floats".to_string(),
+            ))
+        }
+        None => return Err(ConversionError::Empty),
+    };
+    let num_vertices = positions.len();
+
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) if normals.len() == num_vertices => {
+            normals.clone()
+        }
+        Some(VertexAttributeValues::Float32x3(_)) => {
+            return Err(ConversionError::Syntax(
+                "there are not as many normals as vertices".to_string(),
+            ))
+        }
+        Some(_) => {
+            return Err(ConversionError::Unsupported(
+                "normal attribute that is not made of three floats".to_string(),
+            ))
+        }
+        None => vec![[0.0, 0.0, 1.0]; num_vertices],
+    };
+
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) if uvs.len() == num_vertices => uvs.clone(),
+        Some(VertexAttributeValues::Float32x2(_)) => {
+            return Err(ConversionError::Syntax(
+                "there are not as many uvs as vertices".to_string(),
+            ))
+        }
+        Some(_) => {
+            return Err(ConversionError::Unsupported(
+                "uv attribute that is not made of two floats".to_string(),
+            ))
+        }
+        None => vec![[0.0, 0.0]; num_vertices],
+    };
+
+    let indices = match mesh.indices() {
+        Some(indices) => indices.iter().collect::<Vec<usize>>(),
+        None => (0..num_vertices).collect(),
+    };
+
+    if indices.len() % 3 != 0 || indices.iter().any(|i| *i >= num_vertices) {
+        return Err(ConversionError::Syntax(
+            "the indices do not form a triangle list".to_string(),
+        ));
+    }
+
+    // uvs and normals are stored once per vertex, so every face corner uses the same index
+    // for all three
+    let set = obj_exporter::ObjSet {
+        material_library: Some(material_library.to_string()),
+        objects: vec![obj_exporter::Object {
+            name: name.to_string(),
+            vertices: positions
+                .iter()
+                .map(|[x, y, z]| obj_exporter::Vertex {
+                    x: *x as f64,
+                    y: *y as f64,
+                    z: *z as f64,
+                })
+                .collect(),
+            tex_vertices: uvs
+                .iter()
+                .map(|[u, v]| obj_exporter::TVertex {
+                    u: *u as f64,
+                    v: 1.0 - *v as f64,
+                    w: 0.0,
+                })
+                .collect(),
+            normals: normals
+                .iter()
+                .map(|[x, y, z]| obj_exporter::Vertex {
+                    x: *x as f64,
+                    y: *y as f64,
+                    z: *z as f64,
+                })
+                .collect(),
+            geometry: vec![obj_exporter::Geometry {
+                material_name: Some(material.name.clone()),
+                shapes: indices
+                    .chunks_exact(3)
+                    .map(|triangle| obj_exporter::Shape {
+                        primitive: obj_exporter::Primitive::Triangle(
+                            (triangle[0], Some(triangle[0]), Some(triangle[0])),
+                            (triangle[1], Some(triangle[1]), Some(triangle[1])),
+                            (triangle[2], Some(triangle[2]), Some(triangle[2])),
+                        ),
+                        groups: vec![],
+                        smoothing_groups: vec![],
+                    })
+                    .collect(),
+            }],
+        }],
+    };
+
+    let mut bytes = Vec::new();
+    obj_exporter::export(&set, &mut bytes).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+    Ok(bytes)
+}
+
+pub fn write_mtl(material: &ObjMaterial) -> String {
+    let [r, g, b, a] = material.color.as_rgba_f32();
+
+    let mut mtl = String::new();
+    writeln!(mtl, "newmtl {}", material.name).unwrap();
+    writeln!(mtl, "Ka 0.000000 0.000000 0.000000").unwrap();
+    writeln!(mtl, "Kd {:.6} {:.6} {:.6}", r, g, b).unwrap();
+    writeln!(mtl, "Ks 0.000000 0.000000 0.000000").unwrap();
+    writeln!(mtl, "d {:.6}", a).unwrap();
+    writeln!(mtl, "illum 1").unwrap();
+    if let Some(texture) = &material.texture {
+        writeln!(mtl, "map_Kd {}", texture).unwrap();
+    }
+    mtl
+}
+
+/// Reads every material of an `.mtl` file. Unknown statements are ignored.
+pub fn parse_mtl(contents: &str) -> Result<HashMap<String, ObjMaterial>, ConversionError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<ObjMaterial> = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if keyword == "newmtl" {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            current = Some(ObjMaterial {
+                name: rest.to_string(),
+                color: Color::WHITE,
+                texture: None,
+            });
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some(material) => material,
+            None => continue,
+        };
+
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = parse_floats::<3>(rest)?;
+                material.color = Color::rgba(r, g, b, material.color.a());
+            }
+            "d" => {
+                material.color.set_a(parse_floats::<1>(rest)?[0]);
+            }
+            // transparency is the opposite of the dissolve factor
+            "Tr" => {
+                material.color.set_a(1.0 - parse_floats::<1>(rest)?[0]);
+            }
+            // options such as "-s 1 1 1" come before the file name
+            "map_Kd" => {
+                material.texture = rest.split_whitespace().last().map(|name| name.to_string())
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(material) = current.take() {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+/// Reads the vertices and faces of an `.obj` file into a triangle list. Polygons are split
+/// into fans of triangles, and all the objects of the file are merged into one mesh.
+pub fn parse_obj(contents: &str) -> Result<ObjFile, ConversionError> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+
+    let mut material_library = None;
+    let mut material_name = None;
+
+    // a vertex of the mesh for each different position, uv and normal triple
+    let mut corners: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+    let mut mesh_positions: Vec<[f32; 3]> = Vec::new();
+    let mut mesh_uvs: Vec<[f32; 2]> = Vec::new();
+    let mut mesh_normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let syntax_error = |e: ConversionError| match e {
+            ConversionError::Syntax(reason) => {
+                ConversionError::Syntax(format!("line {}: {}", line_number + 1, reason))
+            }
+            e => e,
+        };
+
+        match keyword {
+            "v" => positions.push(parse_floats::<3>(rest).map_err(syntax_error)?),
+            "vt" => {
+                let [u, v] = parse_tex_coord(rest).map_err(syntax_error)?;
+                tex_coords.push([u, 1.0 - v]);
+            }
+            "vn" => normals.push(parse_floats::<3>(rest).map_err(syntax_error)?),
+            "mtllib" => material_library = Some(rest.to_string()),
+            "usemtl" => {
+                // only the first material is kept, a mesh is drawn with a single material
+                if material_name.is_none() {
+                    material_name = Some(rest.to_string());
+                }
+            }
+            "f" => {
+                let mut polygon = Vec::new();
+                for corner in rest.split_whitespace() {
+                    let key =
+                        parse_corner(corner, positions.len(), tex_coords.len(), normals.len())
+                            .map_err(syntax_error)?;
+
+                    let index = *corners.entry(key).or_insert_with(|| {
+                        let (position, uv, normal) = key;
+                        mesh_positions.push(positions[position]);
+                        mesh_uvs.push(uv.map_or([0.0, 0.0], |uv| tex_coords[uv]));
+                        mesh_normals.push(normal.map_or([0.0, 0.0, 1.0], |normal| normals[normal]));
+                        mesh_positions.len() as u32 - 1
+                    });
+                    polygon.push(index);
+                }
+
+                if polygon.len() < 3 {
+                    return Err(syntax_error(ConversionError::Syntax(
+                        "a face needs at least three vertices".to_string(),
+                    )));
+                }
+
+                for k in 1..polygon.len() - 1 {
+                    indices.extend([polygon[0], polygon[k], polygon[k + 1]]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if indices.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh_positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh_normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh_uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+
+    Ok(ObjFile {
+        mesh,
+        material_library,
+        material_name,
+    })
+}
+
+/// Outlines of a triangle list, seen from the top: the edges that belong to a single triangle,
+/// chained into loops. Each loop ends on its first point. Vertices that share a position are
+/// merged, so that seams in the uvs or normals are not mistaken for outlines.
+pub fn mesh_outlines(mesh: &Mesh) -> Result<Vec<Vec<Vec2>>, ConversionError> {
+    let positions: Vec<Vec2> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions
+            .iter()
+            .map(|[x, y, _]| Vec2::new(*x, *y))
+            .collect(),
+        Some(VertexAttributeValues::Float32x2(positions)) => {
+            positions.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
+        }
+        Some(_) => {
+            return Err(ConversionError::Unsupported(
+                "position attribute that is not made of floats".to_string(),
+            ))
+        }
+        None => return Err(ConversionError::Empty),
+    };
+
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    // one id per distinct position
+    let mut ids: HashMap<(u32, u32), usize> = HashMap::new();
+    let mut points: Vec<Vec2> = Vec::new();
+    let mut vertex_ids = Vec::new();
+    for position in positions.iter() {
+        let id = *ids
+            .entry((position.x.to_bits(), position.y.to_bits()))
+            .or_insert_with(|| {
+                points.push(*position);
+                points.len() - 1
+            });
+        vertex_ids.push(id);
+    }
+
+    let mut edge_counts: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut directed_edges = Vec::new();
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| vertex_ids.get(triangle[k]).copied());
+        let (a, b, c) = match (a, b, c) {
+            (Some(a), Some(b), Some(c)) if a != b && b != c && c != a => (a, b, c),
+            (Some(_), Some(_), Some(_)) => continue,
+            _ => {
+                return Err(ConversionError::Syntax(
+                    "an index is out of bounds".to_string(),
+                ))
+            }
+        };
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            *edge_counts.entry((from.min(to), from.max(to))).or_insert(0) += 1;
+            directed_edges.push((from, to));
+        }
+    }
+
+    let mut next: HashMap<usize, usize> = directed_edges
+        .into_iter()
+        .filter(|(from, to)| edge_counts[&(*from.min(to), *from.max(to))] == 1)
+        .collect();
+
+    let mut starts = next.keys().copied().collect::<Vec<usize>>();
+    starts.sort();
+
+    let mut outlines = Vec::new();
+    for start in starts {
+        let mut outline = Vec::new();
+        let mut current = start;
+        while let Some(to) = next.remove(&current) {
+            outline.push(points[current]);
+            current = to;
+        }
+        if outline.len() > 2 && current == start {
+            outline.push(points[start]);
+            outlines.push(outline);
+        }
+    }
+
+    if outlines.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    Ok(outlines)
+}
+
+// "v", "v/vt", "v//vn" or "v/vt/vn", with 1-based or negative (relative) indices
+fn parse_corner(
+    corner: &str,
+    num_positions: usize,
+    num_tex_coords: usize,
+    num_normals: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), ConversionError> {
+    let mut parts = corner.split('/');
+
+    let resolve = |part: Option<&str>, count: usize| -> Result<Option<usize>, ConversionError> {
+        match part {
+            None | Some("") => Ok(None),
+            Some(text) => {
+                let index = text
+                    .parse::<i64>()
+                    .map_err(|_| ConversionError::Syntax(format!("invalid index {}", text)))?;
+                let resolved = if index < 0 {
+                    count as i64 + index
+                } else {
+                    index - 1
+                };
+                if resolved < 0 || resolved >= count as i64 {
+                    return Err(ConversionError::Syntax(format!(
+                        "index {} is out of bounds",
+                        text
+                    )));
+                }
+                Ok(Some(resolved as usize))
+            }
+        }
+    };
+
+    let position = resolve(parts.next(), num_positions)?
+        .ok_or_else(|| ConversionError::Syntax(format!("{} has no vertex index", corner)))?;
+    let tex_coord = resolve(parts.next(), num_tex_coords)?;
+    let normal = resolve(parts.next(), num_normals)?;
+
+    Ok((position, tex_coord, normal))
+}
+
+// the v coordinate is optional, and defaults to 0
+fn parse_tex_coord(text: &str) -> Result<[f32; 2], ConversionError> {
+    if text.split_whitespace().count() == 1 {
+        let [u] = parse_floats::<1>(text)?;
+        Ok([u, 0.0])
+    } else {
+        parse_floats::<2>(text)
+    }
+}
+
+// reads the first N numbers, optional trailing numbers such as the w of a vertex are ignored
+fn parse_floats<const N: usize>(text: &str) -> Result<[f32; N], ConversionError> {
+    let mut values = [0.0; N];
+    let mut numbers = text.split_whitespace();
+
+    for value in values.iter_mut() {
+        let number = numbers
+            .next()
+            .ok_or_else(|| ConversionError::Syntax(format!("expected {} numbers", N)))?;
+        *value = number
+            .parse::<f32>()
+            .map_err(|_| ConversionError::Syntax(format!("invalid number {}", number)))?;
+    }
+
+    Ok(values)
+}
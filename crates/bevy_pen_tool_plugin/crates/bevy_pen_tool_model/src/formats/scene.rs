@@ -0,0 +1,128 @@
+//! Vertex data of the fill and road meshes in the `.scn.ron` scenes exported by the editor,
+//! and the [`PenSceneMeshPlugin`] that draws them again in a game.
+
+use crate::formats::ConversionError;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::sprite::Mesh2dHandle;
+use bevy_pen_tool_runtime::{PenFill, PenMeshData, PenRoad};
+
+/// Builds the meshes of the scenes exported by the editor, next to the `PenRuntimePlugin` of
+/// `bevy_pen_tool_runtime`, which only sets up their followers.
+pub struct PenSceneMeshPlugin;
+
+impl Plugin for PenSceneMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_scene_meshes);
+    }
+}
+
+/// The vertices of a triangle list, as stored in a scene. Meshes without uvs, such as fill
+/// meshes, are stored without them.
+pub fn pen_mesh_data(mesh: &Mesh) -> Result<PenMeshData, ConversionError> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return Err(ConversionError::Unsupported(
+            "mesh that is not a triangle list".to_string(),
+        ));
+    }
+
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            positions.iter().map(|p| Vec3::from(*p)).collect()
+        }
+        Some(VertexAttributeValues::Float32x2(positions)) => positions
+            .iter()
+            .map(|p| Vec2::from(*p).extend(0.0))
+            .collect(),
+        Some(_) => {
+            return Err(ConversionError::Unsupported(
+                "position attribute that is not made of floats".to_string(),
+            ))
+        }
+        None => return Err(ConversionError::Empty),
+    };
+    let num_vertices = positions.len();
+    if num_vertices == 0 {
+        return Err(ConversionError::Empty);
+    }
+
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) if uvs.len() == num_vertices => {
+            uvs.iter().map(|uv| Vec2::from(*uv)).collect()
+        }
+        Some(_) => {
+            return Err(ConversionError::Syntax(
+                "the uvs are not two floats for each vertex".to_string(),
+            ))
+        }
+        None => Vec::new(),
+    };
+
+    let indices = match mesh.indices() {
+        Some(indices) => indices.iter().map(|i| i as u32).collect::<Vec<u32>>(),
+        None => (0..num_vertices as u32).collect(),
+    };
+    if indices.len() % 3 != 0 || indices.iter().any(|i| *i as usize >= num_vertices) {
+        return Err(ConversionError::Syntax(
+            "the indices do not make whole triangles".to_string(),
+        ));
+    }
+
+    Ok(PenMeshData {
+        positions,
+        uvs,
+        indices,
+    })
+}
+
+/// Builds a 2d mesh and its material for every new [`PenMeshData`]. Road textures are loaded
+/// from their asset path.
+pub fn spawn_scene_meshes(
+    mut commands: Commands,
+    query: Query<(Entity, &PenMeshData, Option<&PenFill>, Option<&PenRoad>), Added<PenMeshData>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, data, fill, road) in query.iter() {
+        let num_vertices = data.positions.len();
+        let positions = data
+            .positions
+            .iter()
+            .map(|position| position.to_array())
+            .collect::<Vec<[f32; 3]>>();
+        let uvs = if data.uvs.len() == num_vertices {
+            data.uvs.iter().map(|uv| uv.to_array()).collect()
+        } else {
+            vec![[0.0, 0.0]; num_vertices]
+        };
+
+        // 2d meshes need normals
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; num_vertices]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(data.indices.clone())));
+
+        let material = match (fill, road) {
+            (Some(fill), _) => ColorMaterial::from(Color::rgba_linear(
+                fill.color.x,
+                fill.color.y,
+                fill.color.z,
+                fill.color.w,
+            )),
+            (None, Some(road)) => ColorMaterial::from(asset_server.load::<Image, _>(&road.texture)),
+            (None, None) => ColorMaterial::default(),
+        };
+
+        commands.entity(entity).insert_bundle((
+            Mesh2dHandle(meshes.add(mesh)),
+            materials.add(material),
+            GlobalTransform::default(),
+            Visibility::default(),
+            ComputedVisibility::default(),
+        ));
+    }
+}
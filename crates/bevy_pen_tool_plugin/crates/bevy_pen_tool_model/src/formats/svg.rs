@@ -0,0 +1,974 @@
+//! SVG import and export. On import, paths, basic shapes and transforms are read along with
+//! their colors, and every subpath becomes a chain of latched cubic curves. Lines, quadratic
+//! curves and arcs are converted to cubic curves. On export, every group becomes a `<path>`.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+/// Start, control start, control end and end of a cubic curve, in SVG coordinates.
+type Cubic = [DVec2; 4];
+
+/// Points closer than this are merged while building paths.
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgSubpath {
+    pub curves: Vec<BezierPositions>,
+    pub closed: bool,
+}
+
+/// A path or a basic shape, in canvas coordinates (the y axis of SVG is flipped).
+#[derive(Debug, Clone)]
+pub struct SvgShape {
+    pub subpaths: Vec<SvgSubpath>,
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+}
+
+pub fn parse_svg(contents: &str) -> Result<Vec<SvgShape>, ConversionError> {
+    let document =
+        roxmltree::Document::parse(contents).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+
+    let mut shapes = Vec::new();
+    read_element(
+        document.root_element(),
+        Affine::IDENTITY,
+        Style::default(),
+        &mut shapes,
+    )?;
+    Ok(shapes)
+}
+
+/// Turns every subpath of an SVG document into a group. The fill color of a shape becomes the
+/// color of its curves, or the stroke color if the shape is not filled.
+pub fn svg_to_groups(
+    contents: &str,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let mut groups = Vec::new();
+
+    for shape in parse_svg(contents)? {
+        let color = shape.fill.or(shape.stroke);
+        for subpath in shape.subpaths.iter() {
+            groups.push(GroupSaveLoad::from_chain(
+                &subpath.curves,
+                color,
+                num_points,
+            )?);
+        }
+    }
+
+    if groups.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    Ok(groups)
+}
+
+fn read_element(
+    node: roxmltree::Node,
+    parent_transform: Affine,
+    parent_style: Style,
+    shapes: &mut Vec<SvgShape>,
+) -> Result<(), ConversionError> {
+    let name = node.tag_name().name();
+
+    // elements that are not rendered by themselves
+    if matches!(
+        name,
+        "defs" | "clipPath" | "mask" | "symbol" | "pattern" | "marker" | "style" | "metadata"
+    ) || property(node, "display").as_deref() == Some("none")
+    {
+        return Ok(());
+    }
+
+    let transform = match node.attribute("transform") {
+        Some(text) => parent_transform.then(parse_transform(text)?),
+        None => parent_transform,
+    };
+    let style = parent_style.inherit(node);
+
+    let mut builder = PathBuilder::default();
+    let is_shape = match name {
+        "path" => {
+            parse_path_data(node.attribute("d").unwrap_or(""), &mut builder)?;
+            true
+        }
+        "rect" => build_rect(node, &mut builder),
+        "circle" => {
+            let r = length(node, "r");
+            build_ellipse(length(node, "cx"), length(node, "cy"), r, r, &mut builder)
+        }
+        "ellipse" => build_ellipse(
+            length(node, "cx"),
+            length(node, "cy"),
+            length(node, "rx"),
+            length(node, "ry"),
+            &mut builder,
+        ),
+        "line" => {
+            builder.move_to(DVec2::new(length(node, "x1"), length(node, "y1")));
+            builder.line_to(DVec2::new(length(node, "x2"), length(node, "y2")));
+            true
+        }
+        "polyline" | "polygon" => {
+            let mut tokens = Tokens::new(node.attribute("points").unwrap_or(""));
+            let mut first = true;
+            while !tokens.at_end() {
+                let point = tokens.point()?;
+                if first {
+                    builder.move_to(point);
+                    first = false;
+                } else {
+                    builder.line_to(point);
+                }
+            }
+            if name == "polygon" {
+                builder.close();
+            }
+            true
+        }
+        _ => false,
+    };
+
+    if is_shape {
+        let subpaths = builder
+            .into_subpaths()
+            .into_iter()
+            .map(|(curves, closed)| SvgSubpath {
+                curves: curves
+                    .iter()
+                    .map(|cubic| {
+                        let [start, control_start, control_end, end] =
+                            cubic.map(|p| to_canvas(transform.apply(p)));
+                        BezierPositions {
+                            start,
+                            end,
+                            control_start,
+                            control_end,
+                        }
+                    })
+                    .collect(),
+                closed,
+            })
+            .collect::<Vec<SvgSubpath>>();
+
+        if !subpaths.is_empty() {
+            shapes.push(SvgShape {
+                subpaths,
+                fill: style.fill,
+                stroke: style.stroke,
+            });
+        }
+    }
+
+    for child in node.children().filter(|child| child.is_element()) {
+        read_element(child, transform, style, shapes)?;
+    }
+
+    Ok(())
+}
+
+fn to_canvas(p: DVec2) -> Vec2 {
+    Vec2::new(p.x as f32, -p.y as f32)
+}
+
+////////////////////////////// shapes //////////////////////////////
+
+fn build_rect(node: roxmltree::Node, builder: &mut PathBuilder) -> bool {
+    let (x, y) = (length(node, "x"), length(node, "y"));
+    let (w, h) = (length(node, "width"), length(node, "height"));
+    if w <= 0.0 || h <= 0.0 {
+        return false;
+    }
+
+    // a single radius applies to both axes
+    let (rx, ry) = match (node.attribute("rx"), node.attribute("ry")) {
+        (None, None) => (0.0, 0.0),
+        (Some(_), None) => (length(node, "rx"), length(node, "rx")),
+        (None, Some(_)) => (length(node, "ry"), length(node, "ry")),
+        (Some(_), Some(_)) => (length(node, "rx"), length(node, "ry")),
+    };
+    let (rx, ry) = (rx.clamp(0.0, w / 2.0), ry.clamp(0.0, h / 2.0));
+
+    if rx <= EPSILON || ry <= EPSILON {
+        builder.move_to(DVec2::new(x, y));
+        builder.line_to(DVec2::new(x + w, y));
+        builder.line_to(DVec2::new(x + w, y + h));
+        builder.line_to(DVec2::new(x, y + h));
+        builder.close();
+        return true;
+    }
+
+    let radii = DVec2::new(rx, ry);
+    builder.move_to(DVec2::new(x + rx, y));
+    builder.line_to(DVec2::new(x + w - rx, y));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(x + w, y + ry));
+    builder.line_to(DVec2::new(x + w, y + h - ry));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(x + w - rx, y + h));
+    builder.line_to(DVec2::new(x + rx, y + h));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(x, y + h - ry));
+    builder.line_to(DVec2::new(x, y + ry));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(x + rx, y));
+    builder.close();
+    true
+}
+
+fn build_ellipse(cx: f64, cy: f64, rx: f64, ry: f64, builder: &mut PathBuilder) -> bool {
+    if rx <= 0.0 || ry <= 0.0 {
+        return false;
+    }
+
+    let radii = DVec2::new(rx, ry);
+    builder.move_to(DVec2::new(cx + rx, cy));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(cx, cy + ry));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(cx - rx, cy));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(cx, cy - ry));
+    builder.arc_to(radii, 0.0, false, true, DVec2::new(cx + rx, cy));
+    builder.close();
+    true
+}
+
+////////////////////////////// path data //////////////////////////////
+
+#[derive(Default)]
+struct PathBuilder {
+    subpaths: Vec<(Vec<Cubic>, bool)>,
+    curves: Vec<Cubic>,
+    start: DVec2,
+    point: DVec2,
+}
+
+impl PathBuilder {
+    fn move_to(&mut self, p: DVec2) {
+        self.finish(false);
+        self.start = p;
+        self.point = p;
+    }
+
+    fn line_to(&mut self, p: DVec2) {
+        let a = self.point;
+        self.cubic_to(a.lerp(p, 1.0 / 3.0), a.lerp(p, 2.0 / 3.0), p);
+    }
+
+    // degree elevation of a quadratic curve
+    fn quad_to(&mut self, control: DVec2, p: DVec2) {
+        let a = self.point;
+        self.cubic_to(
+            a + (control - a) * 2.0 / 3.0,
+            p + (control - p) * 2.0 / 3.0,
+            p,
+        );
+    }
+
+    fn cubic_to(&mut self, control_start: DVec2, control_end: DVec2, p: DVec2) {
+        let a = self.point;
+        let is_degenerate = [control_start, control_end, p]
+            .iter()
+            .all(|q| q.distance(a) < EPSILON);
+
+        if !is_degenerate {
+            self.curves.push([a, control_start, control_end, p]);
+        }
+        self.point = p;
+    }
+
+    /// Elliptical arc, converted to at most one cubic curve per quarter turn
+    /// (SVG 1.1, appendix F.6).
+    fn arc_to(&mut self, radii: DVec2, rotation_deg: f64, large_arc: bool, sweep: bool, p: DVec2) {
+        let p0 = self.point;
+        let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+
+        if p0.distance(p) < EPSILON {
+            return;
+        }
+        if rx < EPSILON || ry < EPSILON {
+            self.line_to(p);
+            return;
+        }
+
+        let phi = rotation_deg.to_radians();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        let half = (p0 - p) / 2.0;
+        let x1 = cos_phi * half.x + sin_phi * half.y;
+        let y1 = -sin_phi * half.x + cos_phi * half.y;
+
+        // radii that are too small are scaled up
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+        let coef = sign * (numerator / denominator).max(0.0).sqrt();
+
+        let cx1 = coef * rx * y1 / ry;
+        let cy1 = -coef * ry * x1 / rx;
+
+        let mid = (p0 + p) / 2.0;
+        let center = DVec2::new(
+            cos_phi * cx1 - sin_phi * cy1 + mid.x,
+            sin_phi * cx1 + cos_phi * cy1 + mid.y,
+        );
+
+        let u = DVec2::new((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let v = DVec2::new((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+        let theta1 = angle_between(DVec2::X, u);
+        let mut delta_theta = angle_between(u, v);
+
+        if !sweep && delta_theta > 0.0 {
+            delta_theta -= 2.0 * PI;
+        } else if sweep && delta_theta < 0.0 {
+            delta_theta += 2.0 * PI;
+        }
+
+        let num_segments = (delta_theta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+        let delta = delta_theta / num_segments as f64;
+        let k = 4.0 / 3.0 * (delta / 4.0).tan();
+
+        let on_ellipse = |unit: DVec2| {
+            let scaled = DVec2::new(rx * unit.x, ry * unit.y);
+            center
+                + DVec2::new(
+                    cos_phi * scaled.x - sin_phi * scaled.y,
+                    sin_phi * scaled.x + cos_phi * scaled.y,
+                )
+        };
+
+        for segment in 0..num_segments {
+            let t1 = theta1 + segment as f64 * delta;
+            let t2 = t1 + delta;
+            let (sin1, cos1) = t1.sin_cos();
+            let (sin2, cos2) = t2.sin_cos();
+
+            let control_start = on_ellipse(DVec2::new(cos1 - k * sin1, sin1 + k * cos1));
+            let control_end = on_ellipse(DVec2::new(cos2 + k * sin2, sin2 - k * cos2));
+            let end = if segment + 1 == num_segments {
+                p
+            } else {
+                on_ellipse(DVec2::new(cos2, sin2))
+            };
+
+            self.cubic_to(control_start, control_end, end);
+        }
+    }
+
+    fn close(&mut self) {
+        if self.point.distance(self.start) > EPSILON {
+            self.line_to(self.start);
+        }
+        self.point = self.start;
+        self.finish(true);
+    }
+
+    fn finish(&mut self, closed: bool) {
+        if !self.curves.is_empty() {
+            self.subpaths
+                .push((std::mem::take(&mut self.curves), closed));
+        }
+    }
+
+    fn into_subpaths(mut self) -> Vec<(Vec<Cubic>, bool)> {
+        self.finish(false);
+        self.subpaths
+    }
+}
+
+fn angle_between(u: DVec2, v: DVec2) -> f64 {
+    (u.x * v.y - u.y * v.x).atan2(u.dot(v))
+}
+
+fn parse_path_data(data: &str, builder: &mut PathBuilder) -> Result<(), ConversionError> {
+    let mut tokens = Tokens::new(data);
+    let mut command: Option<u8> = None;
+
+    // second control point of the previous curve, reflected by the smooth commands S and T
+    let mut last_cubic_control: Option<DVec2> = None;
+    let mut last_quad_control: Option<DVec2> = None;
+
+    while !tokens.at_end() {
+        if let Some(new_command) = tokens.command() {
+            command = Some(new_command);
+        }
+
+        let current = command.ok_or_else(|| {
+            ConversionError::Syntax(format!("expected a path command in \"{}\"", data))
+        })?;
+        let relative = current.is_ascii_lowercase();
+        let origin = if relative { builder.point } else { DVec2::ZERO };
+
+        let mut cubic_control = None;
+        let mut quad_control = None;
+
+        match current.to_ascii_uppercase() {
+            b'M' => {
+                builder.move_to(origin + tokens.point()?);
+                // coordinates following a move are implicit lines
+                command = Some(if relative { b'l' } else { b'L' });
+            }
+            b'L' => builder.line_to(origin + tokens.point()?),
+            b'H' => {
+                let x = tokens.number()? + origin.x;
+                builder.line_to(DVec2::new(x, builder.point.y));
+            }
+            b'V' => {
+                let y = tokens.number()? + origin.y;
+                builder.line_to(DVec2::new(builder.point.x, y));
+            }
+            b'C' => {
+                let control_start = origin + tokens.point()?;
+                let control_end = origin + tokens.point()?;
+                let end = origin + tokens.point()?;
+                builder.cubic_to(control_start, control_end, end);
+                cubic_control = Some(control_end);
+            }
+            b'S' => {
+                let control_start = last_cubic_control
+                    .map(|c| 2.0 * builder.point - c)
+                    .unwrap_or(builder.point);
+                let control_end = origin + tokens.point()?;
+                let end = origin + tokens.point()?;
+                builder.cubic_to(control_start, control_end, end);
+                cubic_control = Some(control_end);
+            }
+            b'Q' => {
+                let control = origin + tokens.point()?;
+                let end = origin + tokens.point()?;
+                builder.quad_to(control, end);
+                quad_control = Some(control);
+            }
+            b'T' => {
+                let control = last_quad_control
+                    .map(|c| 2.0 * builder.point - c)
+                    .unwrap_or(builder.point);
+                let end = origin + tokens.point()?;
+                builder.quad_to(control, end);
+                quad_control = Some(control);
+            }
+            b'A' => {
+                let radii = tokens.point()?;
+                let rotation = tokens.number()?;
+                let large_arc = tokens.flag()?;
+                let sweep = tokens.flag()?;
+                let end = origin + tokens.point()?;
+                builder.arc_to(radii, rotation, large_arc, sweep, end);
+            }
+            b'Z' => {
+                builder.close();
+                // Z takes no coordinates, so it cannot be repeated implicitly
+                command = None;
+            }
+            _ => {
+                return Err(ConversionError::Unsupported(format!(
+                    "path command '{}'",
+                    current as char
+                )))
+            }
+        }
+
+        last_cubic_control = cubic_control;
+        last_quad_control = quad_control;
+    }
+
+    Ok(())
+}
+
+/// Reads numbers, flags and commands from path data and other number lists.
+struct Tokens<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            bytes: text.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self.pos < self.bytes.len()
+            && (self.bytes[self.pos].is_ascii_whitespace() || self.bytes[self.pos] == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.bytes.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let byte = *self.bytes.get(self.pos)?;
+        if byte.is_ascii_alphabetic() {
+            self.pos += 1;
+            Some(byte)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, ConversionError> {
+        self.skip_separators();
+        let start = self.pos;
+
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let mut num_digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            num_digits += self.skip_digits();
+        }
+
+        // the exponent is only read if digits follow, so that "2e" stays an error
+        if num_digits > 0 && matches!(self.peek(), Some(b'e' | b'E')) {
+            let before_exponent = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.pos = before_exponent;
+            }
+        }
+
+        if num_digits == 0 {
+            return Err(ConversionError::Syntax(format!(
+                "expected a number at position {} of \"{}\"",
+                start, self.text
+            )));
+        }
+
+        self.text[start..self.pos]
+            .parse::<f64>()
+            .map_err(|e| ConversionError::Syntax(e.to_string()))
+    }
+
+    // arc flags are single digits that may not be separated from what follows
+    fn flag(&mut self) -> Result<bool, ConversionError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => {
+                return Err(ConversionError::Syntax(format!(
+                    "expected an arc flag at position {} of \"{}\"",
+                    self.pos, self.text
+                )))
+            }
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+
+    fn point(&mut self) -> Result<DVec2, ConversionError> {
+        Ok(DVec2::new(self.number()?, self.number()?))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while self.peek().map_or(false, |b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+}
+
+////////////////////////////// transforms //////////////////////////////
+
+/// Affine transform `[a c e; b d f]`, as in the SVG `matrix(a b c d e f)` notation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Affine {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl Affine {
+    const IDENTITY: Self = Self {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    fn translate(tx: f64, ty: f64) -> Self {
+        Self {
+            e: tx,
+            f: ty,
+            ..Self::IDENTITY
+        }
+    }
+
+    fn scale(sx: f64, sy: f64) -> Self {
+        Self {
+            a: sx,
+            d: sy,
+            ..Self::IDENTITY
+        }
+    }
+
+    fn rotate(angle_deg: f64) -> Self {
+        let (sin, cos) = angle_deg.to_radians().sin_cos();
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// `self` applied after `other`.
+    fn then(self, other: Self) -> Self {
+        Self {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    fn apply(&self, p: DVec2) -> DVec2 {
+        DVec2::new(
+            self.a * p.x + self.c * p.y + self.e,
+            self.b * p.x + self.d * p.y + self.f,
+        )
+    }
+}
+
+fn parse_transform(text: &str) -> Result<Affine, ConversionError> {
+    let mut transform = Affine::IDENTITY;
+    let mut rest = text;
+
+    while !rest.trim().is_empty() {
+        let invalid = || ConversionError::Syntax(format!("invalid transform \"{}\"", text));
+        let open = rest.find('(').ok_or_else(invalid)?;
+        let close = rest.find(')').ok_or_else(invalid)?;
+        if close < open {
+            return Err(invalid());
+        }
+
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let mut tokens = Tokens::new(&rest[open + 1..close]);
+        let mut args = Vec::new();
+        while !tokens.at_end() {
+            args.push(tokens.number()?);
+        }
+
+        let local = match (name, args.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Affine { a, b, c, d, e, f },
+            ("translate", &[tx]) => Affine::translate(tx, 0.0),
+            ("translate", &[tx, ty]) => Affine::translate(tx, ty),
+            ("scale", &[s]) => Affine::scale(s, s),
+            ("scale", &[sx, sy]) => Affine::scale(sx, sy),
+            ("rotate", &[angle]) => Affine::rotate(angle),
+            ("rotate", &[angle, cx, cy]) => Affine::translate(cx, cy)
+                .then(Affine::rotate(angle))
+                .then(Affine::translate(-cx, -cy)),
+            ("skewX", &[angle]) => Affine {
+                c: angle.to_radians().tan(),
+                ..Affine::IDENTITY
+            },
+            ("skewY", &[angle]) => Affine {
+                b: angle.to_radians().tan(),
+                ..Affine::IDENTITY
+            },
+            _ => return Err(invalid()),
+        };
+
+        transform = transform.then(local);
+        rest = &rest[close + 1..];
+    }
+
+    Ok(transform)
+}
+
+////////////////////////////// style //////////////////////////////
+
+/// Colors inherited by child elements. Shapes without any color are left for the editor to
+/// color, instead of using the black default of SVG.
+#[derive(Debug, Clone, Copy, Default)]
+struct Style {
+    fill: Option<Color>,
+    stroke: Option<Color>,
+}
+
+impl Style {
+    fn inherit(self, node: roxmltree::Node) -> Self {
+        let mut style = self;
+        if let Some(fill) = property(node, "fill").and_then(|value| parse_paint(&value)) {
+            style.fill = fill;
+        }
+        if let Some(stroke) = property(node, "stroke").and_then(|value| parse_paint(&value)) {
+            style.stroke = stroke;
+        }
+        style
+    }
+}
+
+/// A presentation attribute, looked up in the `style` attribute first.
+fn property(node: roxmltree::Node, name: &str) -> Option<String> {
+    let from_style = node.attribute("style").and_then(|style| {
+        style.split(';').find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            (key.trim() == name).then(|| value.trim().to_string())
+        })
+    });
+
+    from_style.or_else(|| node.attribute(name).map(|value| value.trim().to_string()))
+}
+
+/// `None` keeps the inherited paint, `Some(None)` removes it.
+fn parse_paint(value: &str) -> Option<Option<Color>> {
+    let value = value.trim();
+
+    if value == "none" {
+        return Some(None);
+    }
+
+    if let Some(hex) = value.strip_prefix('#') {
+        // #rgb is a short form of #rrggbb
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+            _ => hex.to_string(),
+        };
+        return Color::hex(hex).ok().map(Some);
+    }
+
+    if let Some(args) = value
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let channels = args
+            .split(',')
+            .map(|channel| {
+                let channel = channel.trim();
+                match channel.strip_suffix('%') {
+                    Some(percent) => percent.parse::<f32>().ok().map(|p| p / 100.0),
+                    None => channel.parse::<f32>().ok().map(|c| c / 255.0),
+                }
+            })
+            .collect::<Option<Vec<f32>>>()?;
+
+        return match channels.as_slice() {
+            &[r, g, b] => Some(Some(Color::rgb(r, g, b))),
+            _ => None,
+        };
+    }
+
+    let named = match value {
+        "black" => Color::BLACK,
+        "white" => Color::WHITE,
+        "red" => Color::RED,
+        "lime" => Color::GREEN,
+        "green" => Color::rgb_u8(0, 128, 0),
+        "blue" => Color::BLUE,
+        "yellow" => Color::YELLOW,
+        "cyan" | "aqua" => Color::CYAN,
+        "magenta" | "fuchsia" => Color::FUCHSIA,
+        "orange" => Color::ORANGE,
+        "purple" => Color::PURPLE,
+        "gray" | "grey" => Color::GRAY,
+        "silver" => Color::SILVER,
+        "maroon" => Color::MAROON,
+        "navy" => Color::NAVY,
+        "olive" => Color::OLIVE,
+        "teal" => Color::TEAL,
+        // gradients, patterns and currentColor keep the inherited paint
+        _ => return None,
+    };
+    Some(Some(named))
+}
+
+/// A length attribute in user units. Units are ignored and missing values are 0.
+fn length(node: roxmltree::Node, name: &str) -> f64 {
+    node.attribute(name)
+        .and_then(|value| Tokens::new(value).number().ok())
+        .unwrap_or(0.0)
+}
+
+////////////////////////////// export //////////////////////////////
+
+/// Width of the exported strokes, in canvas units.
+const STROKE_WIDTH: f32 = 2.0;
+/// Space left around the exported paths.
+const MARGIN: f32 = 10.0;
+
+/// A `<path>` written by [`write_svg`], in canvas coordinates.
+#[derive(Debug, Clone)]
+pub struct SvgExportPath {
+    pub curves: Vec<BezierPositions>,
+    pub closed: bool,
+    pub stroke: Option<Color>,
+    pub fill: Option<Color>,
+}
+
+impl SvgExportPath {
+    /// The curves of a group in their direction of travel, stroked with the curve color.
+    pub fn from_group(group: &GroupSaveLoad, fill: Option<Color>) -> Self {
+        Self {
+            curves: group.path_curves(),
+            closed: group.is_closed(),
+            stroke: group.lut.first().and_then(|(bezier, _, _, _)| bezier.color),
+            fill,
+        }
+    }
+
+    fn path_data(&self) -> String {
+        let mut data = String::new();
+        let mut previous_end: Option<Vec2> = None;
+
+        for curve in self.curves.iter() {
+            let [start, control_start, control_end, end] = [
+                curve.start,
+                curve.control_start,
+                curve.control_end,
+                curve.end,
+            ]
+            .map(from_canvas);
+
+            // curves that do not touch start a new subpath
+            if previous_end.map_or(true, |p| p.distance(start) > ANCHOR_MERGE_DISTANCE) {
+                if !data.is_empty() {
+                    data.push(' ');
+                }
+                write!(data, "M {} {}", number(start.x), number(start.y)).unwrap();
+            }
+
+            write!(
+                data,
+                " C {} {} {} {} {} {}",
+                number(control_start.x),
+                number(control_start.y),
+                number(control_end.x),
+                number(control_end.y),
+                number(end.x),
+                number(end.y)
+            )
+            .unwrap();
+            previous_end = Some(end);
+        }
+
+        if self.closed {
+            data.push_str(" Z");
+        }
+        data
+    }
+}
+
+/// Writes a standalone SVG document holding one `<path>` per entry. The view box fits all
+/// the curves.
+pub fn write_svg(paths: &[SvgExportPath]) -> String {
+    let bounds = paths
+        .iter()
+        .flat_map(|path| path.curves.iter())
+        .flat_map(|curve| {
+            [
+                curve.start,
+                curve.control_start,
+                curve.control_end,
+                curve.end,
+            ]
+        })
+        .map(from_canvas)
+        .fold(None, |bounds: Option<(Vec2, Vec2)>, p| match bounds {
+            Some((min, max)) => Some((min.min(p), max.max(p))),
+            None => Some((p, p)),
+        });
+
+    let (min, max) = bounds.unwrap_or((Vec2::ZERO, Vec2::ZERO));
+    let (min, size) = (
+        min - Vec2::splat(MARGIN),
+        max - min + Vec2::splat(2.0 * MARGIN),
+    );
+
+    let mut svg = String::new();
+    writeln!(svg, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">",
+        number(min.x),
+        number(min.y),
+        number(size.x),
+        number(size.y),
+        number(size.x),
+        number(size.y)
+    )
+    .unwrap();
+
+    for (k, path) in paths.iter().enumerate() {
+        write!(svg, "  <path id=\"group{}\" d=\"{}\"", k, path.path_data()).unwrap();
+        write_paint(&mut svg, "fill", path.fill);
+        write_paint(&mut svg, "stroke", path.stroke);
+        if path.stroke.is_some() {
+            write!(svg, " stroke-width=\"{}\"", number(STROKE_WIDTH)).unwrap();
+        }
+        writeln!(svg, "/>").unwrap();
+    }
+
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+fn write_paint(svg: &mut String, name: &str, color: Option<Color>) {
+    match color {
+        Some(color) => {
+            let [r, g, b, a] = color.as_rgba_f32();
+            let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+            write!(
+                svg,
+                " {}=\"#{:02x}{:02x}{:02x}\"",
+                name,
+                channel(r),
+                channel(g),
+                channel(b)
+            )
+            .unwrap();
+            if a < 1.0 {
+                write!(svg, " {}-opacity=\"{}\"", name, number(a)).unwrap();
+            }
+        }
+        None => write!(svg, " {}=\"none\"", name).unwrap(),
+    }
+}
+
+fn from_canvas(p: Vec2) -> Vec2 {
+    Vec2::new(p.x, -p.y)
+}
+
+// avoids writing "-0" for points on the x axis
+fn number(value: f32) -> String {
+    if value == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}", value)
+    }
+}
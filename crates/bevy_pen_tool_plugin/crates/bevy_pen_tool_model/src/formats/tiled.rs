@@ -0,0 +1,558 @@
+//! Tiled object layers, in the JSON (`.tmj`) and XML (`.tmx`) map formats. On export, every
+//! group becomes a polyline object, or a polygon object when the group is closed, through
+//! points sampled along its look-up table. On import, polyline and polygon objects become
+//! chains of latched curves, and the name, class and custom properties of each object become
+//! the metadata of the curves. The y axis of Tiled points down, so it is flipped both ways.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::prelude::*;
+
+use serde_json::{json, Value};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Version of the map format written on export.
+const TILED_MAP_VERSION: &str = "1.10";
+/// Name of the object layer written on export.
+const LAYER_NAME: &str = "paths";
+/// Metadata keys that hold the name and the class of an object, and are not written as
+/// custom properties.
+const NAME_KEY: &str = "name";
+const CLASS_KEY: &str = "type";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiledFormat {
+    /// A JSON map, with the `.tmj` extension.
+    Json,
+    /// An XML map, with the `.tmx` extension.
+    Tmx,
+}
+
+/// A polyline or polygon object written by [`write_tiled`], in canvas coordinates.
+#[derive(Debug, Clone)]
+pub struct TiledExportPath {
+    pub name: String,
+    pub class: String,
+    pub points: Vec<Vec2>,
+    pub closed: bool,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl TiledExportPath {
+    /// Samples the look-up table of a group every `spacing` canvas units, or closer so that
+    /// the points are evenly spaced. The metadata of the group gives the name, class and
+    /// properties of the object, and unnamed groups are called `path<index>`.
+    pub fn from_group(group: &GroupSaveLoad, index: usize, spacing: f32) -> Self {
+        let lut = &group.standalone_lut;
+        let num_segments = (lut.path_length / spacing.max(f32::EPSILON))
+            .ceil()
+            .clamp(1.0, 100_000.0) as usize;
+
+        let closed = group.is_closed();
+        // a polygon ends where it starts without repeating its first point
+        let num_points = if closed {
+            num_segments
+        } else {
+            num_segments + 1
+        };
+        let points = (0..num_points)
+            .map(|k| lut.position(k as f32 / num_segments as f32))
+            .collect::<Vec<Vec2>>();
+
+        let mut properties = group.metadata();
+        let name = properties
+            .remove(NAME_KEY)
+            .unwrap_or_else(|| format!("path{}", index));
+        let class = properties.remove(CLASS_KEY).unwrap_or_default();
+
+        Self {
+            name,
+            class,
+            points,
+            closed,
+            properties,
+        }
+    }
+}
+
+/// Writes an orthogonal map with a single object layer that holds every path. The map covers
+/// the paths, whose top left corner is moved to the origin of the map.
+pub fn write_tiled(paths: &[TiledExportPath], format: TiledFormat, tile_size: UVec2) -> String {
+    let paths = paths
+        .iter()
+        .filter(|path| path.points.len() > 1)
+        .collect::<Vec<&TiledExportPath>>();
+
+    // top left corner and size of the paths, with the y axis pointing down
+    let (min, max) = paths
+        .iter()
+        .flat_map(|path| path.points.iter())
+        .map(|point| Vec2::new(point.x, -point.y))
+        .fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), point| (min.min(point), max.max(point)),
+        );
+    let (origin, size) = if paths.is_empty() {
+        (Vec2::ZERO, Vec2::ZERO)
+    } else {
+        (min, max - min)
+    };
+
+    let tile_size = tile_size.max(UVec2::ONE);
+    let width = (size.x / tile_size.x as f32).ceil().max(1.0) as u32;
+    let height = (size.y / tile_size.y as f32).ceil().max(1.0) as u32;
+
+    // each object is placed on its first point, and its points are relative to it
+    let objects = paths
+        .iter()
+        .map(|path| {
+            let points = path
+                .points
+                .iter()
+                .map(|point| Vec2::new(point.x, -point.y) - origin)
+                .collect::<Vec<Vec2>>();
+            let position = points[0];
+            let relative = points
+                .iter()
+                .map(|point| *point - position)
+                .collect::<Vec<Vec2>>();
+            (*path, position, relative)
+        })
+        .collect::<Vec<(&TiledExportPath, Vec2, Vec<Vec2>)>>();
+
+    match format {
+        TiledFormat::Json => {
+            let objects = objects
+                .iter()
+                .enumerate()
+                .map(|(k, (path, position, points))| {
+                    let points = points
+                        .iter()
+                        .map(|point| json!({ "x": point.x, "y": point.y }))
+                        .collect::<Vec<Value>>();
+                    let properties = path
+                        .properties
+                        .iter()
+                        .map(|(name, value)| {
+                            let (kind, typed) = typed_property(value);
+                            json!({ "name": name, "type": kind, "value": typed })
+                        })
+                        .collect::<Vec<Value>>();
+
+                    let mut object = json!({
+                        "id": k + 1,
+                        "name": path.name,
+                        "type": path.class,
+                        "x": position.x,
+                        "y": position.y,
+                        "width": 0,
+                        "height": 0,
+                        "rotation": 0,
+                        "visible": true,
+                    });
+                    let shape = if path.closed { "polygon" } else { "polyline" };
+                    object[shape] = Value::Array(points);
+                    if !properties.is_empty() {
+                        object["properties"] = Value::Array(properties);
+                    }
+                    object
+                })
+                .collect::<Vec<Value>>();
+
+            let map = json!({
+                "type": "map",
+                "version": TILED_MAP_VERSION,
+                "orientation": "orthogonal",
+                "renderorder": "right-down",
+                "width": width,
+                "height": height,
+                "tilewidth": tile_size.x,
+                "tileheight": tile_size.y,
+                "infinite": false,
+                "nextlayerid": 2,
+                "nextobjectid": objects.len() + 1,
+                "layers": [{
+                    "type": "objectgroup",
+                    "id": 1,
+                    "name": LAYER_NAME,
+                    "draworder": "topdown",
+                    "opacity": 1,
+                    "visible": true,
+                    "x": 0,
+                    "y": 0,
+                    "objects": objects,
+                }],
+                "tilesets": [],
+            });
+            serde_json::to_string_pretty(&map).unwrap()
+        }
+        TiledFormat::Tmx => {
+            let mut tmx = String::new();
+            writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+            writeln!(
+                tmx,
+                r#"<map version="{}" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="2" nextobjectid="{}">"#,
+                TILED_MAP_VERSION,
+                width,
+                height,
+                tile_size.x,
+                tile_size.y,
+                objects.len() + 1
+            )
+            .unwrap();
+            writeln!(tmx, r#" <objectgroup id="1" name="{}">"#, LAYER_NAME).unwrap();
+
+            for (k, (path, position, points)) in objects.iter().enumerate() {
+                write!(
+                    tmx,
+                    r#"  <object id="{}" name="{}""#,
+                    k + 1,
+                    escape(&path.name)
+                )
+                .unwrap();
+                if !path.class.is_empty() {
+                    write!(tmx, r#" type="{}""#, escape(&path.class)).unwrap();
+                }
+                writeln!(tmx, r#" x="{}" y="{}">"#, position.x, position.y).unwrap();
+
+                if !path.properties.is_empty() {
+                    writeln!(tmx, "   <properties>").unwrap();
+                    for (name, value) in path.properties.iter() {
+                        let (kind, _) = typed_property(value);
+                        write!(tmx, r#"    <property name="{}""#, escape(name)).unwrap();
+                        if kind != "string" {
+                            write!(tmx, r#" type="{}""#, kind).unwrap();
+                        }
+                        writeln!(tmx, r#" value="{}"/>"#, escape(value)).unwrap();
+                    }
+                    writeln!(tmx, "   </properties>").unwrap();
+                }
+
+                let points = points
+                    .iter()
+                    .map(|point| format!("{},{}", point.x, point.y))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                let shape = if path.closed { "polygon" } else { "polyline" };
+                writeln!(tmx, r#"   <{} points="{}"/>"#, shape, points).unwrap();
+                writeln!(tmx, "  </object>").unwrap();
+            }
+
+            writeln!(tmx, " </objectgroup>").unwrap();
+            writeln!(tmx, "</map>").unwrap();
+            tmx
+        }
+    }
+}
+
+// the type of a Tiled property, and its value as json
+fn typed_property(value: &str) -> (&'static str, Value) {
+    if let Ok(int) = value.parse::<i64>() {
+        ("int", json!(int))
+    } else if let Some(float) = value.parse::<f64>().ok().filter(|f| f.is_finite()) {
+        ("float", json!(float))
+    } else if let Ok(boolean) = value.parse::<bool>() {
+        ("bool", json!(boolean))
+    } else {
+        ("string", json!(value))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// a polyline or polygon object, with its points in map coordinates
+struct TiledObject {
+    points: Vec<Vec2>,
+    closed: bool,
+    metadata: BTreeMap<String, String>,
+}
+
+/// Turns the polyline and polygon objects of every object layer of a Tiled map into groups.
+/// JSON and TMX maps are both accepted. Layer offsets and object rotations are applied. When
+/// `fit_max_error` is `None`, each segment becomes a straight curve, otherwise curves are
+/// fitted through the points.
+pub fn tiled_to_groups(
+    contents: &str,
+    fit_max_error: Option<f64>,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let objects = if contents.trim_start().starts_with('<') {
+        read_tmx(contents)?
+    } else {
+        read_tiled_json(contents)?
+    };
+
+    let groups = objects
+        .iter()
+        .filter_map(|object| {
+            let mut points = object
+                .points
+                .iter()
+                .map(|point| Vec2::new(point.x, -point.y))
+                .collect::<Vec<Vec2>>();
+            points.dedup();
+            if object.closed && points.len() > 1 {
+                points.push(points[0]);
+            }
+
+            let curves = match fit_max_error {
+                Some(max_error) => StandaloneLut {
+                    path_length: 0.0,
+                    lut: points,
+                }
+                .fit_curves(max_error),
+                None => points
+                    .windows(2)
+                    .filter(|pair| pair[0].distance(pair[1]) > ANCHOR_MERGE_DISTANCE)
+                    .map(|pair| BezierPositions {
+                        start: pair[0],
+                        end: pair[1],
+                        control_start: pair[0] + (pair[1] - pair[0]) / 3.0,
+                        control_end: pair[1] - (pair[1] - pair[0]) / 3.0,
+                    })
+                    .collect(),
+            };
+            if curves.is_empty() {
+                return None;
+            }
+
+            Some(
+                GroupSaveLoad::from_chain(&curves, None, num_points).map(|mut group| {
+                    group.set_metadata(&object.metadata);
+                    group
+                }),
+            )
+        })
+        .collect::<Result<Vec<GroupSaveLoad>, ConversionError>>()?;
+
+    if groups.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    Ok(groups)
+}
+
+// points of an object, rotated clockwise around its position in degrees, then moved to it
+fn place(points: &[Vec2], position: Vec2, rotation: f32) -> Vec<Vec2> {
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    points
+        .iter()
+        .map(|point| {
+            position + Vec2::new(point.x * cos - point.y * sin, point.x * sin + point.y * cos)
+        })
+        .collect()
+}
+
+fn object_metadata(
+    name: &str,
+    class: &str,
+    properties: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut metadata = properties;
+    if !name.is_empty() {
+        metadata.insert(NAME_KEY.to_string(), name.to_string());
+    }
+    if !class.is_empty() {
+        metadata.insert(CLASS_KEY.to_string(), class.to_string());
+    }
+    metadata
+}
+
+fn read_tiled_json(contents: &str) -> Result<Vec<TiledObject>, ConversionError> {
+    let map: Value =
+        serde_json::from_str(contents).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+
+    let layers = map
+        .get("layers")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ConversionError::Syntax("the map has no layers".to_string()))?;
+
+    let mut objects = Vec::new();
+    read_json_layers(layers, Vec2::ZERO, &mut objects);
+    Ok(objects)
+}
+
+fn read_json_layers(layers: &[Value], parent_offset: Vec2, objects: &mut Vec<TiledObject>) {
+    let number = |value: &Value, key: &str| value.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    for layer in layers.iter() {
+        let offset = parent_offset
+            + Vec2::new(
+                number(layer, "offsetx") as f32,
+                number(layer, "offsety") as f32,
+            );
+
+        match layer.get("type").and_then(Value::as_str) {
+            Some("group") => {
+                if let Some(children) = layer.get("layers").and_then(Value::as_array) {
+                    read_json_layers(children, offset, objects);
+                }
+            }
+            Some("objectgroup") => {
+                let layer_objects = layer.get("objects").and_then(Value::as_array);
+                for object in layer_objects.into_iter().flatten() {
+                    let (points, closed) = match (object.get("polyline"), object.get("polygon")) {
+                        (Some(Value::Array(points)), _) => (points, false),
+                        (_, Some(Value::Array(points))) => (points, true),
+                        _ => continue,
+                    };
+                    let points = points
+                        .iter()
+                        .map(|point| {
+                            Vec2::new(number(point, "x") as f32, number(point, "y") as f32)
+                        })
+                        .collect::<Vec<Vec2>>();
+
+                    let properties = object
+                        .get("properties")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|property| {
+                            let name = property.get("name")?.as_str()?.to_string();
+                            let value = match property.get("value")? {
+                                Value::String(value) => value.clone(),
+                                other => other.to_string(),
+                            };
+                            Some((name, value))
+                        })
+                        .collect::<BTreeMap<String, String>>();
+
+                    // the class of an object was called its type before Tiled 1.9
+                    let class = match text(object, "class") {
+                        class if class.is_empty() => text(object, "type"),
+                        class => class,
+                    };
+
+                    let position =
+                        offset + Vec2::new(number(object, "x") as f32, number(object, "y") as f32);
+                    objects.push(TiledObject {
+                        points: place(&points, position, number(object, "rotation") as f32),
+                        closed,
+                        metadata: object_metadata(&text(object, "name"), &class, properties),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn read_tmx(contents: &str) -> Result<Vec<TiledObject>, ConversionError> {
+    let document =
+        roxmltree::Document::parse(contents).map_err(|e| ConversionError::Syntax(e.to_string()))?;
+
+    let map = document.root_element();
+    if map.tag_name().name() != "map" {
+        return Err(ConversionError::Syntax(
+            "the document is not a Tiled map".to_string(),
+        ));
+    }
+
+    let mut objects = Vec::new();
+    read_tmx_layers(map, Vec2::ZERO, &mut objects)?;
+    Ok(objects)
+}
+
+fn read_tmx_layers(
+    parent: roxmltree::Node,
+    parent_offset: Vec2,
+    objects: &mut Vec<TiledObject>,
+) -> Result<(), ConversionError> {
+    let number = |node: roxmltree::Node, name: &str| -> Result<f32, ConversionError> {
+        match node.attribute(name) {
+            Some(value) => value.parse::<f32>().map_err(|_| {
+                ConversionError::Syntax(format!("invalid value {} for {}", value, name))
+            }),
+            None => Ok(0.0),
+        }
+    };
+
+    for layer in parent.children().filter(|node| node.is_element()) {
+        let offset =
+            parent_offset + Vec2::new(number(layer, "offsetx")?, number(layer, "offsety")?);
+
+        match layer.tag_name().name() {
+            "group" => read_tmx_layers(layer, offset, objects)?,
+            "objectgroup" => {
+                for object in layer.children().filter(|node| node.has_tag_name("object")) {
+                    let shape = object
+                        .children()
+                        .find(|node| node.has_tag_name("polyline") || node.has_tag_name("polygon"));
+                    let shape = match shape {
+                        Some(shape) => shape,
+                        None => continue,
+                    };
+
+                    let points = shape
+                        .attribute("points")
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(|pair| {
+                            let coordinates = pair
+                                .split(',')
+                                .map(|c| c.parse::<f32>())
+                                .collect::<Result<Vec<f32>, _>>();
+                            match coordinates.as_deref() {
+                                Ok([x, y]) => Ok(Vec2::new(*x, *y)),
+                                _ => {
+                                    Err(ConversionError::Syntax(format!("invalid point {}", pair)))
+                                }
+                            }
+                        })
+                        .collect::<Result<Vec<Vec2>, ConversionError>>()?;
+
+                    // long values are written as the text of the property
+                    let properties = object
+                        .children()
+                        .filter(|node| node.has_tag_name("properties"))
+                        .flat_map(|node| node.children())
+                        .filter(|node| node.has_tag_name("property"))
+                        .filter_map(|property| {
+                            let name = property.attribute("name")?.to_string();
+                            let value = property
+                                .attribute("value")
+                                .or_else(|| property.text())
+                                .unwrap_or_default()
+                                .to_string();
+                            Some((name, value))
+                        })
+                        .collect::<BTreeMap<String, String>>();
+
+                    let class = object
+                        .attribute("class")
+                        .or_else(|| object.attribute("type"))
+                        .unwrap_or_default();
+
+                    let position = offset + Vec2::new(number(object, "x")?, number(object, "y")?);
+                    objects.push(TiledObject {
+                        points: place(&points, position, number(object, "rotation")?),
+                        closed: shape.has_tag_name("polygon"),
+                        metadata: object_metadata(
+                            object.attribute("name").unwrap_or_default(),
+                            class,
+                            properties,
+                        ),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Raster tracing. The pixels of an image are sorted into one or several layers, the borders
+//! between the pixels of a layer and the others are followed into closed outlines, and cubic
+//! curves are fitted through the middles of the pixel edges, like potrace does. Outer outlines
+//! run counter-clockwise and holes run clockwise, as for the glyphs of a font.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::{prelude::*, render::render_resource::TextureFormat};
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Which pixels of an image are traced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceLayers {
+    /// Opaque pixels darker than `level`, between 0 and 1, or lighter if `invert` is set.
+    Threshold { level: f32, invert: bool },
+    /// Pixels more opaque than `level`, between 0 and 1, as in collision masks.
+    Alpha { level: f32 },
+    /// One layer per color, made of the opaque pixels closest to that color. The curves of
+    /// a layer get its color.
+    Colors(Vec<Color>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceOptions {
+    pub layers: TraceLayers,
+    /// Outlines enclosing fewer pixels than this are dropped, to get rid of speckles.
+    pub min_area: f32,
+    /// Largest distance between the pixel edges and the fitted curves, in pixels.
+    pub max_error: f64,
+    /// Size of a pixel in canvas units.
+    pub scale: f32,
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            layers: TraceLayers::Threshold {
+                level: 0.5,
+                invert: false,
+            },
+            min_area: 4.0,
+            max_error: 1.0,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Pixels whose alpha is below this are background for the threshold and color layers.
+const OPAQUE_LEVEL: f32 = 0.5;
+
+/// Traces the image into closed groups, holes included. The image is centered on the origin,
+/// with y pointing up. The outlines of a layer share an [`OUTLINE_METADATA`] value, so that
+/// the fill of an outline cuts out its holes.
+pub fn trace_image(
+    image: &Image,
+    options: &TraceOptions,
+    num_points: u32,
+) -> Result<Vec<GroupSaveLoad>, ConversionError> {
+    let width = image.texture_descriptor.size.width as usize;
+    let height = image.texture_descriptor.size.height as usize;
+    let pixels = rgba_pixels(image)?;
+
+    let masks: Vec<(Vec<bool>, Option<Color>)> = match &options.layers {
+        TraceLayers::Threshold { level, invert } => {
+            let mask = pixels
+                .iter()
+                .map(|[r, g, b, a]| {
+                    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                    *a >= OPAQUE_LEVEL && ((luminance < *level) != *invert)
+                })
+                .collect();
+            vec![(mask, None)]
+        }
+        TraceLayers::Alpha { level } => {
+            vec![(pixels.iter().map(|[_, _, _, a]| a > level).collect(), None)]
+        }
+        TraceLayers::Colors(colors) => {
+            let nearest = pixels
+                .iter()
+                .map(|[r, g, b, a]| {
+                    if *a < OPAQUE_LEVEL {
+                        return None;
+                    }
+                    let pixel = Vec3::new(*r, *g, *b);
+                    (0..colors.len()).min_by(|i, j| {
+                        let distance = |k: &usize| {
+                            let [r, g, b, _] = colors[*k].as_rgba_f32();
+                            pixel.distance_squared(Vec3::new(r, g, b))
+                        };
+                        distance(i).total_cmp(&distance(j))
+                    })
+                })
+                .collect::<Vec<Option<usize>>>();
+
+            colors
+                .iter()
+                .enumerate()
+                .map(|(k, color)| {
+                    let mask = nearest.iter().map(|layer| *layer == Some(k)).collect();
+                    (mask, Some(*color))
+                })
+                .collect()
+        }
+    };
+
+    let center = Vec2::new(width as f32, height as f32) / 2.0;
+    let mut groups = Vec::new();
+    for (mask, color) in masks {
+        let metadata = BTreeMap::from([(OUTLINE_METADATA.to_string(), new_outline_id())]);
+        for outline in outlines(&mask, width, height) {
+            if signed_area(&outline).abs() < options.min_area {
+                continue;
+            }
+
+            // the middles of the pixel edges smooth out the staircases
+            let n = outline.len();
+            let mut middles = (0..n)
+                .map(|k| (outline[k] + outline[(k + 1) % n]) / 2.0)
+                .map(|p| (p - center) * options.scale)
+                .collect::<Vec<Vec2>>();
+            middles.push(middles[0]);
+
+            // fitting the two halves apart gives at least two curves to latch together
+            let half = n / 2;
+            let mut curves = Vec::new();
+            for part in [&middles[..=half], &middles[half..]] {
+                curves.extend(
+                    StandaloneLut {
+                        path_length: 0.0,
+                        lut: part.to_vec(),
+                    }
+                    .fit_curves(options.max_error * options.scale as f64),
+                );
+            }
+            if curves.len() < 2 {
+                continue;
+            }
+
+            // close the chain exactly, whatever the precision of the fit
+            let start = curves[0].start;
+            if let Some(last) = curves.last_mut() {
+                last.end = start;
+            }
+            let mut group = GroupSaveLoad::from_chain(&curves, color, num_points)?;
+            group.set_metadata(&metadata);
+            groups.push(group);
+        }
+    }
+
+    if groups.is_empty() {
+        return Err(ConversionError::Empty);
+    }
+    Ok(groups)
+}
+
+// rgba values between 0 and 1, row by row from the top. 16-bit images, such as 16-bit PNG
+// files, are loaded by bevy with Uint formats that hold the same values as Unorm ones.
+fn rgba_pixels(image: &Image) -> Result<Vec<[f32; 4]>, ConversionError> {
+    let to_f32 = |value: u8| value as f32 / 255.0;
+    // the channels of 16-bit images are stored in native byte order
+    let channels_16 = || {
+        image
+            .data
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]) as f32 / 65535.0)
+            .collect::<Vec<f32>>()
+    };
+    let pixels = match image.texture_descriptor.format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => image
+            .data
+            .chunks_exact(4)
+            .map(|p| [to_f32(p[0]), to_f32(p[1]), to_f32(p[2]), to_f32(p[3])])
+            .collect(),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => image
+            .data
+            .chunks_exact(4)
+            .map(|p| [to_f32(p[2]), to_f32(p[1]), to_f32(p[0]), to_f32(p[3])])
+            .collect(),
+        TextureFormat::Rg8Unorm => image
+            .data
+            .chunks_exact(2)
+            .map(|p| [to_f32(p[0]), to_f32(p[0]), to_f32(p[0]), to_f32(p[1])])
+            .collect(),
+        TextureFormat::R8Unorm => image
+            .data
+            .iter()
+            .map(|p| [to_f32(*p), to_f32(*p), to_f32(*p), 1.0])
+            .collect(),
+        TextureFormat::Rgba16Unorm | TextureFormat::Rgba16Uint => channels_16()
+            .chunks_exact(4)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .collect(),
+        TextureFormat::Rg16Unorm | TextureFormat::Rg16Uint => channels_16()
+            .chunks_exact(2)
+            .map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        TextureFormat::R16Unorm | TextureFormat::R16Uint => {
+            channels_16().into_iter().map(|p| [p, p, p, 1.0]).collect()
+        }
+        format => {
+            return Err(ConversionError::Unsupported(format!(
+                "images in the {:?} format",
+                format
+            )))
+        }
+    };
+    Ok(pixels)
+}
+
+/// Closed outlines of the pixels of the mask, through the corners of the pixels, with the
+/// pixels of the mask on their left. Pixels that only touch by a corner are joined.
+fn outlines(mask: &[bool], width: usize, height: usize) -> Vec<Vec<Vec2>> {
+    let inside = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && mask[y as usize * width + x as usize]
+    };
+
+    // edges between the corners of the pixels, with y pointing up
+    let top = height as i32;
+    let mut edges: Vec<(IVec2, IVec2)> = Vec::new();
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            if !inside(x, y) {
+                continue;
+            }
+            let (bottom, up) = (top - y - 1, top - y);
+            if !inside(x, y + 1) {
+                edges.push((IVec2::new(x, bottom), IVec2::new(x + 1, bottom)));
+            }
+            if !inside(x + 1, y) {
+                edges.push((IVec2::new(x + 1, bottom), IVec2::new(x + 1, up)));
+            }
+            if !inside(x, y - 1) {
+                edges.push((IVec2::new(x + 1, up), IVec2::new(x, up)));
+            }
+            if !inside(x - 1, y) {
+                edges.push((IVec2::new(x, up), IVec2::new(x, bottom)));
+            }
+        }
+    }
+
+    let mut edges_from: HashMap<IVec2, Vec<usize>> = HashMap::new();
+    for (k, (start, _)) in edges.iter().enumerate() {
+        edges_from.entry(*start).or_default().push(k);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut outlines = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+
+        let mut outline = Vec::new();
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let (start, end) = edges[current];
+            outline.push(start.as_vec2());
+
+            // where two pixels touch by a corner, turning right keeps them together
+            let direction = end - start;
+            let next = edges_from[&end]
+                .iter()
+                .copied()
+                .filter(|k| !used[*k])
+                .min_by_key(|k| {
+                    let (next_start, next_end) = edges[*k];
+                    direction.perp_dot(next_end - next_start)
+                });
+
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        outlines.push(outline);
+    }
+    outlines
+}
+
+// positive for counter-clockwise polygons, with y pointing up
+fn signed_area(polygon: &[Vec2]) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .map(|k| polygon[k].perp_dot(polygon[(k + 1) % n]))
+        .sum::<f32>()
+        / 2.0
+}
@@ -1,4 +1,4 @@
-use crate::inputs::Cursor;
+use crate::inputs::{ui_zoom, Cursor};
 use crate::materials::ButtonMat;
 
 use crate::model::{Globals, MainUi, OnOffMaterial, UiAction, UiBoard};
@@ -61,12 +61,13 @@ pub fn check_mouse_on_ui(
 
     globals: ResMut<Globals>,
 ) {
+    let ui_zoom = ui_zoom(&ui_query, &globals);
     for (ui_transform, mut ui_board) in ui_query.iter_mut() {
         //
         // if mouseclick is within the ui_board, check if it's on a button
         if cursor.within_rect(
             ui_transform.translation.truncate() / globals.scale,
-            ui_board.size,
+            ui_board.size * ui_zoom,
         ) {
             for (button_transform, shader_handle, mut button_interaction, _ui_button) in
                 query.iter_mut()
@@ -75,7 +76,7 @@ pub fn check_mouse_on_ui(
 
                 if cursor.within_rect(
                     button_transform.translation().truncate() / globals.scale,
-                    shader_params.size * 0.95,
+                    shader_params.size * 0.95 * ui_zoom,
                 ) {
                     let bi = button_interaction.deref_mut();
                     *bi = ButtonInteraction::Hovered;
@@ -12,7 +12,7 @@ use crate::model::{
 };
 
 use bevy::render::camera::OrthographicProjection;
-use bevy::{input::mouse::MouseWheel, prelude::*, window::CursorMoved};
+use bevy::{input::mouse::MouseWheel, prelude::*};
 
 pub struct Cursor {
     pub position: Vec2,
@@ -91,6 +91,19 @@ pub enum Action {
     MakeMesh,
     SpawnRoad,
     StartMoveAnchor,
+    ToggleGrid,
+    FrameSelection,
+    FrameAll,
+    SaveProject,
+    LoadProject,
+    // act on the reference image under the cursor
+    ScaleReferenceUp,
+    ScaleReferenceDown,
+    RotateReferenceLeft,
+    RotateReferenceRight,
+    ReferenceOpacityUp,
+    ReferenceOpacityDown,
+    ToggleReferenceLock,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -158,6 +171,12 @@ pub fn send_action(
     let _pressed_z = keyboard_input.just_pressed(KeyCode::Z);
     let _pressed_t = keyboard_input.just_pressed(KeyCode::T);
     let _pressed_delete = keyboard_input.just_pressed(KeyCode::Delete);
+    let _pressed_f = keyboard_input.just_pressed(KeyCode::F);
+    let _pressed_r = keyboard_input.just_pressed(KeyCode::R);
+    let _pressed_o = keyboard_input.just_pressed(KeyCode::O);
+    let _pressed_k = keyboard_input.just_pressed(KeyCode::K);
+    let _pressed_lbracket = keyboard_input.just_pressed(KeyCode::LBracket);
+    let _pressed_rbracket = keyboard_input.just_pressed(KeyCode::RBracket);
 
     // match keys / mouse buttons / mouse wheel combination and send event to corresponding action
     match (
@@ -174,50 +193,64 @@ pub fn send_action(
         (true, true, false) if _pressed_h => action_event_writer.send(Action::HideControls),
         (false, true, false) if _pressed_s => action_event_writer.send(Action::Save),
         (false, true, false) if _pressed_l => action_event_writer.send(Action::Load),
+        (true, true, false) if _pressed_s => action_event_writer.send(Action::SaveProject),
+        (true, true, false) if _pressed_l => action_event_writer.send(Action::LoadProject),
         (false, true, false) if _pressed_z => action_event_writer.send(Action::Undo),
         (true, true, false) if _pressed_z => action_event_writer.send(Action::Redo),
         (false, true, false) if mouse_wheel_up => action_event_writer.send(Action::ScaleUp),
         (false, true, false) if mouse_wheel_down => action_event_writer.send(Action::ScaleDown),
         (false, false, false) if _pressed_delete => action_event_writer.send(Action::Delete(false)),
         (true, false, false) if _pressed_t => action_event_writer.send(Action::ComputeLut),
+        (true, false, false) if _pressed_g => action_event_writer.send(Action::ToggleGrid),
+        (false, false, false) if _pressed_f => action_event_writer.send(Action::FrameSelection),
+        (true, false, false) if _pressed_f => action_event_writer.send(Action::FrameAll),
+        (false, false, false) if _pressed_rbracket => {
+            action_event_writer.send(Action::ScaleReferenceUp)
+        }
+        (false, false, false) if _pressed_lbracket => {
+            action_event_writer.send(Action::ScaleReferenceDown)
+        }
+        (false, false, false) if _pressed_r => {
+            action_event_writer.send(Action::RotateReferenceLeft)
+        }
+        (true, false, false) if _pressed_r => {
+            action_event_writer.send(Action::RotateReferenceRight)
+        }
+        (false, false, false) if _pressed_o => action_event_writer.send(Action::ReferenceOpacityUp),
+        (true, false, false) if _pressed_o => {
+            action_event_writer.send(Action::ReferenceOpacityDown)
+        }
+        (false, false, false) if _pressed_k => {
+            action_event_writer.send(Action::ToggleReferenceLock)
+        }
 
         _ => {}
     }
 }
 
+// the cursor is converted every frame, since the camera can move or zoom under a still cursor
 pub fn record_mouse_events_system(
-    mut cursor_moved_events: EventReader<CursorMoved>,
     mouse_button_input: Res<Input<MouseButton>>,
     mut cursor_res: ResMut<Cursor>,
-    mut windows: ResMut<Windows>,
-    cam_transform_query: Query<&Transform, With<OrthographicProjection>>,
-    cam_ortho_query: Query<&OrthographicProjection>,
+    windows: Res<Windows>,
+    cam_query: Query<(&Transform, &OrthographicProjection)>,
     globals: Res<Globals>,
 ) {
-    for event in cursor_moved_events.iter() {
-        let cursor_in_pixels = event.position; // lower left is origin
-        let window_size = Vec2::new(
-            windows.get_primary_mut().unwrap().width(),
-            windows.get_primary_mut().unwrap().height(),
-        );
-
-        let screen_position = cursor_in_pixels - window_size / 2.0;
-
-        let cam_transform = cam_transform_query.iter().next().unwrap();
-
-        // this variable currently has no effect
-        let mut scale = 1.0;
+    // lower left is origin
+    let window = windows.get_primary();
+    let cursor_in_pixels = window.and_then(|window| window.cursor_position());
 
-        for ortho in cam_ortho_query.iter() {
-            scale = ortho.scale;
-        }
-
-        let cursor_vec4: Vec4 = cam_transform.compute_matrix()
-            * screen_position.extend(0.0).extend(1.0 / (scale))
-            * scale;
-
-        let cursor_pos = Vec2::new(cursor_vec4.x, cursor_vec4.y);
-        cursor_res.position = cursor_pos / globals.scale;
+    if let (Some(window), Some(cursor_in_pixels), Some((cam_transform, ortho))) =
+        (window, cursor_in_pixels, cam_query.iter().next())
+    {
+        let window_size = Vec2::new(window.width(), window.height());
+        cursor_res.position = cursor_to_canvas(
+            cursor_in_pixels,
+            window_size,
+            cam_transform,
+            ortho.scale,
+            globals.scale,
+        );
         cursor_res.pos_relative_to_click = cursor_res.position - cursor_res.last_click_position;
     }
 
@@ -227,6 +260,33 @@ pub fn record_mouse_events_system(
     }
 }
 
+/// Converts a cursor position in pixels, from the lower left corner of the window, to canvas
+/// coordinates, through the camera and its zoom and then [`Globals::scale`].
+pub fn cursor_to_canvas(
+    cursor_in_pixels: Vec2,
+    window_size: Vec2,
+    cam_transform: &Transform,
+    projection_scale: f32,
+    globals_scale: f32,
+) -> Vec2 {
+    let screen_position = cursor_in_pixels - window_size / 2.0;
+    let world = cam_transform.translation
+        + cam_transform.rotation * (screen_position * projection_scale).extend(0.0);
+    world.truncate() / globals_scale
+}
+
+/// How much larger the UI board is drawn than at [`Globals::scale`], once the camera zoom that
+/// keeps it at the same size on the screen is applied.
+pub fn ui_zoom(
+    ui_query: &Query<(&Transform, &mut UiBoard), With<MainUi>>,
+    globals: &Globals,
+) -> f32 {
+    ui_query
+        .iter()
+        .next()
+        .map_or(1.0, |(transform, _)| transform.scale.x / globals.scale)
+}
+
 type IsLatched = bool;
 
 pub enum MouseClickEvent {
@@ -264,6 +324,7 @@ pub fn check_mouseclick_on_objects(
 ) {
     if mouse_button_input.just_pressed(MouseButton::Left) {
         let scale = globals.scale;
+        let ui_zoom = ui_zoom(&ui_query, &globals);
 
         // TODO: too much boilerplate to check if a button is on...
         let mut spawn_button_on = false;
@@ -300,7 +361,7 @@ pub fn check_mouseclick_on_objects(
             //
             if cursor.within_rect(
                 button_transform.translation().truncate() / scale,
-                shader_params.size * 0.95,
+                shader_params.size * 0.95 * ui_zoom,
             ) {
                 // this sends into nothingness
                 mouse_event_writer.send(MouseClickEvent::OnUiButton(ui_button.clone()));
@@ -319,7 +380,7 @@ pub fn check_mouseclick_on_objects(
 
             if cursor.within_rect(
                 transform.translation().truncate() / scale,
-                shader_params.size * 1.15,
+                shader_params.size * 1.15 * ui_zoom,
             ) {
                 mouse_event_writer.send(MouseClickEvent::OnColorButton((
                     shader_params.color.clone().into(),
@@ -334,7 +395,10 @@ pub fn check_mouseclick_on_objects(
         for (ui_transform, mut ui_board) in ui_query.iter_mut() {
             if
             // ui_board.action == UiAction::None &&
-            cursor.within_rect(ui_transform.translation.truncate() / scale, ui_board.size) {
+            cursor.within_rect(
+                ui_transform.translation.truncate() / scale,
+                ui_board.size * ui_zoom,
+            ) {
                 mouse_event_writer.send(MouseClickEvent::OnUiBoard);
                 ui_board.action = UiAction::MovingUi;
                 return ();
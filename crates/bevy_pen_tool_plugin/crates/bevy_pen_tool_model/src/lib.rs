@@ -1,15 +1,24 @@
+pub mod formats;
 pub mod inputs;
 pub mod materials;
 pub mod mesh;
 pub mod model;
 mod spawner;
 
+pub use formats::*;
 pub use inputs::*;
 pub use materials::*;
 pub use mesh::*;
 pub use model::*;
 pub use spawner::*;
 
+// the look-up table asset, its loaders and the code generation are shared with games that
+// only play paths back
+pub use bevy_pen_tool_runtime::{
+    lut_to_rust, luts_to_rust, module_name, register_scene_types, CurvePositions, GroupFile,
+    GroupLoader, LutLoader, PenAssetsPlugin, PenFill, PenFollower, PenMeshData, PenRoad,
+};
+
 use bevy::{prelude::*, sprite::Material2dPlugin};
 
 pub struct SpawnerPlugin;
@@ -111,9 +120,35 @@ impl Plugin for SpawnerPlugin {
                     .with_system(send_action.after("mouse_release")),
             );
         // .add_system(mouse_release_actions.exclusive_system().at_end());
+
+        register_model_types(app);
     }
 }
 
+/// Registers the curves, groups, their ids and the mesh components for reflection, so that
+/// they can be used in scenes and serialized through the `TypeRegistry`.
+pub fn register_model_types(app: &mut App) {
+    app.register_type::<Bezier>()
+        .register_type::<BezierId>()
+        .register_type::<BezierPositions>()
+        .register_type::<Anchor>()
+        .register_type::<AnchorEdge>()
+        .register_type::<LatchData>()
+        .register_type::<Latches>()
+        .register_type::<CurveMetadata>()
+        .register_type::<Group>()
+        .register_type::<GroupId>()
+        .register_type::<GroupCurves>()
+        .register_type::<CurveHandles>()
+        .register_type::<GroupEnds>()
+        .register_type::<GroupLut>()
+        .register_type::<GroupSaveLoad>()
+        .register_type::<StandaloneLut>()
+        .register_type::<SourceGroup>()
+        .register_type::<StartMovingMesh>();
+    register_scene_types(app);
+}
+
 fn setup(asset_server: Res<AssetServer>, mut meshes: ResMut<Assets<Mesh>>, mut maps: ResMut<Maps>) {
     asset_server.watch_for_changes().unwrap();
 
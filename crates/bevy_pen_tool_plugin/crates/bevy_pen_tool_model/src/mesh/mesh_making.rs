@@ -14,6 +14,7 @@ use lyon::tessellation::path::Path;
 use lyon::tessellation::{FillOptions, FillTessellator, VertexBuffers};
 
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -22,12 +23,21 @@ use std::collections::HashSet;
 //
 //
 
-pub type MeshId = u64;
+// shared with games, which find it in the scenes exported by the editor
+pub use bevy_pen_tool_runtime::{MeshId, PenMesh};
 
-#[derive(Component, Clone, Debug)]
-pub struct PenMesh {
-    pub id: MeshId,
+/// The group a fill or road mesh was generated from.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Default, Reflect)]
+#[reflect(Component)]
+pub struct SourceGroup(pub GroupId);
+
+// side-car information saved next to an exported mesh, with a .meta extension
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MeshMeta {
+    pub center_of_mass: Vec2,
+    pub position: Vec2,
     pub bounding_box: (Vec2, Vec2),
+    pub color: Vec4,
 }
 
 pub struct MinsMaxes {
@@ -70,7 +80,8 @@ impl MinsMaxes {
 //     pub bounding_box: (Vec2, Vec2),
 // }
 
-#[derive(Component, Clone, Debug)]
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
 pub struct StartMovingMesh {
     pub start_position: Vec2,
 }
@@ -98,12 +109,15 @@ pub fn make_road(
                 //
                 // check whether the curve set is part of the same group
                 let mut group_id_set = HashSet::new();
+                let mut outline = None;
 
                 for curve in &curve_set {
                     if let Some(handle_entity) = maps.bezier_map.get(&curve) {
                         //
                         let bezier = curves.get(&handle_entity.handle).unwrap();
                         group_id_set.insert(bezier.group);
+                        outline =
+                            outline.or_else(|| bezier.metadata.get(OUTLINE_METADATA).cloned());
                     }
                 }
                 if group_id_set.iter().count() != 1 {
@@ -126,89 +140,15 @@ pub fn make_road(
 
                     let center_of_mass = group.center_of_mass(&bezier_assets);
 
-                    let num_points = globals.group_lut_num_points;
-
-                    let crop = 0.000001;
-                    let t_range: Vec<f32> = (0..num_points)
-                        .map(|x| {
-                            (x as f32) / (num_points as f32 - 0.99999) / (1.0 + 2.0 * crop) + crop
-                        })
-                        .collect();
-
-                    let mut mesh_contour: Vec<Vec3> = Vec::new();
-
-                    for t in t_range {
-                        let position = group.compute_position_with_lut(t) - center_of_mass;
-                        let normal = group
-                            .compute_normal_with_bezier(&bezier_assets, t as f64)
-                            .normalize();
-
-                        let v1 = Vec3::new(
-                            (position.x + normal.x * globals.road_width) as f32,
-                            (position.y + normal.y * globals.road_width) as f32,
-                            globals.z_pos.road,
-                        );
-
-                        let v2 = Vec3::new(
-                            (position.x - normal.x * globals.road_width) as f32,
-                            (position.y - normal.y * globals.road_width) as f32,
-                            globals.z_pos.road,
-                        );
-
-                        mesh_contour.push(v1);
-                        mesh_contour.push(v2);
-                    }
-
-                    mesh_contour.push(mesh_contour[0]);
-                    mesh_contour.push(mesh_contour[1]);
-
-                    // indices
-                    let mut new_indices: Vec<u32> = Vec::new();
-                    // for kk in 0..(num_points - 1) {
-                    for kk in 0..(num_points) {
-                        let k = kk * 2;
-                        let mut local_inds = vec![k, (k + 1), (k + 2), (k + 1), (k + 3), (k + 2)];
-                        new_indices.append(&mut local_inds);
-                    }
-
-                    // uvs
-                    let path_length = group.standalone_lut.path_length;
-                    let num_repeats = path_length / 100.0;
-                    let mut mesh_attr_uvs: Vec<[f32; 2]> = Vec::new();
-                    for k in 0..(num_points + 1) * 2 {
-                        // let (pos_x, pos_y) = (pos[0], pos[1]);
-                        let v = k as f32 / (num_points as f32 / num_repeats);
-                        mesh_attr_uvs.push([v % 1.0, (k as f32) % 2.0]);
-                    }
-
-                    let mut mesh_pos_attributes: Vec<[f32; 3]> = Vec::new();
-
-                    // show points from look-up table
                     let color = globals.picked_color.unwrap();
-                    let mut colors = Vec::new();
-                    let mut normals = Vec::new();
-
-                    let mut mins_maxes = MinsMaxes::default();
-
-                    for position in mesh_contour {
-                        mesh_pos_attributes.push([position.x, position.y, 0.0]);
-
-                        colors.push([color.r(), color.g(), color.b(), 1.0]);
-                        normals.push([0.0, 0.0, 1.0]);
-
-                        mins_maxes.update(Vec2::new(position.x, position.y));
-                    }
-
-                    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh_pos_attributes.clone());
-
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
-
-                    mesh.set_indices(Some(Indices::U32(new_indices)));
-
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh_attr_uvs);
+                    let (mesh, bounding_box) = road_mesh(
+                        group,
+                        &bezier_assets,
+                        center_of_mass,
+                        globals.group_lut_num_points,
+                        globals.road_width,
+                        color,
+                    );
 
                     let texture_handle = maps.textures.get("single_lane_road").unwrap();
 
@@ -235,10 +175,8 @@ pub fn make_road(
                             transform: road_transform,
                             ..default()
                         })
-                        .insert(PenMesh {
-                            id,
-                            bounding_box: mins_maxes.to_vec2_pair(),
-                        })
+                        .insert(PenMesh { id, bounding_box })
+                        .insert(SourceGroup(group.id))
                         .id();
 
                     maps.mesh_map.insert(id, entity);
@@ -273,27 +211,59 @@ pub fn make_fill_mesh(
                 //
                 // check whether the curve set is part of the same group
                 let mut group_id_set = HashSet::new();
+                let mut outline = None;
 
                 for curve in &curve_set {
                     if let Some(handle_entity) = maps.bezier_map.get(&curve) {
                         //
                         let bezier = curves.get(&handle_entity.handle).unwrap();
                         group_id_set.insert(bezier.group);
+                        outline =
+                            outline.or_else(|| bezier.metadata.get(OUTLINE_METADATA).cloned());
                     }
                 }
                 if group_id_set.iter().count() != 1 {
                     info!("cannot spawn road from curves in different groups");
                     return;
                 }
-                if let Some(group_handle) = maps.group_map.get(group_id_set.iter().next().unwrap())
-                // unwrap never fails
-                {
-                    let group = groups.get_mut(&group_handle).unwrap();
-
+                let group_id = *group_id_set.iter().next().unwrap(); // unwrap never fails
+                if let Some(group_handle) = maps.group_map.get(&group_id) {
                     let bezier_assets = curves
                         .iter()
                         .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
 
+                    // the other contours of the same glyph or traced layer that lie inside the
+                    // filled group are cut out, like the counters of a letter
+                    let contour_ids = curves
+                        .iter()
+                        .filter(|(_, bezier)| {
+                            outline.is_some()
+                                && bezier.group != group_id
+                                && bezier.metadata.get(OUTLINE_METADATA) == outline.as_ref()
+                        })
+                        .map(|(_, bezier)| bezier.group)
+                        .collect::<HashSet<GroupId>>();
+                    let mut closed_luts = Vec::new();
+                    for other_id in contour_ids.iter() {
+                        let other_handle = match maps.group_map.get(other_id) {
+                            Some(handle) => handle,
+                            None => continue,
+                        };
+                        if let Some(other) = groups.get_mut(other_handle) {
+                            other.find_connected_ends(&bezier_assets, maps.bezier_map.clone());
+                            if other.is_closed() {
+                                other.group_lut(&bezier_assets, maps.bezier_map.clone());
+                                other.compute_standalone_lut(
+                                    &bezier_assets,
+                                    globals.group_lut_num_points,
+                                );
+                                closed_luts.push(other.standalone_lut.lut.clone());
+                            }
+                        }
+                    }
+
+                    let group = groups.get_mut(&group_handle).unwrap();
+
                     group.find_connected_ends(&bezier_assets, maps.bezier_map.clone());
 
                     group.group_lut(&bezier_assets, maps.bezier_map.clone());
@@ -301,108 +271,26 @@ pub fn make_fill_mesh(
 
                     let center_of_mass = group.center_of_mass(&bezier_assets);
 
-                    let mut path_builder = Path::builder();
-
-                    let lut = group.standalone_lut.lut.clone();
-
-                    let first = lut[0];
-                    path_builder.begin(point(first.x, first.y));
-
-                    let resto: Vec<Vec2> = lut[1..].to_vec();
-
-                    for e in resto.iter() {
-                        path_builder.line_to(point(e.x, e.y));
-                    }
-
-                    path_builder.end(true);
-                    let path = path_builder.build();
-
-                    // Create the destination vertex and index buffers.
-                    let mut buffers: VertexBuffers<Point, u16> = VertexBuffers::new();
-
-                    {
-                        let mut vertex_builder = simple_builder(&mut buffers);
-
-                        // Create the tessellator.
-                        let mut tessellator = FillTessellator::new();
-
-                        // Compute the tessellation.
-                        let result = tessellator.tessellate_path(
-                            &path,
-                            &FillOptions::default(),
-                            &mut vertex_builder,
-                        );
-                        assert!(result.is_ok());
-                    }
-
-                    let mut mesh_pos_attributes: Vec<[f32; 3]> = Vec::new();
-                    let mut mesh_attr_uvs: Vec<[f32; 2]> = Vec::new();
-                    let mut new_indices: Vec<u32> = Vec::new();
-
-                    // show points from look-up table
                     let color = globals.picked_color.unwrap();
-                    let mut colors = Vec::new();
-
-                    let mut mins_maxes = MinsMaxes::default();
-
-                    for position in buffers.vertices[..].iter() {
-                        let pos_x = position.x - center_of_mass.x;
-                        let pos_y = position.y - center_of_mass.y;
-                        mesh_pos_attributes.push([pos_x, pos_y, 0.0]);
-
-                        colors.push([color.r(), color.g(), color.b(), 1.0]);
-
-                        mins_maxes.update(Vec2::new(pos_x, pos_y));
-                    }
-
-                    //////////////////////////// uvs ///////////////////////////////
-                    let xs: Vec<f32> = mesh_pos_attributes.iter().map(|v| v[0]).collect();
-                    let ys: Vec<f32> = mesh_pos_attributes.iter().map(|v| v[1]).collect();
-
-                    use std::cmp::Ordering;
-
-                    fn bounds(v: &Vec<f32>) -> (f32, f32) {
-                        let max_v: &f32 = v
-                            .iter()
-                            .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
-                            .unwrap();
-
-                        let min_v: &f32 = v
-                            .iter()
-                            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
-                            .unwrap();
-
-                        return (*min_v, *max_v);
-                    }
-
-                    let bounds_x = bounds(&xs);
-                    let size_x = bounds_x.1 - bounds_x.0;
-                    let bounds_y = bounds(&ys);
-                    let size_y = bounds_y.1 - bounds_y.0;
-
-                    let mut normals = Vec::new();
-                    for pos in &mesh_pos_attributes {
-                        let (pos_x, pos_y) = (pos[0], pos[1]);
-
-                        mesh_attr_uvs.push([
-                            1.0 * (pos_x - bounds_x.0) / size_x,
-                            1.0 * (pos_y - bounds_y.0) / size_y,
-                        ]);
-
-                        normals.push([0.0, 0.0, 1.0]);
-                    }
-
-                    for ind in buffers.indices[..].iter().rev() {
-                        new_indices.push(ind.clone() as u32);
-                    }
-
-                    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh_pos_attributes.clone());
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
-                    mesh.set_indices(Some(Indices::U32(new_indices)));
-                    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh_attr_uvs);
+                    let holes = closed_luts
+                        .into_iter()
+                        .filter(|lut| {
+                            lut.iter()
+                                .all(|p| polygon_contains(&group.standalone_lut.lut, *p))
+                        })
+                        .collect::<Vec<Vec<Vec2>>>();
+                    let (mesh, bounding_box) = match fill_mesh_with_holes(
+                        &group.standalone_lut.lut,
+                        &holes,
+                        center_of_mass,
+                        color,
+                    ) {
+                        Some(mesh) => mesh,
+                        None => {
+                            info!("cannot fill a group that does not enclose an area");
+                            return;
+                        }
+                    };
 
                     let mut fill_transform =
                         Transform::from_translation(center_of_mass.extend(globals.z_pos.fill));
@@ -428,8 +316,9 @@ pub fn make_fill_mesh(
                         })
                         .insert(PenMesh {
                             id,
-                            bounding_box: mins_maxes.to_vec2_pair(), // bounding box relative to center of mass
+                            bounding_box, // bounding box relative to center of mass
                         })
+                        .insert(SourceGroup(group.id))
                         .id();
 
                     maps.mesh_map.insert(id, entity);
@@ -440,3 +329,192 @@ pub fn make_fill_mesh(
         }
     }
 }
+
+/// Strip of width `2 * road_width` along the group, with its vertices relative to
+/// `center_of_mass`. The texture repeats every 100 units along the path. Returns the mesh and
+/// its bounding box.
+pub fn road_mesh(
+    group: &Group,
+    bezier_assets: &BezierAssets,
+    center_of_mass: Vec2,
+    num_points: u32,
+    road_width: f32,
+    color: Color,
+) -> (Mesh, (Vec2, Vec2)) {
+    let crop = 0.000001;
+    let t_range: Vec<f32> = (0..num_points)
+        .map(|x| (x as f32) / (num_points as f32 - 0.99999) / (1.0 + 2.0 * crop) + crop)
+        .collect();
+
+    let mut mesh_contour: Vec<Vec2> = Vec::new();
+
+    for t in t_range {
+        let position = group.compute_position_with_lut(t) - center_of_mass;
+        let normal = group
+            .compute_normal_with_bezier(bezier_assets, t as f64)
+            .normalize();
+
+        mesh_contour.push(position + normal * road_width);
+        mesh_contour.push(position - normal * road_width);
+    }
+
+    mesh_contour.push(mesh_contour[0]);
+    mesh_contour.push(mesh_contour[1]);
+
+    // indices
+    let mut new_indices: Vec<u32> = Vec::new();
+    for kk in 0..(num_points) {
+        let k = kk * 2;
+        let mut local_inds = vec![k, (k + 1), (k + 2), (k + 1), (k + 3), (k + 2)];
+        new_indices.append(&mut local_inds);
+    }
+
+    // uvs
+    let path_length = group.standalone_lut.path_length;
+    let num_repeats = path_length / 100.0;
+    let mut mesh_attr_uvs: Vec<[f32; 2]> = Vec::new();
+    for k in 0..(num_points + 1) * 2 {
+        let v = k as f32 / (num_points as f32 / num_repeats);
+        mesh_attr_uvs.push([v % 1.0, (k as f32) % 2.0]);
+    }
+
+    let mut mesh_pos_attributes: Vec<[f32; 3]> = Vec::new();
+    let mut colors = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut mins_maxes = MinsMaxes::default();
+
+    for position in mesh_contour {
+        mesh_pos_attributes.push([position.x, position.y, 0.0]);
+
+        colors.push([color.r(), color.g(), color.b(), 1.0]);
+        normals.push([0.0, 0.0, 1.0]);
+
+        mins_maxes.update(position);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh_pos_attributes);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(new_indices)));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh_attr_uvs);
+
+    (mesh, mins_maxes.to_vec2_pair())
+}
+
+/// Triangulates the area enclosed by the look-up table of a group, with the vertices relative
+/// to `center_of_mass`. Returns the mesh and its bounding box, or `None` when the look-up
+/// table does not enclose an area.
+pub fn fill_mesh(lut: &[Vec2], center_of_mass: Vec2, color: Color) -> Option<(Mesh, (Vec2, Vec2))> {
+    fill_mesh_with_holes(lut, &[], center_of_mass, color)
+}
+
+/// Same as [`fill_mesh`], without the areas enclosed by the `holes`. Holes inside holes are
+/// filled again, following the even-odd rule.
+pub fn fill_mesh_with_holes(
+    lut: &[Vec2],
+    holes: &[Vec<Vec2>],
+    center_of_mass: Vec2,
+    color: Color,
+) -> Option<(Mesh, (Vec2, Vec2))> {
+    if lut.len() < 3 {
+        return None;
+    }
+
+    let mut path_builder = Path::builder();
+
+    for contour in std::iter::once(lut).chain(holes.iter().map(|hole| &hole[..])) {
+        if contour.len() < 3 {
+            continue;
+        }
+        let first = contour[0];
+        path_builder.begin(point(first.x, first.y));
+
+        for e in contour[1..].iter() {
+            path_builder.line_to(point(e.x, e.y));
+        }
+
+        path_builder.end(true);
+    }
+    let path = path_builder.build();
+
+    // Create the destination vertex and index buffers.
+    let mut buffers: VertexBuffers<Point, u16> = VertexBuffers::new();
+
+    {
+        let mut vertex_builder = simple_builder(&mut buffers);
+
+        // Create the tessellator.
+        let mut tessellator = FillTessellator::new();
+
+        // Compute the tessellation.
+        tessellator
+            .tessellate_path(&path, &FillOptions::default(), &mut vertex_builder)
+            .ok()?;
+    }
+
+    if buffers.vertices.is_empty() {
+        return None;
+    }
+
+    let mut mesh_pos_attributes: Vec<[f32; 3]> = Vec::new();
+    let mut mesh_attr_uvs: Vec<[f32; 2]> = Vec::new();
+    let mut new_indices: Vec<u32> = Vec::new();
+    let mut colors = Vec::new();
+
+    let mut mins_maxes = MinsMaxes::default();
+
+    for position in buffers.vertices[..].iter() {
+        let pos_x = position.x - center_of_mass.x;
+        let pos_y = position.y - center_of_mass.y;
+        mesh_pos_attributes.push([pos_x, pos_y, 0.0]);
+
+        colors.push([color.r(), color.g(), color.b(), 1.0]);
+
+        mins_maxes.update(Vec2::new(pos_x, pos_y));
+    }
+
+    //////////////////////////// uvs ///////////////////////////////
+    let (min, max) = mins_maxes.to_vec2_pair();
+    let size = max - min;
+
+    let mut normals = Vec::new();
+    for pos in &mesh_pos_attributes {
+        let (pos_x, pos_y) = (pos[0], pos[1]);
+
+        mesh_attr_uvs.push([(pos_x - min.x) / size.x, (pos_y - min.y) / size.y]);
+
+        normals.push([0.0, 0.0, 1.0]);
+    }
+
+    for ind in buffers.indices[..].iter().rev() {
+        new_indices.push(*ind as u32);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh_pos_attributes);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(new_indices)));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh_attr_uvs);
+
+    Some((mesh, (min, max)))
+}
+
+/// Even-odd test of a point against a closed polygon.
+pub fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for k in 0..n {
+        let (a, b) = (polygon[k], polygon[(k + n - 1) % n]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (b.x - a.x) * (point.y - a.y) / (b.y - a.y)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
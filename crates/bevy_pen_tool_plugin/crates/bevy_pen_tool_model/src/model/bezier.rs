@@ -2,12 +2,18 @@ use crate::inputs::*;
 // use crate::util::materials::*;
 use crate::model::*;
 
-use bevy::{asset::HandleId, prelude::*, reflect::TypeUuid, utils::Uuid};
+use bevy::{
+    asset::HandleId,
+    prelude::*,
+    reflect::{FromReflect, TypeUuid},
+    utils::Uuid,
+};
 
 use serde::{Deserialize, Serialize};
 
 use rand::prelude::*;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -15,6 +21,7 @@ use flo_curves::bezier::BezierCurve;
 use flo_curves::bezier::Curve;
 use flo_curves::*;
 
+#[cfg(feature = "inspector")]
 use bevy_inspector_egui::Inspectable;
 
 pub type BezierAssets<'a> = HashMap<bevy::asset::HandleId, &'a Bezier>;
@@ -31,7 +38,8 @@ pub struct SpawningCurve {
 
 pub type BezierHistId = u64;
 
-#[derive(Debug, Clone, Default, Inspectable)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
 pub struct BezierHist {
     pub positions: BezierPositions,
     pub color: Option<Color>,
@@ -45,7 +53,7 @@ impl From<&Bezier> for BezierHist {
         Self {
             positions: bezier.positions.clone(),
             color: None,
-            latches: bezier.latches.clone(),
+            latches: bezier.latches.0.clone(),
             id: bezier.id.into(),
             do_send_to_history: false,
         }
@@ -94,13 +102,14 @@ pub struct GroupHist {
 impl From<&Group> for GroupHist {
     fn from(group: &Group) -> Self {
         Self {
-            bezier_handles: group.bezier_handles.clone(),
-            ends: group.ends.clone(),
+            bezier_handles: group.bezier_handles.0.clone(),
+            ends: group.ends.0.clone(),
         }
     }
 }
 
-#[derive(Debug, Clone, Inspectable)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
 pub enum HistoryAction {
     MovedAnchor {
         bezier_id: BezierHistId,
@@ -158,7 +167,8 @@ impl Default for HistoryAction {
     }
 }
 
-#[derive(Debug, Clone, Inspectable)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
 pub struct History {
     pub actions: Vec<HistoryAction>,
     pub index: i32,
@@ -174,7 +184,9 @@ impl Default for History {
 }
 
 /// Either the start point or the end point of a Bezier curve.
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize, Hash, Inspectable)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize, Hash, Reflect, FromReflect)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
+#[reflect_value(PartialEq, Hash, Serialize, Deserialize)]
 pub enum AnchorEdge {
     Start,
     End,
@@ -232,8 +244,10 @@ pub struct SpawnMids {
 /// A Bezier curve is defined by four points: the start and end points (also called anchor edges throughout the crate)
 /// and two control points. The [`Anchor::All`] variant is used to refer to all four points.
 #[derive(
-    PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Copy, Inspectable, Hash, Component,
+    PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Copy, Hash, Component, Reflect, FromReflect,
 )]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
+#[reflect_value(PartialEq, Hash, Serialize, Deserialize)]
 pub enum Anchor {
     Start,
     End,
@@ -289,7 +303,8 @@ impl Anchor {
     }
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Deserialize, Inspectable)]
+#[derive(Debug, Clone, Serialize, PartialEq, Deserialize, Reflect, FromReflect)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
 pub struct LatchData {
     pub latched_to_id: BezierId,
     pub self_edge: AnchorEdge,
@@ -315,7 +330,9 @@ pub struct BezierCoord2 {
 }
 
 /// Identifier for a Bezier curve. Collisions are possible but very unlikely.
-#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Inspectable)]
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Reflect, FromReflect)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
+#[reflect_value(PartialEq, Hash, Serialize, Deserialize)]
 pub struct BezierId(pub HandleId);
 
 impl From<HandleId> for BezierId {
@@ -378,7 +395,28 @@ pub struct MovingAnchor {
     pub follow_mouse: bool, // if false, the anchor is the adjoint of actively moving anchor
 }
 
-#[derive(Debug, Clone, TypeUuid, Serialize, Deserialize)]
+/// Latches of a curve, by the edge of the curve that is latched.
+///
+/// Bevy does not reflect std maps, so the map is reflected as a value through its serde impl.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Latches(pub HashMap<AnchorEdge, LatchData>);
+
+deref_to_inner!(Latches, HashMap<AnchorEdge, LatchData>);
+
+/// Free-form properties of a curve, reflected as a value like [`Latches`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CurveMetadata(pub BTreeMap<String, String>);
+
+deref_to_inner!(CurveMetadata, BTreeMap<String, String>);
+
+// the entity of a curve only makes sense in the running editor, so it is left out of the
+// reflected curve but kept by its serde type data
+#[derive(Debug, Clone, TypeUuid, Serialize, Deserialize, Reflect, FromReflect)]
+#[reflect(Serialize, Deserialize)]
 #[uuid = "8cb22c5d-5ab0-4912-8833-ab46062b7d38"] // do not change this uuid without changing the Default impl for BezierId
 pub struct Bezier {
     pub positions: BezierPositions,
@@ -388,10 +426,14 @@ pub struct Bezier {
     pub do_compute_lut: bool,
     pub lut: LutDistance,
     pub id: BezierId,
-    pub latches: HashMap<AnchorEdge, LatchData>,
+    pub latches: Latches,
     pub potential_latch: Option<LatchData>,
     pub group: GroupId,
+    #[reflect(ignore)]
     pub entity: Option<Entity>,
+    /// Free-form properties of the curve, such as the properties of an imported map feature.
+    #[serde(default)]
+    pub metadata: CurveMetadata,
 }
 
 impl Default for Bezier {
@@ -403,11 +445,12 @@ impl Default for Bezier {
             potential_latch: None,
             group: GroupId::default(),
             lut: LutDistance::default(),
-            latches: HashMap::new(),
+            latches: Latches::default(),
             id: BezierId::default(),
             positions: BezierPositions::default(),
             previous_positions: BezierPositions::default(),
             entity: None,
+            metadata: CurveMetadata::default(),
             // ..Default::default()
         }
     }
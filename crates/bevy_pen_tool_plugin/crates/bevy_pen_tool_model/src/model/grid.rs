@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Background grid of the canvas, drawn under the curves. Its lines are snap targets for the
+/// anchors while it is visible, and `snap` turns snapping to the grid and the guides on or off.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Grid {
+    pub visible: bool,
+    /// Distance between two main lines, in canvas units.
+    pub spacing: f32,
+    /// Number of cells between two main lines, drawn with fainter lines.
+    pub subdivisions: u32,
+    pub snap: bool,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            spacing: 100.0,
+            subdivisions: 4,
+            snap: true,
+        }
+    }
+}
+
+impl Grid {
+    /// Distance between two lines, main or not.
+    pub fn step(&self) -> f32 {
+        self.spacing / self.subdivisions.max(1) as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GuideAxis {
+    /// A line of constant y.
+    Horizontal,
+    /// A line of constant x.
+    Vertical,
+}
+
+/// A guide line that spans the whole canvas, pulled from the edge of the window.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Guide {
+    pub id: u64,
+    pub axis: GuideAxis,
+    /// The y of a horizontal guide, or the x of a vertical one, in canvas units.
+    pub position: f32,
+}
+
+impl Guide {
+    pub fn new(axis: GuideAxis, position: f32) -> Self {
+        Self {
+            id: thread_rng().gen(),
+            axis,
+            position,
+        }
+    }
+
+    /// Distance between a point of the canvas and the guide.
+    pub fn distance(&self, point: Vec2) -> f32 {
+        match self.axis {
+            GuideAxis::Horizontal => (point.y - self.position).abs(),
+            GuideAxis::Vertical => (point.x - self.position).abs(),
+        }
+    }
+}
+
+/// Moves each coordinate of the point onto the closest guide or grid line, if one is within
+/// `max_distance` canvas units. Guides are preferred to the grid at equal distance. Nothing
+/// snaps while [`Grid::snap`] is off.
+pub fn snap_point(point: Vec2, grid: &Grid, guides: &[Guide], max_distance: f32) -> Vec2 {
+    if !grid.snap {
+        return point;
+    }
+
+    let snap_coordinate = |value: f32, axis: GuideAxis| {
+        let mut targets = guides
+            .iter()
+            .filter(|guide| guide.axis == axis)
+            .map(|guide| guide.position)
+            .collect::<Vec<f32>>();
+        if grid.visible && grid.step() > 0.0 {
+            targets.push((value / grid.step()).round() * grid.step());
+        }
+
+        targets
+            .into_iter()
+            .map(|target| (target, (target - value).abs()))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(value, |(target, _)| target)
+    };
+
+    Vec2::new(
+        snap_coordinate(point.x, GuideAxis::Vertical),
+        snap_coordinate(point.y, GuideAxis::Horizontal),
+    )
+}
@@ -1,9 +1,14 @@
 use crate::model::*;
 
-use bevy::{prelude::*, reflect::TypeUuid};
+use bevy::{
+    asset::HandleId,
+    prelude::*,
+    reflect::{FromReflect, TypeUuid},
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -18,8 +23,6 @@ use flo_curves::*;
 // map from t-values (between 0 and 1) to distance on Bezier curve.
 // A t-values is converted to an index in the LUT
 pub type LutDistance = Vec<f64>;
-// map from t-values (between 0 and 1) to point on Bezier curve
-type LutPosition = Vec<Vec2>;
 
 pub struct ComputeGroupLut(pub GroupId);
 
@@ -33,43 +36,223 @@ pub struct LutSaveLoad {
     pub lut: Vec<((f64, f64), LutDistance)>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct StandaloneLut {
-    pub path_length: f32,
-    pub lut: LutPosition,
+pub use bevy_pen_tool_runtime::StandaloneLut;
+
+/// Metadata key shared by the contours of one glyph or one traced layer, with a value that is
+/// unique to the import. A fill mesh made from one of these contours cuts out the others that
+/// lie inside it, like the counters of an "O".
+pub const OUTLINE_METADATA: &str = "outline";
+
+/// A new value for the [`OUTLINE_METADATA`] of the contours of an import.
+pub fn new_outline_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, FromReflect)]
+#[reflect_value(Serialize, Deserialize)]
 pub struct GroupSaveLoad {
     // the AnchorEdge corresponds to first anchor encountered when traversing the group
     pub lut: Vec<(Bezier, AnchorEdge, (f64, f64), LutDistance)>,
     pub standalone_lut: StandaloneLut,
 }
 
+impl GroupSaveLoad {
+    /// Positions of the curves in the order in which the group is traversed, each one
+    /// pointing in the direction of travel.
+    pub fn path_curves(&self) -> Vec<BezierPositions> {
+        self.lut
+            .iter()
+            .map(|(bezier, anchor, _, _)| match anchor {
+                // the anchor is the edge where the traversal leaves the curve
+                AnchorEdge::End => bezier.positions,
+                AnchorEdge::Start => BezierPositions {
+                    start: bezier.positions.end,
+                    end: bezier.positions.start,
+                    control_start: bezier.positions.control_end,
+                    control_end: bezier.positions.control_start,
+                },
+            })
+            .collect()
+    }
+
+    /// Whether the last curve of the group is latched back to the first one.
+    pub fn is_closed(&self) -> bool {
+        match (self.lut.first(), self.lut.last()) {
+            (Some((first, first_anchor, _, _)), Some((last, _, _, _))) if self.lut.len() > 1 => {
+                first
+                    .latches
+                    .get(&first_anchor.other())
+                    .map_or(false, |latch| latch.latched_to_id == last.id)
+            }
+            _ => false,
+        }
+    }
+
+    /// Metadata of the group: the metadata of all its curves, where the first curve in the
+    /// direction of travel wins when two curves disagree.
+    pub fn metadata(&self) -> BTreeMap<String, String> {
+        let mut metadata = BTreeMap::new();
+        for (bezier, _, _, _) in self.lut.iter().rev() {
+            metadata.extend(bezier.metadata.0.clone());
+        }
+        metadata
+    }
+
+    /// Gives the same metadata to every curve of the group.
+    pub fn set_metadata(&mut self, metadata: &BTreeMap<String, String>) {
+        for (bezier, _, _, _) in self.lut.iter_mut() {
+            bezier.metadata = CurveMetadata(metadata.clone());
+        }
+    }
+}
+
 // #[derive(Debug, Clone)]
 // pub struct GroupHandleEntity {
 //     pub handle: Handle<Group>,
 //     pub entity: Entity,
 // }
 
-#[derive(Debug, Clone, TypeUuid, PartialEq)]
+// The handle sets and tables of a group are reflected as values, since bevy does not reflect std
+// sets. Their serde impls write the handles as their ids and read them back as weak handles, so
+// a group can be reflected and copied around, but it is still saved to disk through
+// GroupSaveLoad, which holds the curves themselves.
+
+/// The curves of a group, with the entities that hold them.
+#[derive(Debug, Clone, Default, PartialEq, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub struct GroupCurves(pub HashSet<(Entity, Handle<Bezier>)>);
+
+impl Serialize for GroupCurves {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0
+            .iter()
+            .map(|(entity, handle)| (entity.to_bits(), handle.id))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GroupCurves {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let curves = Vec::<(u64, HandleId)>::deserialize(deserializer)?;
+        Ok(Self(
+            curves
+                .into_iter()
+                .map(|(entity, id)| (Entity::from_bits(entity), Handle::weak(id)))
+                .collect(),
+        ))
+    }
+}
+
+/// The handles of the curves of a group.
+#[derive(Debug, Clone, Default, PartialEq, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub struct CurveHandles(pub HashSet<Handle<Bezier>>);
+
+impl Serialize for CurveHandles {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0
+            .iter()
+            .map(|handle| handle.id)
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CurveHandles {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ids = Vec::<HandleId>::deserialize(deserializer)?;
+        Ok(Self(ids.into_iter().map(Handle::weak).collect()))
+    }
+}
+
+/// The start and end points of a group, see [`Group::find_connected_ends`].
+#[derive(Debug, Clone, Default, PartialEq, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub struct GroupEnds(pub Option<Vec<(Handle<Bezier>, AnchorEdge)>>);
+
+impl Serialize for GroupEnds {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0
+            .as_ref()
+            .map(|ends| {
+                ends.iter()
+                    .map(|(handle, anchor)| (handle.id, *anchor))
+                    .collect::<Vec<_>>()
+            })
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GroupEnds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ends = Option::<Vec<(HandleId, AnchorEdge)>>::deserialize(deserializer)?;
+        Ok(Self(ends.map(|ends| {
+            ends.into_iter()
+                .map(|(id, anchor)| (Handle::weak(id), anchor))
+                .collect()
+        })))
+    }
+}
+
+/// The look-up table of each curve of a group, see [`Group::group_lut`].
+#[derive(Debug, Clone, Default, PartialEq, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub struct GroupLut(pub Vec<(Handle<Bezier>, AnchorEdge, (f64, f64), LutDistance)>);
+
+impl Serialize for GroupLut {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0
+            .iter()
+            .map(|(handle, anchor, t_ends, lut)| (handle.id, *anchor, *t_ends, lut))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GroupLut {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let lut =
+            Vec::<(HandleId, AnchorEdge, (f64, f64), LutDistance)>::deserialize(deserializer)?;
+        Ok(Self(
+            lut.into_iter()
+                .map(|(id, anchor, t_ends, lut)| (Handle::weak(id), anchor, t_ends, lut))
+                .collect(),
+        ))
+    }
+}
+
+deref_to_inner!(GroupCurves, HashSet<(Entity, Handle<Bezier>)>);
+deref_to_inner!(CurveHandles, HashSet<Handle<Bezier>>);
+deref_to_inner!(GroupEnds, Option<Vec<(Handle<Bezier>, AnchorEdge)>>);
+deref_to_inner!(
+    GroupLut,
+    Vec<(Handle<Bezier>, AnchorEdge, (f64, f64), LutDistance)>
+);
+
+// the entity of a group only makes sense in the running editor, so it is left out of the
+// reflected and serialized group
+#[derive(Debug, Clone, TypeUuid, PartialEq, Serialize, Deserialize, Reflect, FromReflect)]
+#[reflect(PartialEq, Serialize, Deserialize)]
 #[uuid = "b16f31ff-a594-4fca-a0e3-85e626d3d01a"] // do not change this uuid without changing the Default impl for GroupId
 pub struct Group {
     // TODO: rid Group of redundancy
-    pub group: HashSet<(Entity, Handle<Bezier>)>,
-    pub bezier_handles: HashSet<Handle<Bezier>>,
+    pub group: GroupCurves,
+    pub bezier_handles: CurveHandles,
     //
     // Attempts to store the start and end points of a group.
     // Fails if curves are not connected or if the curves form a loop
-    pub ends: Option<Vec<(Handle<Bezier>, AnchorEdge)>>,
+    pub ends: GroupEnds,
     //
     // vec of each curve's look-up table
     // the tuple (f64, f64) represents (t_min, t_max), the min and max t-values for
     // the curve
     // the AnchorEdge is the starting
-    pub lut: Vec<(Handle<Bezier>, AnchorEdge, (f64, f64), LutDistance)>,
+    pub lut: GroupLut,
     pub standalone_lut: StandaloneLut,
     pub id: GroupId,
+    #[reflect(ignore)]
+    #[serde(skip)]
     pub entity: Option<Entity>,
 }
 
@@ -77,10 +260,10 @@ impl Default for Group {
     fn default() -> Self {
         // let mut rng = thread_rng();
         Group {
-            group: HashSet::new(),
-            bezier_handles: HashSet::new(),
-            lut: Vec::new(),
-            ends: None,
+            group: GroupCurves::default(),
+            bezier_handles: CurveHandles::default(),
+            lut: GroupLut::default(),
+            ends: GroupEnds::default(),
             standalone_lut: StandaloneLut {
                 path_length: 0.0,
                 lut: Vec::new(),
@@ -149,7 +332,7 @@ impl Group {
             // case of the single curve group
             1 => {
                 let handle = self.bezier_handles.iter().next().unwrap(); // never fails
-                self.ends = Some(vec![
+                *self.ends = Some(vec![
                     (handle.clone(), AnchorEdge::Start),
                     (handle.clone(), AnchorEdge::End),
                 ]);
@@ -162,7 +345,7 @@ impl Group {
         //
         // case of the multiple curve group
 
-        let mut handles = self.bezier_handles.clone();
+        let mut handles = self.bezier_handles.0.clone();
         let num_curves = handles.len();
         let handle = handles.iter().next().unwrap().clone(); // unwap never fails
         handles.remove(&handle);
@@ -186,7 +369,7 @@ impl Group {
 
             // if a curve is completely disconnected form other curves, a group cannot be created
             if anchors.len() == 0 && handles.len() > 1 {
-                self.ends = None;
+                *self.ends = None;
                 return ();
             } else if anchors.len() == 1 {
                 // println!("Anchors len : 1");
@@ -239,18 +422,24 @@ impl Group {
             }
 
             if num_con + 2 > num_curves {
-                self.ends = Some(ends.clone());
+                *self.ends = Some(ends.clone());
             }
         }
     }
 
+    /// Whether the curves form a loop. Only valid after [`Group::find_connected_ends`], which
+    /// finds no ends in a loop.
+    pub fn is_closed(&self) -> bool {
+        self.bezier_handles.len() > 1 && self.ends.as_ref().map_or(false, |ends| ends.is_empty())
+    }
+
     pub fn group_lut(
         &mut self,
         bezier_curves: &BezierAssets,
         id_handle_map: HashMap<BezierId, BezierHandleEntity>,
     ) {
         // if the group is connected with latches, then go ahead and group
-        if let Some(ends) = self.ends.clone() {
+        if let Some(ends) = self.ends.0.clone() {
             let (starting_handle, starting_anchor) = if let Some((handle, anchor)) = ends.get(0) {
                 (handle.clone(), anchor.clone())
             } else {
@@ -347,7 +536,7 @@ impl Group {
                 }
 
                 // update the look-up table
-                self.lut = group_lut.clone();
+                *self.lut = group_lut.clone();
             }
         }
     }
@@ -357,7 +546,7 @@ impl Group {
             let mut curve_index = 0;
             let mut pos: Vec2 = Vec2::ZERO;
             //
-            for (_handle, _anchor, (t_min, t_max), _lut) in &self.lut {
+            for (_handle, _anchor, (t_min, t_max), _lut) in self.lut.iter() {
                 // println!("t: {}, t_min: {}, t_max: {}, ", t, t_min, t_max);
                 if &t >= t_min && &t <= &(t_max + 0.000001) {
                     break;
@@ -407,7 +596,7 @@ impl Group {
 
             #[allow(unused_assignments)]
             let mut normal = Vec2::ZERO;
-            for (_handle, _anchor, (t_min, t_max), _lut) in &self.lut {
+            for (_handle, _anchor, (t_min, t_max), _lut) in self.lut.iter() {
                 // println!("t: {}, t_min: {}, t_max: {}, ", t, t_min, t_max);
                 if &t >= t_min && &t <= &(t_max + 0.000001) {
                     break;
@@ -458,7 +647,7 @@ impl Group {
 
     pub fn compute_standalone_lut(&mut self, bezier_curves: &BezierAssets, num_points: u32) {
         let mut total_length: f32 = 0.0;
-        for lut in self.lut.clone() {
+        for lut in self.lut.iter().cloned() {
             if let Some(bezier) = bezier_curves.get(&lut.0.id) {
                 total_length += bezier.length();
             }
@@ -480,26 +669,16 @@ impl Group {
 
         self.standalone_lut = standalone_lut;
     }
-    // this is now used inside the plugin, but this would be the function used in
-    // an application where the look-up table (lut) would be loaded
+    // applications that load the look-up table use StandaloneLut::position from
+    // bevy_pen_tool_runtime instead
     pub fn compute_position_with_lut(&self, t: f32) -> Vec2 {
-        let lut = self.standalone_lut.lut.clone();
-        if lut.len() > 0 {
-            let idx_f64 = t * (lut.len() - 1) as f32;
-            let p1 = lut[(idx_f64 as usize)];
-            let p2 = lut[idx_f64 as usize + 1];
-            let rem = idx_f64 % 1.0;
-            let position = interpolate_vec2(p1, p2, rem);
-            return position;
-        } else {
-            return Vec2::ZERO;
-        }
+        self.standalone_lut.position(t)
     }
 
     // compute the average position of the anchors making up the group
     pub fn center_of_mass(&self, bezier_curves: &BezierAssets) -> Vec2 {
         let mut center_of_mass = Vec2::ZERO;
-        for (handle, anchor, _t_range, _lut) in &self.lut {
+        for (handle, anchor, _t_range, _lut) in self.lut.iter() {
             if let Some(bezier) = bezier_curves.get(&handle.id) {
                 // center_of_mass += bezier.center_of_mass();
                 let pos = match anchor {
@@ -0,0 +1,311 @@
+//! Building blocks shared by the importers. Foreign shapes are turned into chains of latched
+//! curves, stored as [`GroupSaveLoad`]s, and then spawned like any loaded `.group` file.
+
+use crate::formats::ConversionError;
+use crate::model::*;
+
+use bevy::prelude::*;
+
+use flo_curves::bezier::{fit_curve, BezierCurve, Curve};
+use flo_curves::*;
+
+use std::collections::HashMap;
+
+/// Two anchors closer than this are considered to be at the same position.
+pub const ANCHOR_MERGE_DISTANCE: f32 = 0.01;
+
+/// Number of sections used for the look-up table of each imported curve.
+const CURVE_LUT_SECTIONS: usize = 100;
+
+impl GroupSaveLoad {
+    /// Latches consecutive curves end to start into one group. The chain is closed when the
+    /// end of the last curve lands on the start of the first one. Chains without length, and
+    /// look-up tables of fewer than two points, are rejected.
+    pub fn from_chain(
+        curves: &[BezierPositions],
+        color: Option<Color>,
+        num_points: u32,
+    ) -> Result<Self, ConversionError> {
+        check_num_points(num_points)?;
+
+        let group_id = GroupId::default();
+        let ids = curves
+            .iter()
+            .map(|_| BezierId::default())
+            .collect::<Vec<BezierId>>();
+
+        let num_curves = curves.len();
+        let is_closed = num_curves > 1
+            && curves[0].start.distance(curves[num_curves - 1].end) < ANCHOR_MERGE_DISTANCE;
+
+        let mut beziers = Vec::new();
+        for (k, positions) in curves.iter().enumerate() {
+            let positions = separate_controls(*positions);
+            let mut bezier = Bezier {
+                positions,
+                previous_positions: positions,
+                color,
+                id: ids[k],
+                group: group_id,
+                ..Default::default()
+            };
+
+            if k > 0 || is_closed {
+                let previous = (k + num_curves - 1) % num_curves;
+                bezier.latches.insert(
+                    AnchorEdge::Start,
+                    LatchData {
+                        latched_to_id: ids[previous],
+                        self_edge: AnchorEdge::Start,
+                        partners_edge: AnchorEdge::End,
+                    },
+                );
+            }
+
+            if k + 1 < num_curves || is_closed {
+                let next = (k + 1) % num_curves;
+                bezier.latches.insert(
+                    AnchorEdge::End,
+                    LatchData {
+                        latched_to_id: ids[next],
+                        self_edge: AnchorEdge::End,
+                        partners_edge: AnchorEdge::Start,
+                    },
+                );
+            }
+
+            beziers.push(bezier);
+        }
+
+        let total_length = beziers.iter().map(|b| b.length()).sum::<f32>();
+        if total_length <= 0.0 || !total_length.is_finite() {
+            return Err(ConversionError::Degenerate(
+                "the chain has no length".to_string(),
+            ));
+        }
+
+        // the chain is traversed from the start of the first curve, so the anchor stored
+        // with each curve is the end where the traversal leaves it
+        let mut min_t = 0.0;
+        let mut lut = Vec::new();
+        for mut bezier in beziers {
+            let max_t = min_t + bezier.length() / total_length;
+            // the walk steps by a fraction of the length, and never ends on a curve without one
+            if bezier.length() > 0.0 {
+                bezier.compute_lut_walk(CURVE_LUT_SECTIONS);
+            }
+            let local_lut = std::mem::take(&mut bezier.lut);
+            lut.push((
+                bezier,
+                AnchorEdge::End,
+                (min_t as f64, max_t as f64),
+                local_lut,
+            ));
+            min_t = max_t;
+        }
+
+        let mut group = GroupSaveLoad {
+            lut,
+            standalone_lut: StandaloneLut {
+                path_length: total_length,
+                lut: Vec::new(),
+            },
+        };
+        group.compute_standalone_lut(num_points)?;
+        Ok(group)
+    }
+
+    /// Same sampling as [`Group::compute_standalone_lut`], without the need for spawned curves.
+    pub fn compute_standalone_lut(&mut self, num_points: u32) -> Result<(), ConversionError> {
+        check_num_points(num_points)?;
+
+        let mut path_length = 0.0;
+        let mut points = Vec::new();
+
+        for (bezier, _, _, _) in self.lut.iter() {
+            path_length += bezier.length();
+        }
+
+        for k in 0..num_points {
+            let t = k as f64 / (num_points as f64 - 1.0);
+            points.push(self.position_at(t));
+        }
+
+        self.standalone_lut = StandaloneLut {
+            path_length,
+            lut: points,
+        };
+        Ok(())
+    }
+
+    fn position_at(&self, t: f64) -> Vec2 {
+        let entry = self
+            .lut
+            .iter()
+            .find(|(_, _, (t_min, t_max), _)| t_max > t_min && t <= t_max + 0.000001)
+            .or_else(|| self.lut.last());
+
+        if let Some((bezier, anchor, (t_min, t_max), local_lut)) = entry {
+            let mut t_0_1 = if t_max > t_min {
+                (t - t_min) / (t_max - t_min)
+            } else {
+                0.0
+            };
+            if anchor == &AnchorEdge::Start {
+                t_0_1 = 1.0 - t_0_1;
+            }
+            t_0_1 = t_0_1.clamp(0.00000000001, 0.9999);
+
+            let curve = bezier.to_curve();
+            let t_distance = if local_lut.len() > 1 {
+                let idx_f64 = t_0_1 * (local_lut.len() - 1) as f64;
+                let p1 = local_lut[idx_f64 as usize];
+                let p2 = local_lut[(idx_f64 as usize + 1).min(local_lut.len() - 1)];
+                interpolate(p1, p2, idx_f64 % 1.0)
+            } else {
+                t_0_1
+            };
+
+            let Coord2(x, y) = curve.point_at_pos(t_distance);
+            Vec2::new(x as f32, y as f32)
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    pub fn bounding_box(&self) -> Option<(Vec2, Vec2)> {
+        self.lut
+            .iter()
+            .map(|(bezier, _, _, _)| bezier.bounding_box())
+            .reduce(|(min0, max0), (min1, max1)| (min0.min(min1), max0.max(max1)))
+    }
+
+    pub fn translate(&mut self, offset: Vec2) {
+        for (bezier, _, _, _) in self.lut.iter_mut() {
+            for position in [
+                &mut bezier.positions.start,
+                &mut bezier.positions.end,
+                &mut bezier.positions.control_start,
+                &mut bezier.positions.control_end,
+            ] {
+                *position += offset;
+            }
+            bezier.update_previous_pos();
+        }
+
+        for point in self.standalone_lut.lut.iter_mut() {
+            *point += offset;
+        }
+    }
+}
+
+// the samples are spread between t = 0 and t = 1 included
+fn check_num_points(num_points: u32) -> Result<(), ConversionError> {
+    if num_points < 2 {
+        return Err(ConversionError::Degenerate(format!(
+            "a look-up table needs at least two points, not {}",
+            num_points
+        )));
+    }
+    Ok(())
+}
+
+// the look-up table walk fails when a control point sits exactly on its anchor
+fn separate_controls(mut positions: BezierPositions) -> BezierPositions {
+    let nudge = |anchor: Vec2, control: Vec2, towards: Vec2| {
+        if control.distance(anchor) < ANCHOR_MERGE_DISTANCE {
+            anchor + (towards - anchor) * 0.01
+        } else {
+            control
+        }
+    };
+
+    positions.control_start = nudge(positions.start, positions.control_start, positions.end);
+    positions.control_end = nudge(positions.end, positions.control_end, positions.start);
+    positions
+}
+
+/// Gives fresh ids to every curve and group, so that the same file can be imported several
+/// times without colliding with the curves already on the canvas. Latches are kept.
+pub fn renew_ids(groups: &mut [GroupSaveLoad]) {
+    let mut bezier_ids: HashMap<BezierId, BezierId> = HashMap::new();
+    let mut group_ids: HashMap<GroupId, GroupId> = HashMap::new();
+
+    for group in groups.iter() {
+        for (bezier, _, _, _) in group.lut.iter() {
+            bezier_ids.insert(bezier.id, BezierId::default());
+            group_ids
+                .entry(bezier.group)
+                .or_insert_with(GroupId::default);
+        }
+    }
+
+    for group in groups.iter_mut() {
+        for (bezier, _, _, _) in group.lut.iter_mut() {
+            bezier.id = bezier_ids[&bezier.id];
+            bezier.group = group_ids[&bezier.group];
+            bezier.potential_latch = None;
+            bezier.entity = None;
+
+            for latch in bezier.latches.values_mut() {
+                if let Some(new_id) = bezier_ids.get(&latch.latched_to_id) {
+                    latch.latched_to_id = *new_id;
+                }
+            }
+        }
+    }
+}
+
+/// Moves the groups so that the center of their common bounding box lands on `position`.
+pub fn center_groups_on(groups: &mut [GroupSaveLoad], position: Vec2) {
+    let bounding_box = groups
+        .iter()
+        .filter_map(|group| group.bounding_box())
+        .reduce(|(min0, max0), (min1, max1)| (min0.min(min1), max0.max(max1)));
+
+    if let Some((min, max)) = bounding_box {
+        let offset = position - (min + max) / 2.0;
+        for group in groups.iter_mut() {
+            group.translate(offset);
+        }
+    }
+}
+
+/// Turns a look-up table back into curves that can be edited.
+pub trait FitCurves {
+    /// Fits a chain of cubic curves through the points of the look-up table. `max_error` is
+    /// the largest distance allowed between a point and the fitted curves.
+    fn fit_curves(&self, max_error: f64) -> Vec<BezierPositions>;
+}
+
+impl FitCurves for StandaloneLut {
+    fn fit_curves(&self, max_error: f64) -> Vec<BezierPositions> {
+        let points = self
+            .lut
+            .iter()
+            .map(|p| Coord2(p.x as f64, p.y as f64))
+            .collect::<Vec<Coord2>>();
+
+        if points.len() < 2 {
+            return Vec::new();
+        }
+
+        fit_curve::<Curve<Coord2>>(&points, max_error)
+            .unwrap_or_default()
+            .iter()
+            .map(|curve| {
+                let (control_start, control_end) = curve.control_points();
+                BezierPositions {
+                    start: coord_to_vec2(curve.start_point()),
+                    end: coord_to_vec2(curve.end_point()),
+                    control_start: coord_to_vec2(control_start),
+                    control_end: coord_to_vec2(control_end),
+                }
+            })
+            .collect()
+    }
+}
+
+fn coord_to_vec2(coord: Coord2) -> Vec2 {
+    Vec2::new(coord.0 as f32, coord.1 as f32)
+}
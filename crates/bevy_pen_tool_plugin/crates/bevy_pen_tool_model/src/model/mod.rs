@@ -1,7 +1,36 @@
+// derefs a newtype wrapper to the value it wraps
+macro_rules! deref_to_inner {
+    ($wrapper:ty, $inner:ty) => {
+        impl std::ops::Deref for $wrapper {
+            type Target = $inner;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl std::ops::DerefMut for $wrapper {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+    };
+}
+
 mod bezier;
+mod grid;
 mod group;
+mod import;
+mod offline;
+mod project;
+mod save_format;
 pub mod util;
 
 pub use bezier::*;
+pub use grid::*;
 pub use group::*;
+pub use import::*;
+pub use offline::*;
+pub use project::*;
+pub use save_format::*;
 pub use util::*;
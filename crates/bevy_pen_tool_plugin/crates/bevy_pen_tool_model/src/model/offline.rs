@@ -0,0 +1,144 @@
+//! Groups rebuilt from saved files without spawning anything, for tools that run without a
+//! window. The curves are kept in a map instead of `Assets<Bezier>`, so that the look-up
+//! tables and meshes are computed by the same code as in the editor.
+
+use crate::mesh::*;
+use crate::model::*;
+
+use bevy::{asset::HandleId, prelude::*};
+
+use std::collections::HashMap;
+
+/// Number of sections of the look-up table of a curve saved without one.
+const CURVE_LUT_SECTIONS: usize = 100;
+
+pub struct OfflineGroup {
+    pub group: Group,
+    pub curves: HashMap<HandleId, Bezier>,
+    bezier_map: HashMap<BezierId, BezierHandleEntity>,
+}
+
+impl OfflineGroup {
+    /// Rebuilds the group and its latched curves. The curves get weak handles and
+    /// placeholder entities, which are only used as keys.
+    pub fn from_save(saved: &GroupSaveLoad) -> Self {
+        let mut group = Group {
+            id: saved
+                .lut
+                .first()
+                .map_or_else(GroupId::default, |(bezier, _, _, _)| bezier.group),
+            standalone_lut: saved.standalone_lut.clone(),
+            ..Default::default()
+        };
+        let mut curves = HashMap::new();
+        let mut bezier_map = HashMap::new();
+
+        for (k, (bezier, anchor, t_ends, local_lut)) in saved.lut.iter().enumerate() {
+            let mut bezier = bezier.clone();
+            bezier.lut = local_lut.clone();
+            if bezier.lut.len() < 2 {
+                bezier.compute_lut_walk(CURVE_LUT_SECTIONS);
+            }
+
+            let handle = Handle::<Bezier>::weak(bezier.id.0);
+            let entity = Entity::from_raw(k as u32);
+
+            group.add_curve(entity, handle.clone());
+            group
+                .lut
+                .push((handle.clone(), *anchor, *t_ends, bezier.lut.clone()));
+            bezier_map.insert(
+                bezier.id,
+                BezierHandleEntity {
+                    handle,
+                    entity,
+                    anchor_entities: HashMap::new(),
+                },
+            );
+            curves.insert(bezier.id.0, bezier);
+        }
+
+        Self {
+            group,
+            curves,
+            bezier_map,
+        }
+    }
+
+    pub fn bezier_assets(&self) -> BezierAssets {
+        self.curves
+            .iter()
+            .map(|(id, bezier)| (*id, bezier))
+            .collect()
+    }
+
+    /// Traverses the latched curves and samples `num_points` points along them, as the
+    /// editor does before saving a group.
+    pub fn compute_lut(&mut self, num_points: u32) {
+        let bezier_assets: BezierAssets = self
+            .curves
+            .iter()
+            .map(|(id, bezier)| (*id, bezier))
+            .collect();
+
+        self.group
+            .find_connected_ends(&bezier_assets, self.bezier_map.clone());
+        self.group
+            .group_lut(&bezier_assets, self.bezier_map.clone());
+        self.group
+            .compute_standalone_lut(&bezier_assets, num_points);
+    }
+
+    /// Whether the traversal went through every curve. It stops early when the curves are
+    /// not all latched together.
+    pub fn is_connected(&self) -> bool {
+        !self.curves.is_empty() && self.group.lut.len() == self.curves.len()
+    }
+
+    pub fn center_of_mass(&self) -> Vec2 {
+        self.group.center_of_mass(&self.bezier_assets())
+    }
+
+    /// Same as [`Group::into_group_save`].
+    pub fn to_save(&self) -> GroupSaveLoad {
+        let lut = self
+            .group
+            .lut
+            .iter()
+            .filter_map(|(handle, anchor, t_ends, local_lut)| {
+                let mut bezier = self.curves.get(&handle.id)?.clone();
+                bezier.lut = Vec::new();
+                Some((bezier, *anchor, *t_ends, local_lut.clone()))
+            })
+            .collect();
+
+        GroupSaveLoad {
+            lut,
+            standalone_lut: self.group.standalone_lut.clone(),
+        }
+    }
+
+    /// See [`road_mesh`]. The look-up table must have been computed.
+    pub fn road_mesh(
+        &self,
+        num_points: u32,
+        road_width: f32,
+        color: Color,
+    ) -> (Mesh, (Vec2, Vec2)) {
+        let bezier_assets = self.bezier_assets();
+        let center_of_mass = self.group.center_of_mass(&bezier_assets);
+        road_mesh(
+            &self.group,
+            &bezier_assets,
+            center_of_mass,
+            num_points,
+            road_width,
+            color,
+        )
+    }
+
+    /// See [`fill_mesh`]. The look-up table must have been computed.
+    pub fn fill_mesh(&self, color: Color) -> Option<(Mesh, (Vec2, Vec2))> {
+        fill_mesh(&self.group.standalone_lut.lut, self.center_of_mass(), color)
+    }
+}
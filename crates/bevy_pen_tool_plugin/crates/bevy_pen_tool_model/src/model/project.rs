@@ -0,0 +1,60 @@
+use crate::model::*;
+
+use bevy::prelude::*;
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::path::PathBuf;
+
+/// An image shown under the curves, such as concept art or a screenshot of a level, to draw
+/// paths over it.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceImage {
+    pub id: u64,
+    /// Path of the image in the assets directory.
+    pub path: PathBuf,
+    /// Position of the center of the image on the canvas.
+    pub position: Vec2,
+    /// Canvas units per pixel.
+    pub scale: f32,
+    /// Counter-clockwise rotation around the center, in radians.
+    pub rotation: f32,
+    pub opacity: f32,
+    /// Locked images cannot be picked and moved with the mouse.
+    pub locked: bool,
+}
+
+impl ReferenceImage {
+    /// An unlocked image with a new id, centered on the origin, at one canvas unit per pixel
+    /// and half transparent.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            id: thread_rng().gen(),
+            path: path.into(),
+            position: Vec2::ZERO,
+            scale: 1.0,
+            rotation: 0.0,
+            opacity: 0.5,
+            locked: false,
+        }
+    }
+
+    /// Whether a point of the canvas lies on the image, given the size of the image in pixels.
+    pub fn contains(&self, point: Vec2, image_size: Vec2) -> bool {
+        let local = Vec2::from_angle(-self.rotation).rotate(point - self.position) / self.scale;
+        local.abs().cmple(image_size / 2.0).all()
+    }
+}
+
+/// Everything on the canvas that is saved in a `.pen` project file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProjectSaveLoad {
+    pub groups: Vec<GroupSaveLoad>,
+    #[serde(default)]
+    pub references: Vec<ReferenceImage>,
+    #[serde(default)]
+    pub grid: Grid,
+    #[serde(default)]
+    pub guides: Vec<Guide>,
+}
@@ -0,0 +1,380 @@
+//! Versioned on-disk format for `.group`, `.lut`, `.meta` and `.pen` project files.
+//!
+//! Every file is written as `{ "version": N, "data": ... }`. Files that were saved before the
+//! header existed are read as version 0. On load, the payload is upgraded one version at a time
+//! until it reaches [`SAVE_FORMAT_VERSION`], and only then deserialized.
+//!
+//! Groups and look-up tables can also be written as RON or in a compact binary encoding, see
+//! [`SaveEncoding`]. Both carry the same version number, and are detected when loading.
+
+use crate::mesh::MeshMeta;
+use crate::model::*;
+
+use bevy_pen_tool_runtime::{split_binary_header, split_header};
+pub use bevy_pen_tool_runtime::{SaveEncoding, BINARY_MAGIC};
+
+use bevy::prelude::{Color, Entity};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Version written by [`SaveFormat::to_versioned_string`]. Bump it, and add a step to the
+/// `migrate` function of every affected type, whenever a saved struct changes.
+pub const SAVE_FORMAT_VERSION: u32 = 3;
+
+#[derive(Debug)]
+pub enum SaveFormatError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    Malformed(String),
+    Ron(String),
+    Bincode(String),
+}
+
+impl fmt::Display for SaveFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "invalid json: {}", e),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "file version {} is newer than the supported version {}",
+                version, SAVE_FORMAT_VERSION
+            ),
+            Self::Malformed(reason) => write!(f, "malformed file: {}", reason),
+            Self::Ron(reason) => write!(f, "invalid ron: {}", reason),
+            Self::Bincode(reason) => write!(f, "invalid binary file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SaveFormatError {}
+
+impl From<serde_json::Error> for SaveFormatError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[derive(Serialize)]
+struct VersionedRef<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+/// Implemented by every type that is saved to disk on its own.
+pub trait SaveFormat: Serialize + DeserializeOwned {
+    /// Upgrades the raw payload of a file written with `version` to `version + 1`.
+    fn migrate(version: u32, data: Value) -> Result<Value, SaveFormatError> {
+        let _ = version;
+        Ok(data)
+    }
+
+    fn to_versioned_string(&self) -> Result<String, SaveFormatError> {
+        let versioned = VersionedRef {
+            version: SAVE_FORMAT_VERSION,
+            data: self,
+        };
+        Ok(serde_json::to_string_pretty(&versioned)?)
+    }
+
+    fn from_versioned_str(contents: &str) -> Result<Self, SaveFormatError> {
+        let (mut version, mut data) = split_header(serde_json::from_str(contents)?)?;
+
+        if version > SAVE_FORMAT_VERSION {
+            return Err(SaveFormatError::UnsupportedVersion(version));
+        }
+
+        while version < SAVE_FORMAT_VERSION {
+            data = Self::migrate(version, data)?;
+            version += 1;
+        }
+
+        Ok(serde_json::from_value(data)?)
+    }
+
+    fn to_versioned_bytes(&self, encoding: SaveEncoding) -> Result<Vec<u8>, SaveFormatError> {
+        let versioned = VersionedRef {
+            version: SAVE_FORMAT_VERSION,
+            data: self,
+        };
+
+        match encoding {
+            SaveEncoding::Json => Ok(self.to_versioned_string()?.into_bytes()),
+            SaveEncoding::Ron => {
+                ron::ser::to_string_pretty(&versioned, ron::ser::PrettyConfig::new())
+                    .map(String::into_bytes)
+                    .map_err(|e| SaveFormatError::Ron(e.to_string()))
+            }
+            SaveEncoding::Bincode => {
+                let mut bytes = BINARY_MAGIC.to_vec();
+                bytes.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+                bincode::serialize_into(&mut bytes, self)
+                    .map_err(|e| SaveFormatError::Bincode(e.to_string()))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Reads the binary payload of a file written with an older `version`. Binary files have
+    /// no field names, so a type whose layout changed reads its older versions through structs
+    /// with the old layout. By default, the layout is the same in every version.
+    fn from_old_bincode(version: u32, payload: &[u8]) -> Result<Self, SaveFormatError> {
+        let _ = version;
+        deserialize_bincode(payload)
+    }
+
+    /// Reads a file in any [`SaveEncoding`]. Json files of any version are migrated, and RON
+    /// and binary files from version 1 on.
+    fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, SaveFormatError> {
+        match SaveEncoding::detect(bytes) {
+            SaveEncoding::Json => {
+                let contents = std::str::from_utf8(bytes)
+                    .map_err(|e| SaveFormatError::Malformed(e.to_string()))?;
+                Self::from_versioned_str(contents)
+            }
+            SaveEncoding::Ron => {
+                // the fields added since version 1 have defaults, which RON fills in
+                let versioned: Versioned<Self> =
+                    ron::de::from_bytes(bytes).map_err(|e| SaveFormatError::Ron(e.to_string()))?;
+                check_version(versioned.version, 1)?;
+                Ok(versioned.data)
+            }
+            SaveEncoding::Bincode => {
+                let (version, payload) = split_binary_header(bytes).ok_or_else(|| {
+                    SaveFormatError::Bincode("the header is cut short".to_string())
+                })?;
+                check_version(version, 1)?;
+                if version == SAVE_FORMAT_VERSION {
+                    deserialize_bincode(payload)
+                } else {
+                    Self::from_old_bincode(version, payload)
+                }
+            }
+        }
+    }
+}
+
+fn deserialize_bincode<T: DeserializeOwned>(payload: &[u8]) -> Result<T, SaveFormatError> {
+    bincode::deserialize(payload).map_err(|e| SaveFormatError::Bincode(e.to_string()))
+}
+
+// RON and binary files appeared with version 1
+fn check_version(version: u32, oldest: u32) -> Result<(), SaveFormatError> {
+    if version > SAVE_FORMAT_VERSION {
+        Err(SaveFormatError::UnsupportedVersion(version))
+    } else if version < oldest {
+        Err(SaveFormatError::Malformed(format!(
+            "version {} cannot be read from this encoding, only from json",
+            version
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+// a save file holds every group that was selected at save time
+impl SaveFormat for Vec<GroupSaveLoad> {
+    fn migrate(version: u32, data: Value) -> Result<Value, SaveFormatError> {
+        match version {
+            0 => upgrade_legacy_curves(data),
+            1 => add_curve_metadata(data),
+            _ => Ok(data),
+        }
+    }
+
+    // groups kept their layout since version 2
+    fn from_old_bincode(version: u32, payload: &[u8]) -> Result<Self, SaveFormatError> {
+        if version == 1 {
+            let groups: Vec<GroupSaveLoadV1> = deserialize_bincode(payload)?;
+            Ok(groups.into_iter().map(GroupSaveLoad::from).collect())
+        } else {
+            deserialize_bincode(payload)
+        }
+    }
+}
+
+// the layout of groups in version 1, before curves had metadata
+#[derive(Deserialize)]
+struct GroupSaveLoadV1 {
+    lut: Vec<(BezierV1, AnchorEdge, (f64, f64), LutDistance)>,
+    standalone_lut: StandaloneLut,
+}
+
+#[derive(Deserialize)]
+struct BezierV1 {
+    positions: BezierPositions,
+    previous_positions: BezierPositions,
+    color: Option<Color>,
+    do_compute_lut: bool,
+    lut: LutDistance,
+    id: BezierId,
+    latches: HashMap<AnchorEdge, LatchData>,
+    potential_latch: Option<LatchData>,
+    group: GroupId,
+    entity: Option<Entity>,
+}
+
+impl From<GroupSaveLoadV1> for GroupSaveLoad {
+    fn from(group: GroupSaveLoadV1) -> Self {
+        Self {
+            lut: group
+                .lut
+                .into_iter()
+                .map(|(bezier, anchor, t_ends, distances)| {
+                    let bezier = Bezier {
+                        positions: bezier.positions,
+                        previous_positions: bezier.previous_positions,
+                        color: bezier.color,
+                        do_compute_lut: bezier.do_compute_lut,
+                        lut: bezier.lut,
+                        id: bezier.id,
+                        latches: Latches(bezier.latches),
+                        potential_latch: bezier.potential_latch,
+                        group: bezier.group,
+                        entity: bezier.entity,
+                        metadata: Default::default(),
+                    };
+                    (bezier, anchor, t_ends, distances)
+                })
+                .collect(),
+            standalone_lut: group.standalone_lut,
+        }
+    }
+}
+
+impl SaveFormat for StandaloneLut {}
+
+impl SaveFormat for MeshMeta {}
+
+// projects appeared with version 2, and version 3 added the grid and the guides
+impl SaveFormat for ProjectSaveLoad {
+    fn migrate(version: u32, data: Value) -> Result<Value, SaveFormatError> {
+        match version {
+            2 => add_grid_and_guides(data),
+            _ => Ok(data),
+        }
+    }
+
+    fn from_old_bincode(_version: u32, payload: &[u8]) -> Result<Self, SaveFormatError> {
+        let project: ProjectSaveLoadV2 = deserialize_bincode(payload)?;
+        Ok(ProjectSaveLoad {
+            groups: project.groups,
+            references: project.references,
+            ..Default::default()
+        })
+    }
+}
+
+// the layout of projects in version 2
+#[derive(Deserialize)]
+struct ProjectSaveLoadV2 {
+    groups: Vec<GroupSaveLoad>,
+    references: Vec<ReferenceImage>,
+}
+
+// The oldest .group files stored curve ids as plain 128-bit integers and had no group id.
+// Ids are rehashed into the BezierId space so that latches still point to the right curves.
+fn upgrade_legacy_curves(mut data: Value) -> Result<Value, SaveFormatError> {
+    let groups = data
+        .as_array_mut()
+        .ok_or_else(|| SaveFormatError::Malformed("expected a list of groups".to_string()))?;
+
+    for group in groups.iter_mut() {
+        let group_id = serde_json::to_value(GroupId::default())?;
+
+        let lut = group
+            .get_mut("lut")
+            .and_then(Value::as_array_mut)
+            .ok_or_else(|| SaveFormatError::Malformed("group has no curve list".to_string()))?;
+
+        for entry in lut.iter_mut() {
+            let curve = entry
+                .get_mut(0)
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| SaveFormatError::Malformed("expected a curve".to_string()))?;
+
+            if let Some(id) = curve.get_mut("id") {
+                upgrade_legacy_id(id)?;
+            }
+
+            if let Some(latches) = curve.get_mut("latches").and_then(Value::as_object_mut) {
+                for latch in latches.values_mut() {
+                    if let Some(id) = latch.get_mut("latched_to_id") {
+                        upgrade_legacy_id(id)?;
+                    }
+                }
+            }
+
+            if let Some(id) = curve
+                .get_mut("potential_latch")
+                .and_then(|latch| latch.get_mut("latched_to_id"))
+            {
+                upgrade_legacy_id(id)?;
+            }
+
+            curve.entry("group").or_insert_with(|| group_id.clone());
+        }
+    }
+
+    Ok(data)
+}
+
+// version 2 gave every curve a map of free-form properties
+fn add_curve_metadata(mut data: Value) -> Result<Value, SaveFormatError> {
+    let groups = data
+        .as_array_mut()
+        .ok_or_else(|| SaveFormatError::Malformed("expected a list of groups".to_string()))?;
+
+    for group in groups.iter_mut() {
+        let lut = group
+            .get_mut("lut")
+            .and_then(Value::as_array_mut)
+            .ok_or_else(|| SaveFormatError::Malformed("group has no curve list".to_string()))?;
+
+        for entry in lut.iter_mut() {
+            if let Some(curve) = entry.get_mut(0).and_then(Value::as_object_mut) {
+                curve
+                    .entry("metadata")
+                    .or_insert_with(|| Value::Object(Default::default()));
+            }
+        }
+    }
+
+    Ok(data)
+}
+
+// version 3 saved the grid and the guides of the canvas with the project
+fn add_grid_and_guides(mut data: Value) -> Result<Value, SaveFormatError> {
+    let project = data
+        .as_object_mut()
+        .ok_or_else(|| SaveFormatError::Malformed("expected a project".to_string()))?;
+
+    if !project.contains_key("grid") {
+        project.insert("grid".to_string(), serde_json::to_value(Grid::default())?);
+    }
+    project
+        .entry("guides")
+        .or_insert_with(|| Value::Array(Vec::new()));
+
+    Ok(data)
+}
+
+fn upgrade_legacy_id(id: &mut Value) -> Result<(), SaveFormatError> {
+    if let Value::Number(number) = id {
+        let mut hasher = DefaultHasher::new();
+        number.to_string().hash(&mut hasher);
+        *id = serde_json::to_value(BezierId::from(hasher.finish()))?;
+    }
+    Ok(())
+}
@@ -1,10 +1,12 @@
+use crate::formats::GeoJsonProjection;
 use crate::inputs::*;
 use crate::materials::*;
 use crate::mesh::*;
 use crate::model::bezier::*;
 use crate::model::group::*;
+use crate::model::save_format::SaveEncoding;
 
-use bevy::{asset::HandleId, prelude::*, sprite::Mesh2dHandle, utils::Uuid};
+use bevy::{asset::HandleId, prelude::*, reflect::FromReflect, sprite::Mesh2dHandle, utils::Uuid};
 
 // use rand::distributions::Open01;
 use rand::prelude::*;
@@ -13,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+#[cfg(feature = "inspector")]
 use bevy_inspector_egui::Inspectable;
 
 #[derive(Component)]
@@ -120,6 +123,9 @@ impl Default for Selection {
 /// Holds Z position information. Important for drawing order. Contained within [`Globals`].
 #[derive(Clone, Debug)]
 pub struct ZPos {
+    pub reference: f32,
+    pub grid: f32,
+    pub guides: f32,
     pub bezier_parent: f32,
     pub anchors: f32,
     pub controls: f32,
@@ -144,6 +150,9 @@ pub struct ZPos {
 impl Default for ZPos {
     fn default() -> Self {
         Self {
+            reference: 0.1,
+            grid: 0.2,
+            guides: 0.3,
             bezier_parent: 0.33,
 
             anchors: 0.33,
@@ -183,6 +192,10 @@ pub struct Globals {
     pub road_width: f32,
     pub anchor_clicking_dist: f32,
     pub z_pos: ZPos,
+    /// Encoding of the `.group` and `.lut` files written by the editor.
+    pub save_encoding: SaveEncoding,
+    /// Projection of the coordinates of imported and exported GeoJSON files.
+    pub geojson_projection: GeoJsonProjection,
 }
 
 impl Default for Globals {
@@ -200,11 +213,15 @@ impl Default for Globals {
             road_width: 8.0,
             anchor_clicking_dist: 12.0,
             z_pos: ZPos::default(),
+            save_encoding: SaveEncoding::Json,
+            geojson_projection: GeoJsonProjection::default(),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Inspectable)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Reflect, FromReflect)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
+#[reflect_value(PartialEq, Hash, Serialize, Deserialize)]
 pub struct GroupId(pub HandleId);
 impl From<HandleId> for GroupId {
     fn from(id: HandleId) -> Self {
@@ -335,7 +352,8 @@ pub fn update_latched_partner_position(
 
 // leave this public
 /// Holds information about the position of each anchor for a given Bezier curve.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, Inspectable, PartialEq)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Reflect, FromReflect)]
+#[cfg_attr(feature = "inspector", derive(Inspectable))]
 pub struct BezierPositions {
     pub start: Vec2,
     pub end: Vec2,
@@ -354,6 +372,17 @@ impl Default for BezierPositions {
     }
 }
 
+impl From<BezierPositions> for bevy_pen_tool_runtime::CurvePositions {
+    fn from(positions: BezierPositions) -> Self {
+        Self {
+            start: positions.start,
+            end: positions.end,
+            control_start: positions.control_start,
+            control_end: positions.control_end,
+        }
+    }
+}
+
 impl BezierPositions {
     pub const ZERO: Self = Self {
         start: Vec2::ZERO,
@@ -713,7 +742,7 @@ pub fn adjust_group_attributes(
                 // We set the mesh attributes as a function of the bounding box.
                 // This could be done by removing the mesh from the mesh asset
                 // and adding a brand new mesh
-                for (_entity, selected_handle) in group.group.clone() {
+                for (_entity, selected_handle) in group.group.iter().cloned() {
                     let bezier = bezier_curves.get(&selected_handle).unwrap();
 
                     let (bound0, bound1) = bezier.bounding_box();
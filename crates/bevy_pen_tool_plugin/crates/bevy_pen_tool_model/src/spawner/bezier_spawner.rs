@@ -108,7 +108,7 @@ pub fn spawn_bezier_system(
                 control_end,
             },
             previous_positions: BezierPositions::default(),
-            latches,
+            latches: Latches(latches),
             // move_quad: Anchor::End,
             id: default_spawner_id,
             group: group_id,
@@ -119,7 +119,7 @@ pub fn spawn_bezier_system(
         if let Some(bezier_hist) = maybe_bezier_hist {
             do_send_to_history = bezier_hist.do_send_to_history;
             bezier.positions = bezier_hist.positions.clone();
-            bezier.latches = bezier_hist.latches.clone();
+            bezier.latches = Latches(bezier_hist.latches.clone());
             bezier.color = bezier_hist.color.clone();
             bezier.id = bezier_hist.id.into();
             bezier.do_compute_lut = true;
@@ -171,7 +171,7 @@ pub fn spawn_bezier_system(
             // group.find_connected_ends(&bezier_assets, maps.bezier_map.clone());
             // let group_handle: Handle<Group> = Handle::weak(bezier.group.0);
 
-            group.ends = Some(vec![
+            *group.ends = Some(vec![
                 (handle.clone(), AnchorEdge::Start),
                 (handle.clone(), AnchorEdge::End),
             ]);
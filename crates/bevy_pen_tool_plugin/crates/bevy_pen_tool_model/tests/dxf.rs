@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use bevy_pen_tool_model::*;
+
+fn wave() -> Vec<BezierPositions> {
+    vec![
+        BezierPositions {
+            start: Vec2::new(0.0, 0.0),
+            control_start: Vec2::new(20.0, 60.0),
+            control_end: Vec2::new(80.0, 60.0),
+            end: Vec2::new(100.0, 0.0),
+        },
+        BezierPositions {
+            start: Vec2::new(100.0, 0.0),
+            control_start: Vec2::new(120.0, -60.0),
+            control_end: Vec2::new(180.0, -60.0),
+            end: Vec2::new(200.0, 0.0),
+        },
+    ]
+}
+
+#[test]
+fn round_trips_splines() {
+    let group = GroupSaveLoad::from_chain(&wave(), Some(Color::RED), 100).unwrap();
+
+    let contents = write_dxf(
+        &[DxfExportPath::from_group(&group, "group0")],
+        DxfCurves::Splines,
+    );
+    let imported = dxf_to_groups(&contents, 100).unwrap();
+    assert_eq!(imported.len(), 1);
+
+    let exported_curves = group.path_curves();
+    let imported_curves = imported[0].path_curves();
+    assert_eq!(imported_curves.len(), exported_curves.len());
+    for (exported, imported) in exported_curves.iter().zip(imported_curves.iter()) {
+        assert!(imported.start.distance(exported.start) < 0.01);
+        assert!(imported.control_start.distance(exported.control_start) < 0.01);
+        assert!(imported.control_end.distance(exported.control_end) < 0.01);
+        assert!(imported.end.distance(exported.end) < 0.01);
+    }
+}
+
+#[test]
+fn converts_splines_with_simple_knots() {
+    // a quadratic spline with one inner knot: two quadratic curves that meet at (2, 1)
+    let contents = "\
+  0\nSECTION\n  2\nENTITIES\n  0\nSPLINE\n  8\nparts\n 70\n8\n 71\n2\n 72\n7\n 73\n4\n\
+ 40\n0\n 40\n0\n 40\n0\n 40\n1\n 40\n2\n 40\n2\n 40\n2\n\
+ 10\n0\n 20\n0\n 10\n1\n 20\n2\n 10\n3\n 20\n0\n 10\n4\n 20\n2\n\
+  0\nENDSEC\n  0\nEOF\n";
+
+    let imported = dxf_to_groups(contents, 100).unwrap();
+    let curves = imported[0].path_curves();
+    assert_eq!(curves.len(), 2);
+    assert!(curves[0].start.distance(Vec2::new(0.0, 0.0)) < 1e-4);
+    assert!(curves[0].end.distance(Vec2::new(2.0, 1.0)) < 1e-4);
+    assert!(curves[1].end.distance(Vec2::new(4.0, 2.0)) < 1e-4);
+}
+
+#[test]
+fn closes_polylines_of_a_layer() {
+    let contents = "\
+  0\nSECTION\n  2\nENTITIES\n  0\nLWPOLYLINE\n  8\noutline\n 90\n4\n 70\n1\n\
+ 10\n0\n 20\n0\n 10\n10\n 20\n0\n 10\n10\n 20\n10\n 10\n0\n 20\n10\n\
+  0\nLINE\n  8\nother\n 10\n20\n 20\n0\n 11\n30\n 21\n0\n\
+  0\nENDSEC\n  0\nEOF\n";
+
+    let imported = dxf_to_groups(contents, 100).unwrap();
+    assert_eq!(imported.len(), 2);
+    assert!(imported[0].is_closed());
+    assert_eq!(imported[0].lut.len(), 4);
+    assert!(!imported[1].is_closed());
+}
+
+// the code and value pairs of a file, in order
+fn pairs(contents: &str) -> Vec<(i32, &str)> {
+    contents
+        .lines()
+        .collect::<Vec<&str>>()
+        .chunks(2)
+        .map(|chunk| (chunk[0].trim().parse().unwrap(), chunk[1]))
+        .collect()
+}
+
+#[test]
+fn writes_handles_in_files_with_splines() {
+    let group = GroupSaveLoad::from_chain(&wave(), Some(Color::RED), 100).unwrap();
+    let contents = write_dxf(
+        &[
+            DxfExportPath::from_group(&group, "group0"),
+            DxfExportPath::from_group(&group, "group1"),
+        ],
+        DxfCurves::Splines,
+    );
+    let pairs = pairs(&contents);
+
+    assert_eq!(pairs[3], (1, "AC1015"));
+    assert_eq!(pairs[4], (9, "$HANDSEED"));
+
+    // the layer table, its two records and the two splines
+    let handles = pairs
+        .iter()
+        .filter(|(code, _)| *code == 5)
+        .map(|(_, handle)| *handle)
+        .collect::<Vec<&str>>();
+    assert_eq!(handles[1..], ["1", "2", "3", "4", "5"]);
+    assert_eq!(handles[0], "6");
+
+    let spline = pairs
+        .iter()
+        .position(|pair| *pair == (0, "SPLINE"))
+        .unwrap();
+    assert_eq!(pairs[spline + 1].0, 5);
+    assert_eq!(pairs[spline + 2], (100, "AcDbEntity"));
+    assert!(pairs.contains(&(420, "16711680")));
+    assert!(pairs.contains(&(100, "AcDbLayerTableRecord")));
+}
+
+#[test]
+fn writes_polylines_as_r12() {
+    let group = GroupSaveLoad::from_chain(&wave(), Some(Color::rgb(0.9, 0.1, 0.0)), 100).unwrap();
+    let contents = write_dxf(
+        &[DxfExportPath::from_group(&group, "group0")],
+        DxfCurves::Polylines,
+    );
+    let pairs = pairs(&contents);
+
+    assert_eq!(pairs[3], (1, "AC1009"));
+    assert!(pairs.iter().all(|(code, _)| ![5, 100, 420].contains(code)));
+    // the nearest color of the palette
+    assert!(pairs.contains(&(62, "1")));
+
+    let imported = dxf_to_groups(&contents, 100).unwrap();
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].lut[0].0.color, Some(Color::RED));
+    let end = imported[0].path_curves().last().unwrap().end;
+    assert!(end.distance(Vec2::new(200.0, 0.0)) < 0.01);
+}
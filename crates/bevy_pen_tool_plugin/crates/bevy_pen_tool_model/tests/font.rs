@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use bevy_pen_tool_model::*;
+
+use std::path::PathBuf;
+
+// a font of 1000 units per em with three glyphs, drawn with the TrueType orientation: a square
+// "O" with a square hole, an "A" made of a quadratic arch, and a "V", kerned by -100 after "A"
+fn font() -> Vec<u8> {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("fixtures");
+    path.push("fonts");
+    path.push("shapes.ttf");
+    std::fs::read(path).unwrap()
+}
+
+fn signed_area(group: &GroupSaveLoad) -> f32 {
+    let points = group
+        .path_curves()
+        .iter()
+        .map(|curve| curve.start)
+        .collect::<Vec<Vec2>>();
+    let n = points.len();
+    (0..n)
+        .map(|k| points[k].perp_dot(points[(k + 1) % n]))
+        .sum::<f32>()
+        / 2.0
+}
+
+fn min_x(group: &GroupSaveLoad) -> f32 {
+    group.bounding_box().unwrap().0.x
+}
+
+#[test]
+fn turns_contours_into_oriented_groups() {
+    let groups = text_to_groups(&font(), "O", &TextLayout::default(), 100).unwrap();
+    assert_eq!(groups.len(), 2);
+    assert!(groups.iter().all(GroupSaveLoad::is_closed));
+    assert_eq!(groups[0].metadata()["glyph"], "O");
+    assert_eq!(
+        groups[0].metadata()[OUTLINE_METADATA],
+        groups[1].metadata()[OUTLINE_METADATA]
+    );
+
+    // the outer square runs counter-clockwise and the hole clockwise, at 0.1 unit per font unit
+    let mut areas = groups.iter().map(signed_area).collect::<Vec<f32>>();
+    areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert!((areas[0] + 1500.0).abs() < 0.1);
+    assert!((areas[1] - 3500.0).abs() < 0.1);
+
+    // the quadratic arch and the line that closes it
+    let groups = text_to_groups(&font(), "A", &TextLayout::default(), 100).unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].lut.len(), 2);
+    assert!(groups[0].is_closed());
+}
+
+#[test]
+fn lays_out_lines_with_kerning() {
+    let kerned = text_to_groups(&font(), "AV", &TextLayout::default(), 100).unwrap();
+    assert!((min_x(&kerned[1]) - 60.0).abs() < 0.01);
+
+    let layout = TextLayout {
+        kerning: false,
+        letter_spacing: 5.0,
+        ..Default::default()
+    };
+    let spaced = text_to_groups(&font(), "AV", &layout, 100).unwrap();
+    assert!((min_x(&spaced[1]) - 75.0).abs() < 0.01);
+
+    // spaces only move the next glyph, and the second line starts one line height lower
+    let layout = TextLayout {
+        align: TextAlign::Center,
+        ..Default::default()
+    };
+    let lines = text_to_groups(&font(), "O O\nO", &layout, 100).unwrap();
+    assert_eq!(lines.len(), 6);
+    // only the contours of the same glyph cut each other out
+    assert_ne!(
+        lines[0].metadata()[OUTLINE_METADATA],
+        lines[2].metadata()[OUTLINE_METADATA]
+    );
+    let (min, max) = lines[4].bounding_box().unwrap();
+    assert!((min.x + 25.0).abs() < 0.01 && (max.x - 25.0).abs() < 0.01);
+    assert!((min.y + 100.0).abs() < 0.01);
+
+    assert!(matches!(
+        text_to_groups(&font(), " ", &layout, 100),
+        Err(ConversionError::Empty)
+    ));
+}
@@ -0,0 +1,96 @@
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use bevy_pen_tool_model::*;
+
+const ROADS: &str = r##"{
+    "type": "FeatureCollection",
+    "features": [
+        {
+            "type": "Feature",
+            "id": 7,
+            "properties": { "highway": "primary", "lanes": 2, "stroke": "#ff0000" },
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [[2.35, 48.85], [2.351, 48.851], [2.352, 48.851]]
+            }
+        },
+        {
+            "type": "Feature",
+            "properties": { "landuse": "park" },
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [[[2.35, 48.85], [2.36, 48.85], [2.36, 48.86], [2.35, 48.85]]]
+            }
+        }
+    ]
+}"##;
+
+#[test]
+fn imports_lines_and_polygons() {
+    let projection = GeoJsonProjection {
+        fit_max_error: None,
+        ..Default::default()
+    };
+    let groups = geojson_to_groups(ROADS, &projection, 100).unwrap();
+    assert_eq!(groups.len(), 2);
+
+    assert!(!groups[0].is_closed());
+    assert_eq!(groups[0].lut.len(), 2);
+    let metadata = groups[0].metadata();
+    assert_eq!(metadata["highway"], "primary");
+    assert_eq!(metadata["lanes"], "2");
+    assert_eq!(metadata["id"], "7");
+    assert_eq!(groups[0].lut[0].0.color, Some(Color::rgb(1.0, 0.0, 0.0)));
+
+    // the ring repeats its first position, which does not give an extra curve
+    assert!(groups[1].is_closed());
+    assert_eq!(groups[1].lut.len(), 3);
+    assert_eq!(groups[1].metadata()["landuse"], "park");
+}
+
+#[test]
+fn projects_back_and_forth() {
+    for projection in [
+        GeoProjection::Planar,
+        GeoProjection::Equirectangular,
+        GeoProjection::WebMercator,
+    ] {
+        let projection = GeoJsonProjection {
+            projection,
+            origin: Some(DVec2::new(2.35, 48.85)),
+            scale: 0.5,
+            fit_max_error: None,
+        };
+        let point = DVec2::new(2.36, 48.86);
+        let back = projection.unproject(projection.project(point));
+        assert!(back.distance(point) < 1e-6);
+    }
+}
+
+#[test]
+fn exports_metadata_as_properties() {
+    let projection = GeoJsonProjection {
+        origin: Some(DVec2::new(2.35, 48.85)),
+        ..Default::default()
+    };
+    let groups = geojson_to_groups(ROADS, &projection, 100).unwrap();
+    let paths = groups
+        .iter()
+        .map(GeoJsonExportPath::from_group)
+        .collect::<Vec<GeoJsonExportPath>>();
+
+    let contents = write_geojson(&paths, &projection);
+    let document: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    let features = document["features"].as_array().unwrap();
+
+    assert_eq!(features[0]["geometry"]["type"], "LineString");
+    assert_eq!(features[0]["properties"]["lanes"], 2);
+    assert_eq!(features[0]["properties"]["highway"], "primary");
+    assert_eq!(features[1]["geometry"]["type"], "Polygon");
+
+    let ring = features[1]["geometry"]["coordinates"][0]
+        .as_array()
+        .unwrap();
+    assert_eq!(ring.first(), ring.last());
+}
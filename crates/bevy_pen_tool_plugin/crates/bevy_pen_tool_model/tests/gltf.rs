@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+
+use bevy_pen_tool_model::*;
+use serde_json::Value;
+
+const FILL_COLOR: [f32; 4] = [0.5, 0.25, 1.0, 0.8];
+
+// a triangle whose vertex colors hold the fill color, as in the editor
+fn triangle(material: GltfMaterial) -> GltfMesh {
+    let mut mesh = uncolored_triangle();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![FILL_COLOR; 3]);
+
+    GltfMesh::from_mesh(
+        "triangle",
+        &mesh,
+        Transform::from_xyz(5.0, 0.0, 0.0),
+        material,
+    )
+    .unwrap()
+}
+
+fn uncolored_triangle() -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [0.0, 20.0, 0.0]],
+    );
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2])));
+    mesh
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+// splits a .glb file into its json and binary chunks, checking the header
+fn read_glb(glb: &[u8]) -> (Value, Vec<u8>) {
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(u32_at(glb, 4), 2);
+    assert_eq!(u32_at(glb, 8) as usize, glb.len());
+
+    let json_length = u32_at(glb, 12) as usize;
+    assert_eq!(&glb[16..20], b"JSON");
+    assert_eq!(json_length % 4, 0);
+    let document = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+
+    let bin_start = 20 + json_length;
+    let bin_length = u32_at(glb, bin_start) as usize;
+    assert_eq!(&glb[bin_start + 4..bin_start + 8], b"BIN\0");
+    assert_eq!(bin_start + 8 + bin_length, glb.len());
+    let buffer = glb[bin_start + 8..].to_vec();
+
+    (document, buffer)
+}
+
+// the floats of an accessor, read through its buffer view
+fn accessor_floats(document: &Value, buffer: &[u8], accessor: usize) -> Vec<f32> {
+    let accessor = &document["accessors"][accessor];
+    assert_eq!(accessor["componentType"], 5126);
+    let view = &document["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+    let offset = view["byteOffset"].as_u64().unwrap() as usize;
+    let length = view["byteLength"].as_u64().unwrap() as usize;
+    assert_eq!(offset % 4, 0);
+
+    buffer[offset..offset + length]
+        .chunks(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect()
+}
+
+// the base color factor of the material of a primitive
+fn base_color_factor(document: &Value, primitive: &Value) -> Vec<f32> {
+    let material = &document["materials"][primitive["material"].as_u64().unwrap() as usize];
+    material["pbrMetallicRoughness"]["baseColorFactor"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_f64().unwrap() as f32)
+        .collect()
+}
+
+#[test]
+fn writes_fill_meshes() {
+    let glb = write_glb(&[triangle(GltfMaterial::Color(Vec4::from(FILL_COLOR)))]).unwrap();
+    let (document, buffer) = read_glb(&glb);
+
+    assert_eq!(document["asset"]["version"], "2.0");
+    assert_eq!(document["buffers"][0]["byteLength"], buffer.len());
+    assert_eq!(
+        document["nodes"][0]["translation"],
+        serde_json::json!([5.0, 0.0, 0.0])
+    );
+
+    let primitive = &document["meshes"][0]["primitives"][0];
+    let attributes = primitive["attributes"].as_object().unwrap();
+    assert_eq!(attributes.len(), 4);
+    // positions, normals, uvs, colors and indices
+    assert_eq!(document["accessors"].as_array().unwrap().len(), 5);
+
+    let positions = attributes["POSITION"].as_u64().unwrap() as usize;
+    assert_eq!(document["accessors"][positions]["count"], 3);
+    assert_eq!(document["accessors"][positions]["type"], "VEC3");
+    assert_eq!(
+        document["accessors"][positions]["max"],
+        serde_json::json!([10.0, 20.0, 0.0])
+    );
+    assert_eq!(
+        accessor_floats(&document, &buffer, positions),
+        vec![0.0, 0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 20.0, 0.0]
+    );
+
+    let indices = primitive["indices"].as_u64().unwrap() as usize;
+    assert_eq!(document["accessors"][indices]["count"], 3);
+    assert_eq!(document["accessors"][indices]["componentType"], 5125);
+
+    assert!(document.get("textures").is_none());
+    assert!(document.get("samplers").is_none());
+}
+
+#[test]
+fn keeps_the_vertex_colors_of_fill_meshes() {
+    let glb = write_glb(&[triangle(GltfMaterial::Color(Vec4::from(FILL_COLOR)))]).unwrap();
+    let (document, buffer) = read_glb(&glb);
+
+    let primitive = &document["meshes"][0]["primitives"][0];
+    let colors = primitive["attributes"]["COLOR_0"].as_u64().unwrap() as usize;
+    assert_eq!(document["accessors"][colors]["type"], "VEC4");
+    assert_eq!(document["accessors"][colors]["count"], 3);
+    assert_eq!(
+        accessor_floats(&document, &buffer, colors),
+        FILL_COLOR.repeat(3)
+    );
+
+    // the vertex colors are not tinted a second time
+    assert_eq!(base_color_factor(&document, primitive), vec![1.0; 4]);
+}
+
+#[test]
+fn colors_fill_meshes_without_vertex_colors_with_their_material() {
+    let mesh = GltfMesh::from_mesh(
+        "triangle",
+        &uncolored_triangle(),
+        Transform::identity(),
+        GltfMaterial::Color(Vec4::from(FILL_COLOR)),
+    )
+    .unwrap();
+    assert!(mesh.colors.is_none());
+    let (document, _) = read_glb(&write_glb(&[mesh]).unwrap());
+
+    let primitive = &document["meshes"][0]["primitives"][0];
+    assert!(primitive["attributes"].get("COLOR_0").is_none());
+    assert_eq!(document["accessors"].as_array().unwrap().len(), 4);
+    assert_eq!(base_color_factor(&document, primitive), FILL_COLOR.to_vec());
+}
+
+#[test]
+fn tints_textures_with_vertex_colors() {
+    let texture = GltfMaterial::Texture {
+        mime_type: "image/png".to_string(),
+        bytes: vec![1, 2, 3],
+    };
+    let (document, buffer) = read_glb(&write_glb(&[triangle(texture)]).unwrap());
+
+    let primitive = &document["meshes"][0]["primitives"][0];
+    let colors = primitive["attributes"]["COLOR_0"].as_u64().unwrap() as usize;
+    assert_eq!(document["accessors"][colors]["type"], "VEC4");
+    assert_eq!(
+        accessor_floats(&document, &buffer, colors),
+        FILL_COLOR.repeat(3)
+    );
+
+    let pbr = &document["materials"][0]["pbrMetallicRoughness"];
+    assert!(pbr.get("baseColorFactor").is_none());
+    assert_eq!(pbr["baseColorTexture"]["index"], 0);
+    assert_eq!(document["images"][0]["mimeType"], "image/png");
+}
+
+#[test]
+fn embeds_the_buffer_of_gltf_files() {
+    let gltf = write_gltf(&[triangle(GltfMaterial::Color(Vec4::ONE))]).unwrap();
+    let document: Value = serde_json::from_str(&gltf).unwrap();
+
+    let uri = document["buffers"][0]["uri"].as_str().unwrap();
+    let buffer =
+        base64::decode(uri.trim_start_matches("data:application/octet-stream;base64,")).unwrap();
+    assert_eq!(document["buffers"][0]["byteLength"], buffer.len());
+    assert_eq!(
+        accessor_floats(&document, &buffer, 0),
+        vec![0.0, 0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 20.0, 0.0]
+    );
+
+    assert!(matches!(write_gltf(&[]), Err(ConversionError::Empty)));
+}
@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use bevy_pen_tool_model::*;
+
+#[test]
+fn snaps_to_grid_lines_and_guides() {
+    let grid = Grid {
+        visible: true,
+        spacing: 100.0,
+        subdivisions: 4,
+        snap: true,
+    };
+    let guides = vec![
+        Guide::new(GuideAxis::Vertical, 52.0),
+        Guide::new(GuideAxis::Horizontal, -10.0),
+    ];
+
+    // x goes to the guide at 52 rather than the grid line at 50, y to the grid line at 25
+    let snapped = snap_point(Vec2::new(51.0, 27.0), &grid, &guides, 5.0);
+    assert_eq!(snapped, Vec2::new(52.0, 25.0));
+
+    // coordinates too far from any line are left alone
+    let snapped = snap_point(Vec2::new(62.0, 12.0), &grid, &guides, 5.0);
+    assert_eq!(snapped, Vec2::new(62.0, 12.0));
+
+    // a hidden grid is not a snap target, the guides still are
+    let hidden = Grid {
+        visible: false,
+        ..grid.clone()
+    };
+    let snapped = snap_point(Vec2::new(49.0, -12.0), &hidden, &guides, 5.0);
+    assert_eq!(snapped, Vec2::new(52.0, -10.0));
+    let snapped = snap_point(Vec2::new(99.0, 74.0), &hidden, &[], 5.0);
+    assert_eq!(snapped, Vec2::new(99.0, 74.0));
+
+    // turning snapping off leaves points alone, even next to a guide
+    let off = Grid {
+        snap: false,
+        ..grid.clone()
+    };
+    let snapped = snap_point(Vec2::new(51.0, 27.0), &off, &guides, 5.0);
+    assert_eq!(snapped, Vec2::new(51.0, 27.0));
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use bevy_pen_tool_model::*;
+
+use std::collections::HashSet;
+
+fn square() -> Vec<BezierPositions> {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn closes_chains_whose_ends_meet() {
+    let closed = GroupSaveLoad::from_chain(&square(), None, 100).unwrap();
+    assert!(closed.is_closed());
+    assert_eq!(closed.standalone_lut.lut.len(), 100);
+    assert!((closed.standalone_lut.path_length - 400.0).abs() < 1.0);
+
+    let open = GroupSaveLoad::from_chain(&square()[..3], None, 100).unwrap();
+    assert!(!open.is_closed());
+    assert!(!open.lut[0].0.latches.contains_key(&AnchorEdge::Start));
+    assert!(!open.lut[2].0.latches.contains_key(&AnchorEdge::End));
+    assert!(open.standalone_lut.lut[99].distance(Vec2::new(0.0, 100.0)) < 1.0);
+}
+
+#[test]
+fn separates_controls_that_sit_on_their_anchors() {
+    let curve = BezierPositions {
+        start: Vec2::ZERO,
+        end: Vec2::new(100.0, 0.0),
+        control_start: Vec2::ZERO,
+        control_end: Vec2::new(100.0, 0.0),
+    };
+    let group = GroupSaveLoad::from_chain(&[curve], None, 50).unwrap();
+
+    let positions = group.lut[0].0.positions;
+    assert_ne!(positions.control_start, positions.start);
+    assert_ne!(positions.control_end, positions.end);
+    assert!(group
+        .standalone_lut
+        .lut
+        .iter()
+        .all(|point| point.is_finite()));
+}
+
+#[test]
+fn rejects_degenerate_chains() {
+    let point = BezierPositions {
+        start: Vec2::ONE,
+        end: Vec2::ONE,
+        control_start: Vec2::ONE,
+        control_end: Vec2::ONE,
+    };
+    for curves in [vec![], vec![point], vec![point, point]] {
+        assert!(matches!(
+            GroupSaveLoad::from_chain(&curves, None, 10),
+            Err(ConversionError::Degenerate(_))
+        ));
+    }
+
+    // the samples are spread from the start to the end of the chain
+    for num_points in [0, 1] {
+        assert!(matches!(
+            GroupSaveLoad::from_chain(&square(), None, num_points),
+            Err(ConversionError::Degenerate(_))
+        ));
+    }
+    let mut group = GroupSaveLoad::from_chain(&square(), None, 2).unwrap();
+    assert!(group.standalone_lut.lut.iter().all(|p| p.is_finite()));
+    assert!(group.compute_standalone_lut(1).is_err());
+}
+
+#[test]
+fn samples_chains_with_a_curve_without_length() {
+    let mut curves = square();
+    let corner = curves[1].start;
+    curves.insert(
+        1,
+        BezierPositions {
+            start: corner,
+            end: corner,
+            control_start: corner,
+            control_end: corner,
+        },
+    );
+
+    let group = GroupSaveLoad::from_chain(&curves, None, 100).unwrap();
+    assert!(group.is_closed());
+    assert!(group
+        .standalone_lut
+        .lut
+        .iter()
+        .all(|point| point.is_finite()));
+}
+
+#[test]
+fn ignores_empty_groups() {
+    let mut groups = vec![GroupSaveLoad {
+        lut: Vec::new(),
+        standalone_lut: StandaloneLut {
+            path_length: 0.0,
+            lut: Vec::new(),
+        },
+    }];
+    assert!(!groups[0].is_closed());
+    assert!(groups[0].bounding_box().is_none());
+
+    // nothing to move and no ids to renew
+    center_groups_on(&mut groups, Vec2::ONE);
+    renew_ids(&mut groups);
+    assert!(groups[0].lut.is_empty());
+}
+
+#[test]
+fn renews_ids_and_keeps_latches() {
+    let original = GroupSaveLoad::from_chain(&square(), None, 100).unwrap();
+    let mut groups = vec![original.clone()];
+    renew_ids(&mut groups);
+
+    let old_ids = original
+        .lut
+        .iter()
+        .map(|(bezier, _, _, _)| bezier.id)
+        .collect::<HashSet<BezierId>>();
+    let new_ids = groups[0]
+        .lut
+        .iter()
+        .map(|(bezier, _, _, _)| bezier.id)
+        .collect::<HashSet<BezierId>>();
+    assert_eq!(new_ids.len(), 4);
+    assert!(old_ids.is_disjoint(&new_ids));
+    assert_ne!(groups[0].lut[0].0.group, original.lut[0].0.group);
+
+    for (bezier, _, _, _) in groups[0].lut.iter() {
+        assert_eq!(bezier.latches.len(), 2);
+        assert!(bezier
+            .latches
+            .values()
+            .all(|latch| new_ids.contains(&latch.latched_to_id)));
+    }
+    assert!(groups[0].is_closed());
+}
+
+#[test]
+fn centers_groups_on_a_position() {
+    let mut groups = vec![
+        GroupSaveLoad::from_chain(&square(), None, 100).unwrap(),
+        GroupSaveLoad::from_chain(&square(), None, 100).unwrap(),
+    ];
+    groups[1].translate(Vec2::new(200.0, 0.0));
+
+    center_groups_on(&mut groups, Vec2::new(500.0, -200.0));
+    let (min, max) = groups
+        .iter()
+        .filter_map(GroupSaveLoad::bounding_box)
+        .reduce(|(min0, max0), (min1, max1)| (min0.min(min1), max0.max(max1)))
+        .unwrap();
+    assert!(((min + max) / 2.0).distance(Vec2::new(500.0, -200.0)) < 0.01);
+    assert!((max.x - min.x - 300.0).abs() < 0.01);
+}
+
+#[test]
+fn fits_curves_through_look_up_tables() {
+    let group = GroupSaveLoad::from_chain(&square(), None, 200).unwrap();
+    let lut = &group.standalone_lut.lut;
+    let curves = group.standalone_lut.fit_curves(1.0);
+
+    assert!(!curves.is_empty());
+    assert!(curves[0].start.distance(lut[0]) < 1.0);
+    assert!(curves[curves.len() - 1].end.distance(lut[lut.len() - 1]) < 1.0);
+}
+
+#[test]
+fn fits_no_curves_through_too_few_points() {
+    for points in [vec![], vec![Vec2::ONE]] {
+        let lut = StandaloneLut {
+            path_length: 0.0,
+            lut: points,
+        };
+        assert!(lut.fit_curves(1.0).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use bevy_pen_tool_model::*;
+
+fn square() -> Vec<BezierPositions> {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_closed_groups() {
+    let group = GroupSaveLoad::from_chain(&square(), Some(Color::RED), 100).unwrap();
+    assert!(group.is_closed());
+
+    let contents = write_lottie(
+        &[LottieExportPath::from_group(&group, None)],
+        Some(LottieAnimation::default()),
+    );
+    let imported = lottie_to_groups(&contents, 100).unwrap();
+
+    // the follower layer is an ellipse, which is not imported
+    assert_eq!(imported.len(), 1);
+    assert!(imported[0].is_closed());
+
+    let exported_curves = group.path_curves();
+    let imported_curves = imported[0].path_curves();
+    assert_eq!(imported_curves.len(), exported_curves.len());
+
+    // the document is moved so that its top left corner is at the origin
+    let offset = imported_curves[0].start - exported_curves[0].start;
+    for (exported, imported) in exported_curves.iter().zip(imported_curves.iter()) {
+        assert!(imported.start.distance(exported.start + offset) < 0.01);
+        assert!(
+            imported
+                .control_start
+                .distance(exported.control_start + offset)
+                < 0.01
+        );
+        assert!(imported.control_end.distance(exported.control_end + offset) < 0.01);
+        assert!(imported.end.distance(exported.end + offset) < 0.01);
+    }
+}
+
+#[test]
+fn applies_layer_transforms() {
+    let contents = r#"{
+        "v": "5.7.4", "fr": 30, "ip": 0, "op": 30, "w": 100, "h": 100,
+        "layers": [{
+            "ty": 4, "ind": 1,
+            "ks": { "p": { "a": 0, "k": [10, 20, 0] }, "s": { "a": 0, "k": [200, 200, 100] } },
+            "shapes": [{
+                "ty": "sh",
+                "ks": { "a": 0, "k": {
+                    "v": [[0, 0], [5, 0]], "i": [[0, 0], [0, 0]], "o": [[1, 1], [0, 0]], "c": false
+                } }
+            }]
+        }]
+    }"#;
+
+    let groups = lottie_to_groups(contents, 100).unwrap();
+    let curves = groups[0].path_curves();
+    assert_eq!(curves.len(), 1);
+    // scaled by 2, moved by (10, 20), and the y axis flipped
+    assert!(curves[0].start.distance(Vec2::new(10.0, -20.0)) < 1e-4);
+    assert!(curves[0].control_start.distance(Vec2::new(12.0, -22.0)) < 1e-4);
+    assert!(curves[0].end.distance(Vec2::new(20.0, -20.0)) < 1e-4);
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+
+use bevy_pen_tool_model::*;
+
+fn count_lines(contents: &str, keyword: &str) -> usize {
+    contents
+        .lines()
+        .filter(|line| line.split_whitespace().next() == Some(keyword))
+        .count()
+}
+
+// vertices that are not part of a face are not written
+fn used_vertices(mesh: &Mesh) -> usize {
+    mesh.indices()
+        .unwrap()
+        .iter()
+        .collect::<std::collections::HashSet<usize>>()
+        .len()
+}
+
+fn uvs(mesh: &Mesh) -> Vec<[f32; 2]> {
+    match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) => uvs.clone(),
+        _ => panic!("the mesh has no uvs"),
+    }
+}
+
+fn square() -> Vec<BezierPositions> {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_road_meshes() {
+    let mut group = OfflineGroup::from_save(
+        &GroupSaveLoad::from_chain(&square(), Some(Color::RED), 100).unwrap(),
+    );
+    group.compute_lut(100);
+    let (mesh, _) = group.road_mesh(100, 8.0, Color::WHITE);
+    let num_triangles = mesh.indices().unwrap().len() / 3;
+
+    let material = ObjMaterial {
+        name: "road".to_string(),
+        color: Color::WHITE,
+        texture: Some("road.png".to_string()),
+    };
+    let bytes = write_obj(&mesh, "road0", "road0.mtl", &material).unwrap();
+    let contents = String::from_utf8(bytes).unwrap();
+
+    // uvs and normals are written once per vertex
+    assert_eq!(count_lines(&contents, "v"), mesh.count_vertices());
+    assert_eq!(count_lines(&contents, "vt"), mesh.count_vertices());
+    assert_eq!(count_lines(&contents, "vn"), mesh.count_vertices());
+    assert_eq!(count_lines(&contents, "f"), num_triangles);
+    assert_eq!(count_lines(&contents, "usemtl"), 1);
+
+    let obj = parse_obj(&contents).unwrap();
+    assert_eq!(obj.material_library.as_deref(), Some("road0.mtl"));
+    assert_eq!(obj.material_name.as_deref(), Some("road"));
+    assert_eq!(obj.mesh.count_vertices(), used_vertices(&mesh));
+    assert_eq!(obj.mesh.indices().unwrap().len(), num_triangles * 3);
+
+    // the v axis is flipped when writing and flipped back when reading
+    let (read_uvs, written_uvs) = (uvs(&obj.mesh), uvs(&mesh));
+    for (read, written) in obj
+        .mesh
+        .indices()
+        .unwrap()
+        .iter()
+        .zip(mesh.indices().unwrap().iter())
+    {
+        assert!((read_uvs[read][0] - written_uvs[written][0]).abs() < 1e-5);
+        assert!((read_uvs[read][1] - written_uvs[written][1]).abs() < 1e-5);
+    }
+
+    let materials = parse_mtl(&write_mtl(&material)).unwrap();
+    assert_eq!(materials.len(), 1);
+    assert_eq!(materials["road"], material);
+}
+
+#[test]
+fn round_trips_fill_materials() {
+    let group = OfflineGroup::from_save(&GroupSaveLoad::from_chain(&square(), None, 100).unwrap());
+    let (mesh, _) = group.fill_mesh(Color::BLUE).unwrap();
+
+    let material = ObjMaterial {
+        name: "fill".to_string(),
+        color: Color::rgba(0.5, 0.25, 1.0, 0.75),
+        texture: None,
+    };
+    let contents =
+        String::from_utf8(write_obj(&mesh, "fill0", "fill0.mtl", &material).unwrap()).unwrap();
+    let obj = parse_obj(&contents).unwrap();
+    assert_eq!(obj.mesh.count_vertices(), used_vertices(&mesh));
+    assert_eq!(
+        obj.mesh.indices().unwrap().len(),
+        mesh.indices().unwrap().len()
+    );
+
+    let mtl = write_mtl(&material);
+    assert!(mtl.contains("Kd 0.500000 0.250000 1.000000"));
+    assert!(mtl.contains("d 0.750000"));
+    assert!(!mtl.contains("map_Kd"));
+
+    // a second material in the same library is read as well
+    let library = format!(
+        "{}\n{}",
+        mtl,
+        write_mtl(&ObjMaterial {
+            name: "other".to_string(),
+            color: Color::BLACK,
+            texture: None,
+        })
+    );
+    let materials = parse_mtl(&library).unwrap();
+    assert_eq!(materials.len(), 2);
+    assert_eq!(materials["fill"], material);
+    assert_eq!(materials["other"].color, Color::BLACK);
+}
+
+#[test]
+fn reads_texture_coordinates_without_v() {
+    let contents = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5\nvt 1 0.25\nvt 0.75 0.5 0\nf 1/1 2/2 3/3";
+    let obj = parse_obj(contents).unwrap();
+
+    // a missing v is 0, and the v axis is flipped when reading
+    assert_eq!(uvs(&obj.mesh), vec![[0.5, 1.0], [1.0, 0.75], [0.75, 0.5]]);
+    assert!(matches!(
+        parse_obj("v 0 0 0\nvt\nf 1/1 1/1 1/1"),
+        Err(ConversionError::Syntax(_))
+    ));
+}
+
+#[test]
+fn rejects_meshes_that_are_not_triangle_lists() {
+    let (mut mesh, _) =
+        OfflineGroup::from_save(&GroupSaveLoad::from_chain(&square(), None, 100).unwrap())
+            .fill_mesh(Color::BLUE)
+            .unwrap();
+    let material = ObjMaterial {
+        name: "fill".to_string(),
+        color: Color::BLUE,
+        texture: None,
+    };
+
+    let mut indices = mesh.indices().unwrap().iter().collect::<Vec<usize>>();
+    indices.pop();
+    mesh.set_indices(Some(Indices::U32(
+        indices.iter().map(|i| *i as u32).collect(),
+    )));
+    assert!(matches!(
+        write_obj(&mesh, "fill0", "fill0.mtl", &material),
+        Err(ConversionError::Syntax(_))
+    ));
+
+    assert!(matches!(
+        parse_obj("v 0 0 0\nv 1 0 0\nf 1 2"),
+        Err(ConversionError::Syntax(_))
+    ));
+    assert!(matches!(parse_obj("v 0 0 0"), Err(ConversionError::Empty)));
+}
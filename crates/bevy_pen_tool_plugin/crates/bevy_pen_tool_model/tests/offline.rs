@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+
+use bevy_pen_tool_model::*;
+
+use std::path::PathBuf;
+
+fn fixture(version: &str, name: &str) -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("fixtures");
+    path.push(version);
+    path.push(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn regenerates_lut_without_spawning() {
+    let groups =
+        Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap();
+
+    let mut group = OfflineGroup::from_save(&groups[0]);
+    group.compute_lut(250);
+
+    assert!(group.is_connected());
+    assert_eq!(group.group.standalone_lut.lut.len(), 250);
+    assert!(
+        (group.group.standalone_lut.path_length - groups[0].standalone_lut.path_length).abs()
+            < 1e-3
+    );
+
+    let saved = group.to_save();
+    assert_eq!(saved.lut.len(), groups[0].lut.len());
+    assert_eq!(saved.standalone_lut.lut.len(), 250);
+
+    let (mesh, (min, max)) = group.road_mesh(250, 8.0, Color::WHITE);
+    assert_eq!(mesh.count_vertices(), 2 * 250 + 2);
+    assert!(min.x < max.x && min.y < max.y);
+}
+
+#[test]
+fn traces_mesh_outlines() {
+    // two triangles making a square, with the shared corners duplicated as with uv seams
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ],
+    );
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 3, 4, 5])));
+
+    let outlines = mesh_outlines(&mesh).unwrap();
+    assert_eq!(outlines.len(), 1);
+    assert_eq!(outlines[0].len(), 5);
+    assert_eq!(outlines[0].first(), outlines[0].last());
+    assert!(!outlines[0].contains(&Vec2::new(0.5, 0.5)));
+}
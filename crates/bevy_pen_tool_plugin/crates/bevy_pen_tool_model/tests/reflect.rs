@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use bevy::reflect::{
+    serde::{ReflectDeserializer, ReflectSerializer},
+    FromReflect, Reflect, ReflectDeserialize, ReflectRef, TypeRegistry,
+};
+use bevy_pen_tool_model::*;
+
+use serde::de::DeserializeSeed;
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+
+fn registry() -> TypeRegistry {
+    let mut app = App::new();
+    register_model_types(&mut app);
+    app.world.resource::<TypeRegistry>().clone()
+}
+
+// serializes the value to RON through the registry, and reads it back
+fn round_trip<T: Reflect + FromReflect>(value: &T, registry: &TypeRegistry) -> T {
+    let registry = registry.read();
+    let serializer = ReflectSerializer::new(value, &registry);
+    let contents = ron::to_string(&serializer).unwrap();
+
+    let mut deserializer = ron::Deserializer::from_str(&contents).unwrap();
+    let reflected = ReflectDeserializer::new(&registry)
+        .deserialize(&mut deserializer)
+        .unwrap();
+    T::from_reflect(&*reflected).unwrap()
+}
+
+// writes the value with serde, and reads it back through the serde type data of the registry
+fn serde_round_trip<T: Reflect + serde::Serialize>(value: &T, registry: &TypeRegistry) -> T {
+    assert!(value.serializable().is_some());
+    let contents = ron::to_string(value).unwrap();
+
+    let registry = registry.read();
+    let deserialize = registry
+        .get_type_data::<ReflectDeserialize>(TypeId::of::<T>())
+        .unwrap();
+    let mut deserializer = ron::Deserializer::from_str(&contents).unwrap();
+    *deserialize
+        .deserialize(&mut deserializer)
+        .unwrap()
+        .downcast::<T>()
+        .unwrap()
+}
+
+// the names of the fields that an inspector sees
+fn field_names(value: &dyn Reflect) -> Vec<&str> {
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => (0..value.field_len())
+            .map(|k| value.name_at(k).unwrap())
+            .collect(),
+        _ => panic!("{} is not reflected field by field", value.type_name()),
+    }
+}
+
+fn square() -> Vec<BezierPositions> {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_curves() {
+    let registry = registry();
+    let mut group = GroupSaveLoad::from_chain(&square(), Some(Color::ORANGE), 100).unwrap();
+    group.set_metadata(&BTreeMap::from([(
+        "name".to_string(),
+        "square".to_string(),
+    )]));
+    let bezier = group.lut[0].0.clone();
+    assert!(!bezier.latches.is_empty());
+
+    let names = field_names(&bezier);
+    assert!(names.contains(&"positions") && names.contains(&"color"));
+    assert!(names.contains(&"latches") && names.contains(&"metadata"));
+    assert!(!names.contains(&"entity"));
+
+    let reloaded = round_trip(&bezier, &registry);
+    assert_eq!(reloaded.id, bezier.id);
+    assert_eq!(reloaded.group, bezier.group);
+    assert_eq!(reloaded.positions, bezier.positions);
+    assert_eq!(reloaded.color, bezier.color);
+    assert_eq!(reloaded.lut, bezier.lut);
+    assert_eq!(reloaded.latches, bezier.latches);
+    assert_eq!(reloaded.metadata, bezier.metadata);
+
+    let reloaded = serde_round_trip(&bezier, &registry);
+    assert_eq!(reloaded.id, bezier.id);
+    assert_eq!(reloaded.latches, bezier.latches);
+    assert_eq!(reloaded.metadata, bezier.metadata);
+
+    // the parts of a curve are reflected on their own too
+    assert_eq!(round_trip(&bezier.positions, &registry), bezier.positions);
+    assert_eq!(round_trip(&bezier.latches, &registry), bezier.latches);
+    for latch in bezier.latches.values() {
+        assert_eq!(&round_trip(latch, &registry), latch);
+    }
+}
+
+#[test]
+fn round_trips_groups() {
+    let registry = registry();
+    // an open chain, so that the group has two ends
+    let mut offline = OfflineGroup::from_save(
+        &GroupSaveLoad::from_chain(&square()[..3], Some(Color::ORANGE), 100).unwrap(),
+    );
+    offline.compute_lut(100);
+    let group = offline.group;
+    assert_eq!(group.bezier_handles.len(), 3);
+    assert_eq!(group.ends.as_ref().map(Vec::len), Some(2));
+    assert_eq!(group.lut.len(), 3);
+
+    let names = field_names(&group);
+    assert_eq!(
+        names,
+        vec![
+            "group",
+            "bezier_handles",
+            "ends",
+            "lut",
+            "standalone_lut",
+            "id"
+        ]
+    );
+
+    // handles come back as weak handles with the same ids, and the entity is left out
+    let expected = Group {
+        entity: None,
+        ..group.clone()
+    };
+    assert_eq!(round_trip(&group, &registry), expected);
+    assert_eq!(serde_round_trip(&group, &registry), expected);
+    assert_eq!(round_trip(&group.ends, &registry), group.ends);
+    assert_eq!(round_trip(&group.lut, &registry), group.lut);
+    assert_eq!(
+        round_trip(&group.standalone_lut, &registry),
+        group.standalone_lut
+    );
+
+    // saved groups are still reflected as a whole
+    let saved = GroupSaveLoad::from_chain(&square(), Some(Color::ORANGE), 100).unwrap();
+    let reloaded = round_trip(&saved, &registry);
+    assert!(reloaded.is_closed());
+    assert_eq!(
+        serde_json::to_value(&reloaded).unwrap(),
+        serde_json::to_value(&saved).unwrap()
+    );
+}
@@ -0,0 +1,379 @@
+use bevy::prelude::*;
+use bevy_pen_tool_model::*;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+fn fixture(version: &str, name: &str) -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("fixtures");
+    path.push(version);
+    path.push(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+// saves the loaded data with the current version, loads it back and compares both
+fn assert_round_trip<T: SaveFormat>(loaded: &T) {
+    let saved = loaded.to_versioned_string().unwrap();
+    let header: serde_json::Value = serde_json::from_str(&saved).unwrap();
+    assert_eq!(header["version"], SAVE_FORMAT_VERSION);
+
+    let reloaded = T::from_versioned_str(&saved).unwrap();
+    assert_eq!(
+        serde_json::to_value(loaded).unwrap(),
+        serde_json::to_value(&reloaded).unwrap()
+    );
+}
+
+fn assert_latches_are_consistent(groups: &Vec<GroupSaveLoad>) {
+    for group in groups {
+        let ids = group
+            .lut
+            .iter()
+            .map(|(bezier, _, _, _)| bezier.id)
+            .collect::<HashSet<BezierId>>();
+        assert_eq!(ids.len(), group.lut.len());
+
+        for (bezier, _, _, _) in group.lut.iter() {
+            for latch in bezier.latches.values() {
+                assert!(ids.contains(&latch.latched_to_id));
+            }
+        }
+    }
+}
+
+#[test]
+fn loads_v0_legacy_group() {
+    let groups = Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v0", "legacy.group")).unwrap();
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].lut.len(), 5);
+    assert_eq!(groups[0].standalone_lut.lut.len(), 100);
+    assert_latches_are_consistent(&groups);
+
+    // all curves of a legacy group share the group id created during migration
+    let group_ids = groups[0]
+        .lut
+        .iter()
+        .map(|(bezier, _, _, _)| bezier.group)
+        .collect::<HashSet<GroupId>>();
+    assert_eq!(group_ids.len(), 1);
+
+    assert_round_trip(&groups);
+}
+
+#[test]
+fn loads_v0_group() {
+    let groups =
+        Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v0", "my_group.group")).unwrap();
+
+    assert_eq!(groups[0].lut.len(), 3);
+    assert_latches_are_consistent(&groups);
+    assert_round_trip(&groups);
+}
+
+#[test]
+fn loads_v1_group() {
+    let v0 = Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v0", "my_group.group")).unwrap();
+    let v1 = Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&v0).unwrap(),
+        serde_json::to_value(&v1).unwrap()
+    );
+    assert_round_trip(&v1);
+}
+
+#[test]
+fn gives_metadata_to_older_curves() {
+    let v1 = Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap();
+    assert!(v1[0].metadata().is_empty());
+
+    let mut group = v1[0].clone();
+    let metadata = [("name".to_string(), "ring road".to_string())]
+        .into_iter()
+        .collect();
+    group.set_metadata(&metadata);
+    for encoding in [SaveEncoding::Json, SaveEncoding::Ron, SaveEncoding::Bincode] {
+        let saved = vec![group.clone()].to_versioned_bytes(encoding).unwrap();
+        let reloaded = Vec::<GroupSaveLoad>::from_versioned_bytes(&saved).unwrap();
+        assert_eq!(reloaded[0].metadata(), metadata);
+    }
+}
+
+#[test]
+fn loads_lut_of_every_version() {
+    let v0 = StandaloneLut::from_versioned_str(&fixture("v0", "my_group.lut")).unwrap();
+    let v1 = StandaloneLut::from_versioned_str(&fixture("v1", "my_group.lut")).unwrap();
+
+    assert_eq!(v0.lut.len(), 100);
+    assert_eq!(v0, v1);
+    assert_round_trip(&v1);
+}
+
+#[test]
+fn loads_mesh_meta_of_every_version() {
+    let v0 = MeshMeta::from_versioned_str(&fixture("v0", "my_mesh.meta")).unwrap();
+    let v1 = MeshMeta::from_versioned_str(&fixture("v1", "my_mesh.meta")).unwrap();
+
+    assert_eq!(v0.position, v1.position);
+    assert_eq!(v0.bounding_box, v1.bounding_box);
+    assert_eq!(v0.color, v1.color);
+    assert_round_trip(&v1);
+}
+
+#[test]
+fn rejects_newer_versions() {
+    let contents = format!(
+        "{{\"version\": {}, \"data\": {{\"path_length\": 0.0, \"lut\": []}}}}",
+        SAVE_FORMAT_VERSION + 1
+    );
+
+    match StandaloneLut::from_versioned_str(&contents) {
+        Err(SaveFormatError::UnsupportedVersion(version)) => {
+            assert_eq!(version, SAVE_FORMAT_VERSION + 1)
+        }
+        _ => panic!("a file from a newer version should not load"),
+    }
+}
+
+#[test]
+fn reports_malformed_files() {
+    assert!(Vec::<GroupSaveLoad>::from_versioned_str("{\"lut\": 3}").is_err());
+    assert!(StandaloneLut::from_versioned_str("not json").is_err());
+}
+
+// saves with the given encoding and loads back without being told the encoding
+fn assert_encoded_round_trip<T: SaveFormat>(loaded: &T, encoding: SaveEncoding) {
+    let saved = loaded.to_versioned_bytes(encoding).unwrap();
+    assert_eq!(SaveEncoding::detect(&saved), encoding);
+
+    let reloaded = T::from_versioned_bytes(&saved).unwrap();
+    assert_eq!(
+        serde_json::to_value(loaded).unwrap(),
+        serde_json::to_value(&reloaded).unwrap()
+    );
+}
+
+#[test]
+fn round_trips_every_encoding() {
+    let groups =
+        Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap();
+    let lut = StandaloneLut::from_versioned_str(&fixture("v1", "my_group.lut")).unwrap();
+    let meta = MeshMeta::from_versioned_str(&fixture("v1", "my_mesh.meta")).unwrap();
+
+    for encoding in [SaveEncoding::Json, SaveEncoding::Ron, SaveEncoding::Bincode] {
+        assert_encoded_round_trip(&groups, encoding);
+        assert_encoded_round_trip(&lut, encoding);
+        assert_encoded_round_trip(&meta, encoding);
+    }
+}
+
+#[test]
+fn reads_json_files_as_bytes() {
+    let contents = fixture("v0", "my_group.lut");
+    let from_str = StandaloneLut::from_versioned_str(&contents).unwrap();
+    let from_bytes = StandaloneLut::from_versioned_bytes(contents.as_bytes()).unwrap();
+    assert_eq!(from_str, from_bytes);
+}
+
+#[test]
+fn rejects_truncated_binary_files() {
+    let lut = StandaloneLut::from_versioned_str(&fixture("v1", "my_group.lut")).unwrap();
+    let saved = lut.to_versioned_bytes(SaveEncoding::Bincode).unwrap();
+    assert!(StandaloneLut::from_versioned_bytes(&saved[..saved.len() / 2]).is_err());
+    assert!(StandaloneLut::from_versioned_bytes(&BINARY_MAGIC).is_err());
+}
+
+// a binary file written with an older version
+fn binary_file<T: serde::Serialize>(version: u32, data: &T) -> Vec<u8> {
+    let mut bytes = BINARY_MAGIC.to_vec();
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(data).unwrap());
+    bytes
+}
+
+// the layout of curves in version 1, before they had metadata
+#[derive(serde::Serialize)]
+struct BezierV1<'a> {
+    positions: &'a BezierPositions,
+    previous_positions: &'a BezierPositions,
+    color: &'a Option<Color>,
+    do_compute_lut: bool,
+    lut: &'a LutDistance,
+    id: BezierId,
+    latches: &'a std::collections::HashMap<AnchorEdge, LatchData>,
+    potential_latch: &'a Option<LatchData>,
+    group: GroupId,
+    entity: Option<Entity>,
+}
+
+#[derive(serde::Serialize)]
+struct GroupSaveLoadV1<'a> {
+    lut: Vec<(BezierV1<'a>, AnchorEdge, (f64, f64), &'a LutDistance)>,
+    standalone_lut: &'a StandaloneLut,
+}
+
+#[test]
+fn loads_v1_binary_files() {
+    let groups =
+        Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap();
+    let v1_groups = groups
+        .iter()
+        .map(|group| GroupSaveLoadV1 {
+            lut: group
+                .lut
+                .iter()
+                .map(|(bezier, anchor, t_ends, distances)| {
+                    let bezier = BezierV1 {
+                        positions: &bezier.positions,
+                        previous_positions: &bezier.previous_positions,
+                        color: &bezier.color,
+                        do_compute_lut: bezier.do_compute_lut,
+                        lut: &bezier.lut,
+                        id: bezier.id,
+                        latches: &bezier.latches,
+                        potential_latch: &bezier.potential_latch,
+                        group: bezier.group,
+                        entity: bezier.entity,
+                    };
+                    (bezier, *anchor, *t_ends, distances)
+                })
+                .collect(),
+            standalone_lut: &group.standalone_lut,
+        })
+        .collect::<Vec<GroupSaveLoadV1>>();
+
+    let loaded = Vec::<GroupSaveLoad>::from_versioned_bytes(&binary_file(1, &v1_groups)).unwrap();
+    assert_eq!(
+        serde_json::to_value(&loaded).unwrap(),
+        serde_json::to_value(&groups).unwrap()
+    );
+    assert!(loaded[0].metadata().is_empty());
+
+    // look-up tables and mesh metadata kept their layout
+    let lut = StandaloneLut::from_versioned_str(&fixture("v1", "my_group.lut")).unwrap();
+    assert_eq!(
+        StandaloneLut::from_versioned_bytes(&binary_file(1, &lut)).unwrap(),
+        lut
+    );
+    let meta = MeshMeta::from_versioned_str(&fixture("v1", "my_mesh.meta")).unwrap();
+    let loaded = MeshMeta::from_versioned_bytes(&binary_file(1, &meta)).unwrap();
+    assert_eq!(loaded.bounding_box, meta.bounding_box);
+
+    let mut v0 = binary_file(1, &lut);
+    v0[4..8].copy_from_slice(&0u32.to_le_bytes());
+    assert!(matches!(
+        StandaloneLut::from_versioned_bytes(&v0),
+        Err(SaveFormatError::Malformed(_))
+    ));
+}
+
+#[test]
+fn round_trips_projects() {
+    let mut reference = ReferenceImage::new("textures/level.png");
+    reference.position = Vec2::new(10.0, -20.0);
+    reference.rotation = 0.5;
+    reference.locked = true;
+
+    let project = ProjectSaveLoad {
+        groups: Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap(),
+        references: vec![reference.clone()],
+        grid: Grid {
+            visible: true,
+            spacing: 64.0,
+            ..Default::default()
+        },
+        guides: vec![Guide::new(GuideAxis::Vertical, 32.0)],
+    };
+    for encoding in [SaveEncoding::Json, SaveEncoding::Ron, SaveEncoding::Bincode] {
+        assert_encoded_round_trip(&project, encoding);
+    }
+
+    let reloaded =
+        ProjectSaveLoad::from_versioned_str(&project.to_versioned_string().unwrap()).unwrap();
+    assert_eq!(reloaded.references, vec![reference]);
+    assert_eq!(reloaded.grid, project.grid);
+    assert_eq!(reloaded.guides, project.guides);
+
+    // projects without reference images, grid or guides leave the fields out
+    let empty = ProjectSaveLoad::from_versioned_str("{\"version\": 3, \"data\": {\"groups\": []}}")
+        .unwrap();
+    assert!(empty.references.is_empty() && empty.guides.is_empty());
+    assert_eq!(empty.grid, Grid::default());
+}
+
+// the layout of projects in version 2, before the grid and the guides were saved
+#[derive(serde::Serialize)]
+struct ProjectSaveLoadV2<'a> {
+    groups: &'a Vec<GroupSaveLoad>,
+    references: &'a Vec<ReferenceImage>,
+}
+
+#[derive(serde::Serialize)]
+struct VersionedV2<'a> {
+    version: u32,
+    data: ProjectSaveLoadV2<'a>,
+}
+
+#[test]
+fn loads_v2_projects() {
+    let project = ProjectSaveLoad::from_versioned_str(&fixture("v2", "my_project.pen")).unwrap();
+    let groups =
+        Vec::<GroupSaveLoad>::from_versioned_str(&fixture("v1", "my_group.group")).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&project.groups).unwrap(),
+        serde_json::to_value(&groups).unwrap()
+    );
+    assert_eq!(project.references.len(), 1);
+    assert_eq!(project.references[0].position, Vec2::new(10.0, -20.0));
+    assert!(project.references[0].locked);
+    assert_eq!(project.grid, Grid::default());
+    assert!(project.guides.is_empty());
+    assert_round_trip(&project);
+
+    // binary files have no field names, and are read through the old layout
+    let v2 = ProjectSaveLoadV2 {
+        groups: &project.groups,
+        references: &project.references,
+    };
+    let loaded = ProjectSaveLoad::from_versioned_bytes(&binary_file(2, &v2)).unwrap();
+    assert_eq!(loaded.references, project.references);
+    assert_eq!(loaded.grid, Grid::default());
+    assert!(loaded.guides.is_empty());
+
+    // RON files fill in the missing fields with their defaults
+    let ron = ron::to_string(&VersionedV2 {
+        version: 2,
+        data: v2,
+    })
+    .unwrap();
+    let loaded = ProjectSaveLoad::from_versioned_bytes(ron.as_bytes()).unwrap();
+    assert_eq!(loaded.references, project.references);
+    assert_eq!(loaded.grid, Grid::default());
+    assert!(loaded.guides.is_empty());
+
+    // the groups of version 2 kept their layout
+    let loaded = Vec::<GroupSaveLoad>::from_versioned_bytes(&binary_file(2, &groups)).unwrap();
+    assert_eq!(
+        serde_json::to_value(&loaded).unwrap(),
+        serde_json::to_value(&groups).unwrap()
+    );
+}
+
+#[test]
+fn picks_reference_images_within_their_bounds() {
+    let mut reference = ReferenceImage::new("textures/level.png");
+    reference.position = Vec2::new(100.0, 0.0);
+    reference.scale = 2.0;
+    reference.rotation = std::f32::consts::FRAC_PI_2;
+
+    // a 40 x 10 pixel image becomes 20 units wide and 80 units high once rotated and scaled
+    let size = Vec2::new(40.0, 10.0);
+    assert!(reference.contains(Vec2::new(100.0, 35.0), size));
+    assert!(reference.contains(Vec2::new(109.0, -35.0), size));
+    assert!(!reference.contains(Vec2::new(115.0, 0.0), size));
+    assert!(!reference.contains(Vec2::new(100.0, 45.0), size));
+}
@@ -0,0 +1,179 @@
+use bevy::asset::AssetPlugin;
+use bevy::ecs::entity::EntityMap;
+use bevy::prelude::*;
+use bevy::reflect::TypeRegistry;
+use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+use bevy::scene::serde::SceneDeserializer;
+use bevy::sprite::Mesh2dHandle;
+
+use bevy_pen_tool_model::*;
+use serde::de::DeserializeSeed;
+
+// the types that a game registers to load the scenes of the editor
+fn scene_app() -> App {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .register_type::<Transform>();
+    register_scene_types(&mut app);
+    app
+}
+
+fn square() -> Vec<BezierPositions> {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn spawns_exported_scenes() {
+    let mut group =
+        OfflineGroup::from_save(&GroupSaveLoad::from_chain(&square(), None, 100).unwrap());
+    group.compute_lut(100);
+    let (mesh, bounding_box) = group.fill_mesh(Color::BLUE).unwrap();
+    let data = pen_mesh_data(&mesh).unwrap();
+    assert_eq!(data.positions.len(), mesh.count_vertices());
+    assert_eq!(data.indices.len(), mesh.indices().unwrap().len());
+
+    let mut app = scene_app();
+    let type_registry = app.world.resource::<TypeRegistry>().clone();
+
+    // the entities that the editor exports for a fill mesh and a path follower
+    let mut scene_world = World::new();
+    let transform = Transform::from_xyz(50.0, 50.0, 0.0);
+    let color = Vec4::new(0.0, 0.0, 1.0, 0.5);
+    scene_world.spawn().insert_bundle((
+        Name::new("fill3"),
+        transform,
+        PenMesh {
+            id: 3,
+            bounding_box,
+        },
+        data.clone(),
+        PenFill { color },
+    ));
+    let lut = group.group.standalone_lut.clone();
+    scene_world.spawn().insert_bundle((
+        Name::new("follower0"),
+        Transform::from_translation(lut.lut[0].extend(1.0)),
+        PenFollower {
+            lut: lut.lut.clone(),
+            path_length: lut.path_length,
+            speed: 40.0,
+            looping: true,
+            rotate: true,
+        },
+    ));
+
+    let contents = DynamicScene::from_world(&scene_world, &type_registry)
+        .serialize_ron(&type_registry)
+        .unwrap();
+    let scene = SceneDeserializer {
+        type_registry: &type_registry.read(),
+    }
+    .deserialize(&mut ron::de::Deserializer::from_str(&contents).unwrap())
+    .unwrap();
+    scene
+        .write_to_world(&mut app.world, &mut EntityMap::default())
+        .unwrap();
+
+    let mut meshes = app
+        .world
+        .query::<(&Name, &Transform, &PenMesh, &PenMeshData, &PenFill)>();
+    let (name, loaded_transform, pen_mesh, loaded, fill) = meshes.single(&app.world);
+    assert_eq!(name.as_str(), "fill3");
+    assert_eq!(*loaded_transform, transform);
+    assert_eq!((pen_mesh.id, pen_mesh.bounding_box), (3, bounding_box));
+    assert_eq!(loaded.positions, data.positions);
+    assert_eq!(loaded.uvs, data.uvs);
+    assert_eq!(loaded.indices, data.indices);
+    assert_eq!(fill.color, color);
+
+    let mut followers = app.world.query::<&PenFollower>();
+    let follower = followers.single(&app.world);
+    assert_eq!(follower.lut, lut.lut);
+    assert_eq!(follower.path_length, lut.path_length);
+    assert_eq!(follower.speed, 40.0);
+}
+
+#[test]
+fn builds_the_meshes_of_scenes() {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(AssetPlugin)
+        .add_asset::<Mesh>()
+        .add_asset::<ColorMaterial>()
+        .add_asset::<Image>()
+        .add_plugin(PenSceneMeshPlugin);
+
+    let color = Vec4::new(0.0, 0.0, 1.0, 0.5);
+    let entity = app
+        .world
+        .spawn()
+        .insert_bundle((
+            PenMeshData {
+                positions: vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+                uvs: Vec::new(),
+                indices: vec![0, 1, 2],
+            },
+            PenFill { color },
+        ))
+        .id();
+    app.update();
+
+    let handle = app.world.get::<Mesh2dHandle>(entity).unwrap().0.clone();
+    let mesh = app.world.resource::<Assets<Mesh>>().get(&handle).unwrap();
+    assert_eq!(mesh.count_vertices(), 3);
+    assert_eq!(
+        mesh.indices().unwrap().iter().collect::<Vec<usize>>(),
+        vec![0, 1, 2]
+    );
+    // fill meshes have no uvs in the scene, but 2d meshes need them
+    assert!(mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_some());
+
+    let material = app.world.get::<Handle<ColorMaterial>>(entity).unwrap();
+    let material = app
+        .world
+        .resource::<Assets<ColorMaterial>>()
+        .get(material)
+        .unwrap();
+    assert_eq!(Vec4::from(material.color.as_linear_rgba_f32()), color);
+}
+
+#[test]
+fn stores_triangle_lists_only() {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![[0.0, 0.0], [10.0, 0.0], [0.0, 20.0]],
+    );
+    let data = pen_mesh_data(&mesh).unwrap();
+    assert_eq!(data.positions[2], Vec3::new(0.0, 20.0, 0.0));
+    assert_eq!(data.indices, vec![0, 1, 2]);
+    assert!(data.uvs.is_empty());
+
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 3])));
+    assert!(matches!(
+        pen_mesh_data(&mesh),
+        Err(ConversionError::Syntax(_))
+    ));
+
+    let lines = Mesh::new(PrimitiveTopology::LineList);
+    assert!(matches!(
+        pen_mesh_data(&lines),
+        Err(ConversionError::Unsupported(_))
+    ));
+}
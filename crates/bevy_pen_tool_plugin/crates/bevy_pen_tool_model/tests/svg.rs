@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+use bevy_pen_tool_model::*;
+
+fn document(body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200\" height=\"200\">{}</svg>",
+        body
+    )
+}
+
+// the subpaths of a single path
+fn subpaths(data: &str) -> Vec<SvgSubpath> {
+    let shapes = parse_svg(&document(&format!("<path d=\"{}\"/>", data))).unwrap();
+    assert_eq!(shapes.len(), 1);
+    shapes[0].subpaths.clone()
+}
+
+fn point_at(curve: &BezierPositions, t: f32) -> Vec2 {
+    let s = 1.0 - t;
+    curve.start * s * s * s
+        + curve.control_start * 3.0 * s * s * t
+        + curve.control_end * 3.0 * s * t * t
+        + curve.end * t * t * t
+}
+
+fn assert_near(a: Vec2, b: Vec2) {
+    assert!(a.distance(b) < 0.01, "{} is not {}", a, b);
+}
+
+fn assert_same_subpaths(a: &[SvgSubpath], b: &[SvgSubpath]) {
+    assert_eq!(a.len(), b.len());
+    for (a, b) in a.iter().zip(b.iter()) {
+        assert_eq!(a.closed, b.closed);
+        assert_eq!(a.curves.len(), b.curves.len());
+        for (a, b) in a.curves.iter().zip(b.curves.iter()) {
+            assert_near(a.start, b.start);
+            assert_near(a.control_start, b.control_start);
+            assert_near(a.control_end, b.control_end);
+            assert_near(a.end, b.end);
+        }
+    }
+}
+
+// every point along the curves lies on the circle, in canvas coordinates
+fn assert_on_circle(curves: &[BezierPositions], center: Vec2, radius: f32) {
+    for curve in curves {
+        for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let distance = point_at(curve, t).distance(center);
+            assert!((distance - radius).abs() < 0.05 * radius.max(1.0));
+        }
+    }
+}
+
+#[test]
+fn reads_relative_and_absolute_commands() {
+    let absolute = subpaths("M 10 10 L 110 10 L 110 60 Z");
+    assert_eq!(absolute.len(), 1);
+    assert!(absolute[0].closed);
+    assert_eq!(absolute[0].curves.len(), 3);
+
+    // the y axis of SVG points down
+    assert_near(absolute[0].curves[0].start, Vec2::new(10.0, -10.0));
+    assert_near(absolute[0].curves[1].end, Vec2::new(110.0, -60.0));
+    assert_near(absolute[0].curves[2].end, Vec2::new(10.0, -10.0));
+
+    for data in [
+        "m 10 10 l 100 0 l 0 50 z",
+        "M10,10H110V60Z",
+        "m10 10h100v50z",
+        // coordinates after a move are implicit lines
+        "M 10 10 110 10 110 60 Z",
+        "m 10 10 100 0 0 50 z",
+    ] {
+        assert_same_subpaths(&subpaths(data), &absolute);
+    }
+
+    // a relative move after a close starts from the start of the closed subpath
+    let two = subpaths("M 10 10 L 20 10 L 20 20 Z m 100 0 l 10 0");
+    assert_eq!(two.len(), 2);
+    assert!(two[0].closed && !two[1].closed);
+    assert_near(two[1].curves[0].start, Vec2::new(110.0, -10.0));
+    assert_near(two[1].curves[0].end, Vec2::new(120.0, -10.0));
+}
+
+#[test]
+fn converts_arcs_to_cubic_curves() {
+    // a quarter turn around the origin is a single curve
+    let quarter = subpaths("M 100 0 A 100 100 0 0 1 0 100");
+    assert_eq!(quarter[0].curves.len(), 1);
+    assert_near(quarter[0].curves[0].end, Vec2::new(0.0, -100.0));
+    assert_on_circle(&quarter[0].curves, Vec2::ZERO, 100.0);
+    assert_near(
+        point_at(&quarter[0].curves[0], 0.5),
+        Vec2::new(70.71, -70.71),
+    );
+
+    // the large arc between the same points turns around the other center
+    let large = subpaths("M 100 0 A 100 100 0 1 1 0 100");
+    assert_eq!(large[0].curves.len(), 3);
+    assert_on_circle(&large[0].curves, Vec2::new(100.0, -100.0), 100.0);
+
+    // radii too small to join the points are scaled up, to a half turn here
+    let half = subpaths("M 0 0 a 1 1 0 0 1 100 0");
+    assert_eq!(half[0].curves.len(), 2);
+    assert_on_circle(&half[0].curves, Vec2::new(50.0, 0.0), 50.0);
+
+    // circles are four arcs
+    let shapes = parse_svg(&document("<circle cx=\"20\" cy=\"30\" r=\"50\"/>")).unwrap();
+    let circle = &shapes[0].subpaths[0];
+    assert!(circle.closed);
+    assert_eq!(circle.curves.len(), 4);
+    assert_on_circle(&circle.curves, Vec2::new(20.0, -30.0), 50.0);
+}
+
+#[test]
+fn reflects_controls_of_smooth_curves() {
+    let cubic = subpaths("M 0 0 C 0 50 50 50 50 0 S 100 -50 100 0");
+    assert_eq!(cubic[0].curves.len(), 2);
+    assert_near(cubic[0].curves[1].control_start, Vec2::new(50.0, 50.0));
+    assert_near(cubic[0].curves[1].control_end, Vec2::new(100.0, 50.0));
+    assert_same_subpaths(&subpaths("m 0 0 c 0 50 50 50 50 0 s 50 -50 50 0"), &cubic);
+
+    // without a previous cubic curve, the first control is the current point
+    let lone = subpaths("M 0 0 S 50 50 100 0");
+    assert_near(lone[0].curves[0].control_start, Vec2::ZERO);
+
+    // the reflected control of T is (150, -50), elevated to a cubic curve
+    let quadratic = subpaths("M 0 0 Q 50 50 100 0 T 200 0");
+    assert_eq!(quadratic[0].curves.len(), 2);
+    assert_near(
+        quadratic[0].curves[1].control_start,
+        Vec2::new(100.0 + 100.0 / 3.0, 100.0 / 3.0),
+    );
+    assert_near(
+        quadratic[0].curves[1].control_end,
+        Vec2::new(200.0 - 100.0 / 3.0, 100.0 / 3.0),
+    );
+    assert_same_subpaths(&subpaths("m 0 0 q 50 50 100 0 t 100 0"), &quadratic);
+}
+
+#[test]
+fn applies_nested_transforms() {
+    let contents = document(
+        "<g transform=\"translate(100 0)\">
+            <g transform=\"scale(2)\">
+                <path d=\"M 0 0 L 10 0\" transform=\"rotate(90)\"/>
+            </g>
+            <path d=\"M 1 0 L 2 0\" transform=\"translate(10,20) scale(2)\"/>
+            <path d=\"M 10 0 L 20 0\" transform=\"rotate(180 10 0)\"/>
+            <path d=\"M 1 1 L 2 2\" transform=\"matrix(1 0 0 1 5 5)\"/>
+        </g>",
+    );
+    let shapes = parse_svg(&contents).unwrap();
+    assert_eq!(shapes.len(), 4);
+    let curve = |k: usize| shapes[k].subpaths[0].curves[0];
+
+    // rotated first, then scaled, then moved
+    assert_near(curve(0).start, Vec2::new(100.0, 0.0));
+    assert_near(curve(0).end, Vec2::new(100.0, -20.0));
+
+    // the rightmost transform of a list applies first
+    assert_near(curve(1).start, Vec2::new(112.0, -20.0));
+    assert_near(curve(1).end, Vec2::new(114.0, -20.0));
+
+    // rotation around (10, 0)
+    assert_near(curve(2).start, Vec2::new(110.0, 0.0));
+    assert_near(curve(2).end, Vec2::new(100.0, 0.0));
+
+    assert_near(curve(3).start, Vec2::new(106.0, -6.0));
+}
+
+#[test]
+fn reports_malformed_input() {
+    let is_syntax_error =
+        |contents: &str| matches!(parse_svg(contents).unwrap_err(), ConversionError::Syntax(_));
+    let path = |data: &str| document(&format!("<path d=\"{}\"/>", data));
+    let transformed = |transform: &str| {
+        document(&format!(
+            "<path d=\"M 0 0 L 1 1\" transform=\"{}\"/>",
+            transform
+        ))
+    };
+
+    assert!(is_syntax_error("<svg"));
+    assert!(is_syntax_error(&path("10 10 L 20 20")));
+    assert!(is_syntax_error(&path("M 0 0 L 10")));
+    assert!(is_syntax_error(&path("M 1e 2")));
+    assert!(is_syntax_error(&path("M 0 0 A 10 10 0 2 1 5 5")));
+    assert!(is_syntax_error(&path("M 0 0 Z 10 10")));
+    assert!(is_syntax_error(&path("M 0 0 L \u{663} 4")));
+    assert!(is_syntax_error(&document(
+        "<polygon points=\"0 0 10 10 20\"/>"
+    )));
+    assert!(is_syntax_error(&transformed("rotate(1 2)")));
+    assert!(is_syntax_error(&transformed("translate(1")));
+    assert!(is_syntax_error(&transformed("skew(3)")));
+    assert!(is_syntax_error(&transformed("scale)2(")));
+
+    assert!(matches!(
+        parse_svg(&path("M 0 0 X 1 1")).unwrap_err(),
+        ConversionError::Unsupported(_)
+    ));
+    assert!(matches!(
+        svg_to_groups(&document("<rect width=\"0\" height=\"10\"/>"), 100).unwrap_err(),
+        ConversionError::Empty
+    ));
+}
+
+// a square with its controls at a quarter of each side, so that every number is written exactly
+fn quarter_square(color: Option<Color>) -> GroupSaveLoad {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    let curves = (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start.lerp(end, 0.25),
+                control_end: start.lerp(end, 0.75),
+            }
+        })
+        .collect::<Vec<BezierPositions>>();
+    GroupSaveLoad::from_chain(&curves, color, 100).unwrap()
+}
+
+#[test]
+fn exports_groups_as_path_data() {
+    let group = quarter_square(Some(Color::RED));
+    let contents = write_svg(&[SvgExportPath::from_group(&group, Some(Color::BLUE))]);
+
+    let document = roxmltree::Document::parse(&contents).unwrap();
+    let svg = document.root_element();
+    assert_eq!(svg.attribute("viewBox"), Some("-10 -110 120 120"));
+
+    let path = svg
+        .children()
+        .find(|node| node.has_tag_name("path"))
+        .unwrap();
+    assert_eq!(path.attribute("id"), Some("group0"));
+    assert_eq!(
+        path.attribute("d"),
+        Some(
+            "M 0 0 C 25 0 75 0 100 0 C 100 -25 100 -75 100 -100 \
+             C 75 -100 25 -100 0 -100 C 0 -75 0 -25 0 0 Z"
+        )
+    );
+    assert_eq!(path.attribute("fill"), Some("#0000ff"));
+    assert_eq!(path.attribute("stroke"), Some("#ff0000"));
+    assert_eq!(path.attribute("stroke-width"), Some("2"));
+
+    // reading the file back gives the same curves
+    let imported = svg_to_groups(&contents, 100).unwrap();
+    assert_eq!(imported.len(), 1);
+    assert!(imported[0].is_closed());
+    for (exported, imported) in group
+        .path_curves()
+        .iter()
+        .zip(imported[0].path_curves().iter())
+    {
+        assert_near(imported.start, exported.start);
+        assert_near(imported.control_start, exported.control_start);
+        assert_near(imported.control_end, exported.control_end);
+        assert_near(imported.end, exported.end);
+    }
+}
+
+#[test]
+fn exports_open_groups_without_closing_them() {
+    let mut curves = quarter_square(None).path_curves();
+    curves.pop();
+    let path = SvgExportPath {
+        curves,
+        closed: false,
+        stroke: None,
+        fill: None,
+    };
+    let contents = write_svg(&[path]);
+
+    assert!(contents.contains(
+        "d=\"M 0 0 C 25 0 75 0 100 0 C 100 -25 100 -75 100 -100 C 75 -100 25 -100 0 -100\""
+    ));
+    assert!(contents.contains("fill=\"none\" stroke=\"none\"/>"));
+}
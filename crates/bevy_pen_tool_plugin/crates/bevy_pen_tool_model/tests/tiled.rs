@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use bevy_pen_tool_model::*;
+
+use std::collections::BTreeMap;
+
+fn square() -> GroupSaveLoad {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    let curves = (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect::<Vec<BezierPositions>>();
+
+    let mut group = GroupSaveLoad::from_chain(&curves, None, 400).unwrap();
+    let metadata = [
+        ("name", "pond"),
+        ("type", "water"),
+        ("depth", "3"),
+        ("frozen", "false"),
+    ]
+    .iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect::<BTreeMap<String, String>>();
+    group.set_metadata(&metadata);
+    group
+}
+
+#[test]
+fn round_trips_objects_in_both_formats() {
+    let group = square();
+    let paths = [TiledExportPath::from_group(&group, 0, 10.0)];
+    assert!(paths[0].closed);
+    assert_eq!(paths[0].points.len(), 40);
+
+    for format in [TiledFormat::Json, TiledFormat::Tmx] {
+        let contents = write_tiled(&paths, format, UVec2::splat(32));
+        let imported = tiled_to_groups(&contents, None, 100).unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert!(imported[0].is_closed());
+        assert_eq!(imported[0].lut.len(), 40);
+        assert_eq!(imported[0].metadata(), group.metadata());
+    }
+}
+
+#[test]
+fn applies_layer_offsets_and_rotations() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="10" height="10" tilewidth="32" tileheight="32">
+ <group offsetx="10" offsety="0">
+  <objectgroup offsety="20">
+   <object id="1" name="fence" x="100" y="100" rotation="90">
+    <properties>
+     <property name="note">built in spring</property>
+    </properties>
+    <polyline points="0,0 50,0"/>
+   </object>
+  </objectgroup>
+ </group>
+</map>"#;
+
+    let groups = tiled_to_groups(tmx, None, 100).unwrap();
+    let curves = groups[0].path_curves();
+    assert_eq!(curves.len(), 1);
+
+    // a clockwise quarter turn points the line down in the map, which is down on the canvas
+    assert!(curves[0].start.distance(Vec2::new(110.0, -120.0)) < 1e-3);
+    assert!(curves[0].end.distance(Vec2::new(110.0, -170.0)) < 1e-3);
+
+    let metadata = groups[0].metadata();
+    assert_eq!(metadata["name"], "fence");
+    assert_eq!(metadata["note"], "built in spring");
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_pen_tool_model::*;
+
+const SIZE: u32 = 16;
+
+// an image of SIZE x SIZE pixels, with the color given by `paint` for each pixel
+fn image(paint: impl Fn(u32, u32) -> [u8; 4]) -> Image {
+    let data = (0..SIZE)
+        .flat_map(|y| (0..SIZE).map(move |x| (x, y)))
+        .flat_map(|(x, y)| paint(x, y))
+        .collect::<Vec<u8>>();
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn signed_area(group: &GroupSaveLoad) -> f32 {
+    let points = &group.standalone_lut.lut;
+    let n = points.len();
+    (0..n)
+        .map(|k| points[k].perp_dot(points[(k + 1) % n]))
+        .sum::<f32>()
+        / 2.0
+}
+
+fn assert_box(group: &GroupSaveLoad, half_size: f32) {
+    let (min, max) = group.bounding_box().unwrap();
+    for value in [min.x, min.y, -max.x, -max.y] {
+        assert!((value + half_size).abs() <= 1.0);
+    }
+}
+
+#[test]
+fn traces_shapes_with_holes() {
+    // a black square of 10 pixels with a hole of 4 pixels, on white
+    let square = image(|x, y| {
+        let in_square = (3..13).contains(&x) && (3..13).contains(&y);
+        let in_hole = (6..10).contains(&x) && (6..10).contains(&y);
+        if in_square && !in_hole {
+            [0, 0, 0, 255]
+        } else {
+            [255, 255, 255, 255]
+        }
+    });
+
+    let groups = trace_image(&square, &TraceOptions::default(), 100).unwrap();
+    assert_eq!(groups.len(), 2);
+    assert!(groups.iter().all(GroupSaveLoad::is_closed));
+
+    let (outer, hole) = if signed_area(&groups[0]) > 0.0 {
+        (&groups[0], &groups[1])
+    } else {
+        (&groups[1], &groups[0])
+    };
+    assert!(signed_area(outer) > 90.0);
+    assert!(signed_area(hole) < -10.0);
+    assert_box(outer, 5.0);
+    assert_box(hole, 2.0);
+
+    // the fill of the square cuts out the hole, which comes from the same layer
+    let outline = &outer.metadata()[OUTLINE_METADATA];
+    assert_eq!(&hole.metadata()[OUTLINE_METADATA], outline);
+    let traced_again = trace_image(&square, &TraceOptions::default(), 100).unwrap();
+    assert_ne!(&traced_again[0].metadata()[OUTLINE_METADATA], outline);
+
+    // the same shape, traced from the light pixels, is the frame around the square and the
+    // inside of the hole
+    let options = TraceOptions {
+        layers: TraceLayers::Threshold {
+            level: 0.5,
+            invert: true,
+        },
+        ..Default::default()
+    };
+    assert_eq!(trace_image(&square, &options, 100).unwrap().len(), 3);
+}
+
+#[test]
+fn traces_one_layer_per_color() {
+    // red on the left, blue on the right, and a transparent border
+    let halves = image(|x, y| {
+        if !(2..14).contains(&x) || !(2..14).contains(&y) {
+            [0, 0, 0, 0]
+        } else if x < 8 {
+            [250, 10, 10, 255]
+        } else {
+            [10, 10, 250, 255]
+        }
+    });
+
+    let options = TraceOptions {
+        layers: TraceLayers::Colors(vec![Color::RED, Color::BLUE]),
+        scale: 2.0,
+        ..Default::default()
+    };
+    let groups = trace_image(&halves, &options, 100).unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].lut[0].0.color, Some(Color::RED));
+    assert_eq!(groups[1].lut[0].0.color, Some(Color::BLUE));
+    assert_ne!(
+        groups[0].metadata()[OUTLINE_METADATA],
+        groups[1].metadata()[OUTLINE_METADATA]
+    );
+
+    // the red half covers x from -12 to 0 once scaled
+    let (min, max) = groups[0].bounding_box().unwrap();
+    assert!((min.x + 12.0).abs() <= 2.0 && max.x.abs() <= 2.0);
+
+    let options = TraceOptions {
+        layers: TraceLayers::Alpha { level: 0.5 },
+        ..Default::default()
+    };
+    assert_eq!(trace_image(&halves, &options, 100).unwrap().len(), 1);
+
+    let blank = image(|_, _| [255, 255, 255, 255]);
+    assert!(matches!(
+        trace_image(&blank, &TraceOptions::default(), 100),
+        Err(ConversionError::Empty)
+    ));
+}
+
+#[test]
+fn traces_16_bit_images() {
+    // the black square with a hole, in the formats of 16-bit PNG files
+    let gray = |x: u32, y: u32| {
+        let in_square = (3..13).contains(&x) && (3..13).contains(&y);
+        let in_hole = (6..10).contains(&x) && (6..10).contains(&y);
+        if in_square && !in_hole {
+            0u16
+        } else {
+            u16::MAX
+        }
+    };
+    let image_16 = |channels: Vec<u16>, format: TextureFormat| {
+        Image::new(
+            Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            channels.iter().flat_map(|c| c.to_ne_bytes()).collect(),
+            format,
+        )
+    };
+    let pixels = (0..SIZE).flat_map(|y| (0..SIZE).map(move |x| gray(x, y)));
+
+    let rgba = pixels
+        .clone()
+        .flat_map(|value| [value, value, value, u16::MAX])
+        .collect();
+    let luma = pixels.collect();
+    for image in [
+        image_16(rgba, TextureFormat::Rgba16Uint),
+        image_16(luma, TextureFormat::R16Uint),
+    ] {
+        let groups = trace_image(&image, &TraceOptions::default(), 100).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(GroupSaveLoad::is_closed));
+    }
+
+    let float = image_16(
+        vec![0; (SIZE * SIZE * 4) as usize],
+        TextureFormat::Rgba16Float,
+    );
+    assert!(matches!(
+        trace_image(&float, &TraceOptions::default(), 100),
+        Err(ConversionError::Unsupported(_))
+    ));
+}
@@ -72,7 +72,7 @@ fn main() {
     let bezier_curves = app.world.resource::<BezierTestHashed>();
 
     let bezier = bezier_curves.0.get(&id2).unwrap();
-    assert_eq!(bezier.latches, HashMap::new());
+    assert!(bezier.latches.is_empty());
     // for id in maps.bezier_map.keys() {
     //     let bezier = bezier_curves.0.get(&id).unwrap();
     //     assert_eq!(bezier.latches, HashMap::new());
@@ -61,7 +61,7 @@ fn main() {
 
     for id in maps.bezier_map.keys() {
         let bezier = bezier_curves.0.get(&id).unwrap();
-        assert_eq!(bezier.latches, HashMap::new());
+        assert!(bezier.latches.is_empty());
     }
     println!("unlatch_test passed");
 }
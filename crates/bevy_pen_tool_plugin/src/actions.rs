@@ -1,8 +1,10 @@
+use crate::guides::{CanvasView, SNAP_PIXELS};
+
 use bevy_pen_tool_model::inputs::{Action, Cursor};
 use bevy_pen_tool_model::mesh::PenMesh;
 use bevy_pen_tool_model::model::*;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::OrthographicProjection};
 
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -68,7 +70,16 @@ pub fn update_anchors(
     mut query: Query<(&Handle<Bezier>, &Anchor, &MovingAnchor)>,
     cursor: Res<Cursor>,
     maps: ResMut<Maps>,
+    grid: Res<Grid>,
+    guide_query: Query<&Guide>,
+    globals: Res<Globals>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Transform, &OrthographicProjection)>,
 ) {
+    let guides = guide_query.iter().cloned().collect::<Vec<Guide>>();
+    let snap_distance = CanvasView::new(&windows, camera_query.iter().next(), &globals)
+        .map_or(0.0, |view| SNAP_PIXELS * view.pixel);
+
     // TODO: remove dependency on Cursor
     if cursor.latch.is_empty() {
         for (bezier_handle, anchor, moving_anchor) in query.iter_mut() {
@@ -77,6 +88,7 @@ pub fn update_anchors(
                 //
                 if moving_anchor.follow_mouse {
                     bezier.update_positions_cursor(&cursor, *anchor);
+                    snap_anchor(bezier, *anchor, &grid, &guides, snap_distance);
 
                     let latch_info = bezier.get_anchor_latch_info(*anchor);
 
@@ -93,6 +105,24 @@ pub fn update_anchors(
     }
 }
 
+// moves the dragged anchor onto the closest grid line or guide, along with its control point
+fn snap_anchor(bezier: &mut Bezier, anchor: Anchor, grid: &Grid, guides: &[Guide], distance: f32) {
+    let positions = &mut bezier.positions;
+    let (point, control) = match anchor {
+        Anchor::Start => (&mut positions.start, Some(&mut positions.control_start)),
+        Anchor::End => (&mut positions.end, Some(&mut positions.control_end)),
+        Anchor::ControlStart => (&mut positions.control_start, None),
+        Anchor::ControlEnd => (&mut positions.control_end, None),
+        _ => return,
+    };
+
+    let offset = snap_point(*point, grid, guides, distance) - *point;
+    *point += offset;
+    if let Some(control) = control {
+        *control += offset;
+    }
+}
+
 // TODO: separate into three separate systems:
 // 1) move anchor order
 // 2) unlatch anchor order
@@ -0,0 +1,310 @@
+//! Camera navigation. The camera that has a [`PenCamera`] component is panned with the middle
+//! mouse button, by dragging the canvas with space held, or with its keys, and zoomed with the
+//! mouse wheel around the point under the cursor. [`Action::FrameSelection`] and
+//! [`Action::FrameAll`] fit the selected curves, or every curve and mesh, in the window. The UI
+//! board keeps its place and size on the screen wherever the camera goes.
+
+use crate::reference::StartMovingReference;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::OrthographicProjection,
+    transform::TransformSystem,
+};
+use bevy_pen_tool_model::inputs::{Action, MouseClickEvent};
+use bevy_pen_tool_model::mesh::{PenMesh, StartMovingMesh};
+use bevy_pen_tool_model::model::{
+    Bezier, Globals, MainUi, Maps, MovingAnchor, Selection, SelectionChoice, UiBoard,
+};
+
+/// Part of the window left around framed curves, on each side.
+const FRAME_MARGIN: f32 = 0.1;
+
+/// Pixels of a touchpad scroll that count as one notch of the mouse wheel.
+const PIXELS_PER_NOTCH: f32 = 50.0;
+
+#[derive(Component)]
+pub struct PenCamera {
+    /// Speed of the keyboard pan, in pixels per frame.
+    pub speed: f32,
+    pub key_left: KeyCode,
+    pub key_right: KeyCode,
+    pub key_up: KeyCode,
+    pub key_down: KeyCode,
+    /// Zoom factor of one notch of the mouse wheel.
+    pub zoom_step: f32,
+    /// Bounds of the scale of the projection. Larger scales show more of the canvas.
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub enabled: bool,
+}
+
+impl Default for PenCamera {
+    fn default() -> Self {
+        Self {
+            speed: 3.0,
+            key_up: KeyCode::W,
+            key_down: KeyCode::S,
+            key_left: KeyCode::A,
+            key_right: KeyCode::D,
+            zoom_step: 1.1,
+            min_scale: 0.05,
+            max_scale: 20.0,
+            enabled: true,
+        }
+    }
+}
+
+pub(crate) struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(pan_camera)
+            .add_system(zoom_camera)
+            .add_system(frame_camera)
+            // the UI follows the camera before anything is drawn
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                pin_ui_to_camera.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn movement_axis(input: &Input<KeyCode>, plus: KeyCode, minus: KeyCode) -> f32 {
+    if input.pressed(KeyCode::LControl) || input.pressed(KeyCode::LShift) {
+        return 0.0;
+    }
+    let mut axis = 0.0;
+    if input.pressed(plus) {
+        axis += 1.0;
+    }
+    if input.pressed(minus) {
+        axis -= 1.0;
+    }
+    axis
+}
+
+pub fn pan_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    mut mouse_click_events: EventReader<MouseClickEvent>,
+    mut camera_query: Query<(&PenCamera, &mut Transform, &OrthographicProjection)>,
+    moving_query: Query<
+        (),
+        Or<(
+            With<MovingAnchor>,
+            With<StartMovingMesh>,
+            With<StartMovingReference>,
+        )>,
+    >,
+    mut space_dragging: Local<bool>,
+) {
+    // a space drag only pans if it starts on the canvas rather than on an anchor, which space
+    // detaches, or on the UI
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        *space_dragging = keyboard_input.pressed(KeyCode::Space);
+    }
+    let clicked_object = mouse_click_events.iter().count() > 0;
+    if !mouse_button_input.pressed(MouseButton::Left) || clicked_object || !moving_query.is_empty()
+    {
+        *space_dragging = false;
+    }
+
+    // mouse motions point down, the canvas y up
+    let mut drag = Vec2::ZERO;
+    for motion in mouse_motion_events.iter() {
+        drag += Vec2::new(motion.delta.x, -motion.delta.y);
+    }
+    if !mouse_button_input.pressed(MouseButton::Middle) && !*space_dragging {
+        drag = Vec2::ZERO;
+    }
+
+    for (camera, mut transform, projection) in camera_query.iter_mut() {
+        if !camera.enabled {
+            continue;
+        }
+        let keys = Vec2::new(
+            movement_axis(&keyboard_input, camera.key_right, camera.key_left),
+            movement_axis(&keyboard_input, camera.key_up, camera.key_down),
+        );
+
+        // the canvas follows the cursor while the keys move the view
+        let pixels = keys * camera.speed - drag;
+        if pixels != Vec2::ZERO {
+            transform.translation += (pixels * projection.scale).extend(0.0);
+        }
+    }
+}
+
+/// The mouse wheel zooms around the point under the cursor, which stays in place on the
+/// screen. With control held, the wheel rescales the curves instead.
+pub fn zoom_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    windows: Res<Windows>,
+    mut camera_query: Query<(&PenCamera, &mut Transform, &mut OrthographicProjection)>,
+) {
+    let notches = mouse_wheel_events.iter().map(wheel_notches).sum::<f32>();
+    if notches == 0.0 || keyboard_input.pressed(KeyCode::LControl) {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    let screen_position = window
+        .cursor_position()
+        .map_or(Vec2::ZERO, |position| position - window_size / 2.0);
+
+    for (camera, mut transform, mut projection) in camera_query.iter_mut() {
+        if !camera.enabled {
+            continue;
+        }
+        let scale = (projection.scale * camera.zoom_step.powf(-notches))
+            .clamp(camera.min_scale, camera.max_scale);
+
+        let under_cursor = transform.translation.truncate() + screen_position * projection.scale;
+        let center = under_cursor - screen_position * scale;
+        transform.translation = center.extend(transform.translation.z);
+        projection.scale = scale;
+    }
+}
+
+/// Notches of the mouse wheel in a scroll event. Touchpads scroll by pixels, many times per
+/// notch's worth of movement.
+pub fn wheel_notches(wheel: &MouseWheel) -> f32 {
+    match wheel.unit {
+        MouseScrollUnit::Line => wheel.y,
+        MouseScrollUnit::Pixel => wheel.y / PIXELS_PER_NOTCH,
+    }
+}
+
+pub fn frame_camera(
+    bezier_curves: Res<Assets<Bezier>>,
+    mesh_query: Query<(&PenMesh, &Transform), Without<PenCamera>>,
+    selection: Res<Selection>,
+    maps: Res<Maps>,
+    globals: Res<Globals>,
+    windows: Res<Windows>,
+    mut camera_query: Query<(&PenCamera, &mut Transform, &mut OrthographicProjection)>,
+    mut action_event_reader: EventReader<Action>,
+) {
+    let mut bounds = Bounds::default();
+    for action in action_event_reader.iter() {
+        match action {
+            Action::FrameSelection => {
+                for selected in selection.selected.iter() {
+                    match selected {
+                        SelectionChoice::CurveSet(bezier_set) => {
+                            for bezier in bezier_set
+                                .iter()
+                                .filter_map(|id| maps.bezier_map.get(id))
+                                .filter_map(|handle_entity| {
+                                    bezier_curves.get(&handle_entity.handle)
+                                })
+                            {
+                                bounds.add_curve_box(bezier.bounding_box(), globals.scale);
+                            }
+                        }
+                        SelectionChoice::Mesh(pen_mesh, translation) => {
+                            let (min, max) = pen_mesh.bounding_box;
+                            bounds.add_box((min + *translation, max + *translation));
+                        }
+                        SelectionChoice::None => {}
+                    }
+                }
+            }
+            Action::FrameAll => {
+                for (_, bezier) in bezier_curves.iter() {
+                    bounds.add_curve_box(bezier.bounding_box(), globals.scale);
+                }
+                for (pen_mesh, transform) in mesh_query.iter() {
+                    let (min, max) = pen_mesh.bounding_box;
+                    let translation = transform.translation.truncate();
+                    bounds.add_box((min + translation, max + translation));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (min, max) = match (bounds.min, bounds.max) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return,
+    };
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    for (camera, mut transform, mut projection) in camera_query.iter_mut() {
+        let (center, scale) = frame_box((min, max), window_size, camera);
+        projection.scale = scale;
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
+
+/// The center and the projection scale of a camera that fits a box, in world coordinates, in
+/// a window of the given size, leaving [`FRAME_MARGIN`] around it.
+pub fn frame_box((min, max): (Vec2, Vec2), window_size: Vec2, camera: &PenCamera) -> (Vec2, f32) {
+    let visible_size = window_size * (1.0 - 2.0 * FRAME_MARGIN);
+    let fit = (max - min) / visible_size;
+    let scale = fit.x.max(fit.y).clamp(camera.min_scale, camera.max_scale);
+    ((min + max) / 2.0, scale)
+}
+
+#[derive(Default)]
+struct Bounds {
+    min: Option<Vec2>,
+    max: Option<Vec2>,
+}
+
+impl Bounds {
+    fn add_box(&mut self, (min, max): (Vec2, Vec2)) {
+        self.min = Some(self.min.map_or(min, |current| current.min(min)));
+        self.max = Some(self.max.map_or(max, |current| current.max(max)));
+    }
+
+    // curves are in canvas coordinates, which [`Globals::scale`] turns into world ones, while
+    // meshes are placed in the world directly
+    fn add_curve_box(&mut self, (min, max): (Vec2, Vec2), scale: f32) {
+        self.add_box((min * scale, max * scale));
+    }
+}
+
+/// Keeps the UI board at the same place and size on the screen, whatever the position and the
+/// zoom of the camera, and whatever [`Globals::scale`].
+pub fn pin_ui_to_camera(
+    camera_query: Query<(&Transform, &OrthographicProjection), (With<PenCamera>, Without<MainUi>)>,
+    mut ui_query: Query<(&mut Transform, &mut UiBoard), With<MainUi>>,
+    globals: Res<Globals>,
+    mut last_camera: Local<Option<(Vec2, f32)>>,
+) {
+    let (camera_transform, projection) = match camera_query.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let camera = (camera_transform.translation.truncate(), projection.scale);
+
+    for (mut transform, mut ui_board) in ui_query.iter_mut() {
+        if let Some((last_center, last_scale)) = *last_camera {
+            if (last_center, last_scale) != camera {
+                let offset = (transform.translation.truncate() - last_center) / last_scale;
+                let position = camera.0 + offset * camera.1;
+                transform.translation = position.extend(transform.translation.z);
+                ui_board.previous_position = position;
+            }
+        }
+
+        let scale = globals.scale * camera.1;
+        if transform.scale.x != scale {
+            transform.scale = Vec3::new(scale, scale, 1.0);
+        }
+    }
+    *last_camera = Some(camera);
+}
@@ -0,0 +1,366 @@
+//! File dialog front end for saving and loading. The dialogs only pick paths: the actual work
+//! is done by the [`IoRequest`] handlers in [`crate::io`].
+//!
+//! Dialogs never block the frame. Each Save, Load, SaveProject or LoadProject action starts one
+//! dialog flow on the [`AsyncComputeTaskPool`]; when the flow is over, its picks are sent as
+//! [`FileDialogClosed`] events and turned into [`IoRequest`]s.
+
+use crate::io::IoRequest;
+use crate::storage::{PenStorage, StorageBackend};
+
+use bevy_pen_tool_model::inputs::Action;
+use bevy_pen_tool_model::mesh::*;
+use bevy_pen_tool_model::model::*;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+
+use futures_lite::future;
+use rfd::AsyncFileDialog;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// What a file dialog was opened for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialogPurpose {
+    /// A folder was picked to save every listed group, along with its look-up table.
+    SaveGroups(Vec<GroupId>),
+    SaveMesh(MeshId),
+    /// A `.pen` file was picked to save the whole canvas to.
+    SaveProject,
+    LoadGroup,
+    LoadMesh,
+    LoadProject,
+}
+
+/// Sent when a file dialog closes. `path` is `None` if the user cancelled the dialog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDialogClosed {
+    pub purpose: DialogPurpose,
+    pub path: Option<PathBuf>,
+}
+
+/// A dialog flow that is still open. Only one flow runs at a time.
+#[derive(Component)]
+pub struct FileDialogTask(Task<Vec<FileDialogClosed>>);
+
+pub(crate) struct DialogPlugin;
+
+impl Plugin for DialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FileDialogClosed>()
+            .add_system(poll_file_dialogs.label("poll_file_dialogs"))
+            .add_system(send_dialog_requests.after("poll_file_dialogs"))
+            .add_system_set(
+                SystemSet::on_update("ModelViewController")
+                    .with_system(load_with_dialogs)
+                    .with_system(save_with_dialogs)
+                    .with_system(load_project_with_dialog)
+                    .with_system(save_project_with_dialog)
+                    .after("model"),
+            );
+    }
+}
+
+pub fn save_with_dialogs(
+    mut commands: Commands,
+    bezier_curves: Res<Assets<Bezier>>,
+    selection: Res<Selection>,
+    maps: Res<Maps>,
+    storage: Res<PenStorage>,
+    open_dialogs: Query<&FileDialogTask>,
+    mut action_event_reader: EventReader<Action>,
+) {
+    if !action_event_reader.iter().any(|x| x == &Action::Save) || !open_dialogs.is_empty() {
+        return;
+    }
+
+    // collect all the different groups
+    let mut group_ids: Vec<GroupId> = Vec::new();
+    let mut mesh_ids: Vec<MeshId> = Vec::new();
+    for selected in selection.selected.iter() {
+        match selected {
+            SelectionChoice::CurveSet(bezier_ids) => {
+                let mut seen: HashSet<GroupId> = HashSet::new();
+                // curves can be deleted while a dialog is open, before the selection is updated
+                for bezier in bezier_ids
+                    .iter()
+                    .filter_map(|bezier_id| maps.bezier_map.get(bezier_id))
+                    .filter_map(|handle| bezier_curves.get(&handle.handle))
+                {
+                    if seen.insert(bezier.group) {
+                        group_ids.push(bezier.group);
+                    }
+                }
+            }
+            SelectionChoice::Mesh(PenMesh { id, .. }, _position) => mesh_ids.push(*id),
+            _ => (),
+        }
+    }
+
+    if group_ids.is_empty() && mesh_ids.is_empty() {
+        return;
+    }
+
+    let groups_folder = default_folder("saved", "groups");
+    let meshes_folder = default_folder("saved", "meshes");
+
+    // the storage cannot be reached from the dialog task, so the default names are found first
+    let mut k = 0;
+    let mesh_names = mesh_ids
+        .iter()
+        .map(|_| {
+            k = first_free_index(&**storage, &meshes_folder, "my_mesh", &["obj"], k);
+            k += 1;
+            format!("my_mesh{}", k - 1)
+        })
+        .collect::<Vec<String>>();
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let mut closed = Vec::new();
+
+        // a single folder pick for all the groups, instead of two dialogs per group
+        if !group_ids.is_empty() {
+            let path = AsyncFileDialog::new()
+                .set_directory(&groups_folder)
+                .pick_folder()
+                .await
+                .map(|handle| handle.path().to_path_buf());
+            closed.push(FileDialogClosed {
+                purpose: DialogPurpose::SaveGroups(group_ids),
+                path,
+            });
+        }
+
+        for (mesh_id, default_name) in mesh_ids.into_iter().zip(mesh_names) {
+            let path = AsyncFileDialog::new()
+                .set_file_name(&format!("{}.obj", default_name))
+                .add_filter("mesh", &["obj", "gltf", "glb"])
+                .set_directory(&meshes_folder)
+                .save_file()
+                .await
+                .map(|handle| handle.path().to_path_buf());
+            closed.push(FileDialogClosed {
+                purpose: DialogPurpose::SaveMesh(mesh_id),
+                path,
+            });
+        }
+
+        closed
+    });
+
+    commands.spawn().insert(FileDialogTask(task));
+}
+
+pub fn load_with_dialogs(
+    mut commands: Commands,
+    open_dialogs: Query<&FileDialogTask>,
+    mut action_event_reader: EventReader<Action>,
+) {
+    if !action_event_reader.iter().any(|x| x == &Action::Load) || !open_dialogs.is_empty() {
+        return;
+    }
+
+    let groups_folder = default_folder("saved", "groups");
+    let meshes_folder = default_folder("assets", "meshes");
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let group = pick_file(&groups_folder, "group").await;
+        let mesh = pick_file(&meshes_folder, "obj").await;
+
+        vec![
+            FileDialogClosed {
+                purpose: DialogPurpose::LoadGroup,
+                path: group,
+            },
+            FileDialogClosed {
+                purpose: DialogPurpose::LoadMesh,
+                path: mesh,
+            },
+        ]
+    });
+
+    commands.spawn().insert(FileDialogTask(task));
+}
+
+pub fn save_project_with_dialog(
+    mut commands: Commands,
+    storage: Res<PenStorage>,
+    open_dialogs: Query<&FileDialogTask>,
+    mut action_event_reader: EventReader<Action>,
+) {
+    if !action_event_reader
+        .iter()
+        .any(|x| x == &Action::SaveProject)
+        || !open_dialogs.is_empty()
+    {
+        return;
+    }
+
+    let projects_folder = default_folder("saved", "projects");
+    let default_name = available_name(&**storage, &projects_folder, "my_project", &["pen"]);
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let path = AsyncFileDialog::new()
+            .set_file_name(&format!("{}.pen", default_name))
+            .add_filter("project", &["pen"])
+            .set_directory(&projects_folder)
+            .save_file()
+            .await
+            .map(|handle| handle.path().to_path_buf());
+
+        vec![FileDialogClosed {
+            purpose: DialogPurpose::SaveProject,
+            path,
+        }]
+    });
+
+    commands.spawn().insert(FileDialogTask(task));
+}
+
+pub fn load_project_with_dialog(
+    mut commands: Commands,
+    open_dialogs: Query<&FileDialogTask>,
+    mut action_event_reader: EventReader<Action>,
+) {
+    if !action_event_reader
+        .iter()
+        .any(|x| x == &Action::LoadProject)
+        || !open_dialogs.is_empty()
+    {
+        return;
+    }
+
+    let projects_folder = default_folder("saved", "projects");
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        vec![FileDialogClosed {
+            purpose: DialogPurpose::LoadProject,
+            path: pick_file(&projects_folder, "pen").await,
+        }]
+    });
+
+    commands.spawn().insert(FileDialogTask(task));
+}
+
+pub fn poll_file_dialogs(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut FileDialogTask)>,
+    mut closed_writer: EventWriter<FileDialogClosed>,
+) {
+    for (entity, mut task) in tasks.iter_mut() {
+        if let Some(closed) = future::block_on(future::poll_once(&mut task.0)) {
+            info!("file dialogs closed: {:?}", closed);
+            closed_writer.send_batch(closed.into_iter());
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn send_dialog_requests(
+    storage: Res<PenStorage>,
+    mut closed_reader: EventReader<FileDialogClosed>,
+    mut io_requests: EventWriter<IoRequest>,
+) {
+    for FileDialogClosed { purpose, path } in closed_reader.iter() {
+        let path = if let Some(path) = path {
+            path.clone()
+        } else {
+            continue;
+        };
+
+        match purpose {
+            DialogPurpose::SaveGroups(group_ids) => {
+                // the files are only written later on, so the numbering is carried over
+                let mut k = 0;
+                for group_id in group_ids.iter() {
+                    k = first_free_index(&**storage, &path, "my_group", &["group", "lut"], k);
+                    let group_path = path.join(format!("my_group{}", k));
+                    k += 1;
+
+                    io_requests.send(IoRequest::ExportLut {
+                        group_id: *group_id,
+                        path: group_path.with_extension("lut"),
+                    });
+                    io_requests.send(IoRequest::SaveGroup {
+                        group_id: *group_id,
+                        path: group_path.with_extension("group"),
+                    });
+                }
+            }
+            DialogPurpose::SaveMesh(mesh_id) => {
+                let extension = path.extension().and_then(|extension| extension.to_str());
+                if matches!(extension, Some("gltf") | Some("glb")) {
+                    io_requests.send(IoRequest::ExportGltf {
+                        mesh_id: *mesh_id,
+                        path,
+                    });
+                } else {
+                    io_requests.send(IoRequest::SaveMesh {
+                        mesh_id: *mesh_id,
+                        path,
+                    });
+                }
+            }
+            DialogPurpose::SaveProject => io_requests.send(IoRequest::SaveProject { path }),
+            DialogPurpose::LoadGroup => io_requests.send(IoRequest::LoadGroup { path }),
+            DialogPurpose::LoadMesh => io_requests.send(IoRequest::LoadMesh { path }),
+            DialogPurpose::LoadProject => io_requests.send(IoRequest::LoadProject { path }),
+        }
+    }
+}
+
+// returns None if the user cancelled the file dialog
+async fn pick_file(folder: &Path, extension: &str) -> Option<PathBuf> {
+    AsyncFileDialog::new()
+        .add_filter("text", &[extension])
+        .set_directory(folder)
+        .pick_file()
+        .await
+        .map(|handle| handle.path().to_path_buf())
+}
+
+// relative to the working directory, or to the folder the dialog opens in by default if the
+// working directory cannot be read
+fn default_folder(root: &str, folder: &str) -> PathBuf {
+    let mut default_path = std::env::current_dir().unwrap_or_default();
+    default_path.push(root);
+    default_path.push(folder);
+    default_path
+}
+
+/// First `{save_name}{k}` that is not taken by a file with any of the given extensions in
+/// `folder` of the storage.
+pub fn available_name(
+    storage: &dyn StorageBackend,
+    folder: &Path,
+    save_name: &str,
+    extensions: &[&str],
+) -> String {
+    let k = first_free_index(storage, folder, save_name, extensions, 0);
+    format!("{}{}", save_name, k)
+}
+
+fn first_free_index(
+    storage: &dyn StorageBackend,
+    folder: &Path,
+    save_name: &str,
+    extensions: &[&str],
+    from: usize,
+) -> usize {
+    let mut k = from;
+
+    loop {
+        let name = format!("{}{}", save_name, k);
+        let taken = extensions
+            .iter()
+            .any(|extension| storage.exists(&folder.join(&name).with_extension(extension)));
+
+        if !taken {
+            return k;
+        }
+
+        k += 1;
+    }
+}
@@ -0,0 +1,85 @@
+//! Drag-and-drop import. Files dropped on the window are routed by extension to the matching
+//! [`IoRequest`], and their content is placed under the cursor. The `.json` extension is
+//! shared by several formats, so those files are routed by their content. A dropped `.pen`
+//! project replaces the whole canvas instead.
+
+use crate::io::IoRequest;
+use crate::storage::{PenStorage, StorageBackend};
+
+use bevy_pen_tool_model::inputs::Cursor;
+
+use bevy::prelude::*;
+
+use serde_json::Value;
+
+use std::path::PathBuf;
+
+pub fn import_dropped_files(
+    cursor: Res<Cursor>,
+    storage: Res<PenStorage>,
+    mut drop_events: EventReader<FileDragAndDrop>,
+    mut io_requests: EventWriter<IoRequest>,
+) {
+    for event in drop_events.iter() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            match dropped_file_request(&**storage, path_buf.clone(), cursor.position) {
+                Some(request) => io_requests.send(request),
+                None => error!(
+                    "cannot import {}: unsupported file type (expected .dxf, .geojson, .group, .lut, .obj, .pen, .svg, .tmj or .tmx, or .json with a Lottie animation, GeoJSON, a group or a look-up table)",
+                    path_buf.display()
+                ),
+            }
+        }
+    }
+}
+
+/// The request that imports a dropped file at `position`, or `None` if the file type is not
+/// supported. Only `.json` files are read from the storage, to tell their format apart.
+pub fn dropped_file_request(
+    storage: &dyn StorageBackend,
+    path: PathBuf,
+    position: Vec2,
+) -> Option<IoRequest> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        "dxf" => Some(IoRequest::ImportDxf { path, position }),
+        "geojson" => Some(IoRequest::ImportGeoJson { path, position }),
+        "group" => Some(IoRequest::ImportGroup { path, position }),
+        "json" => {
+            let contents = storage.read(&path).ok()?;
+            json_request(&contents, path, position)
+        }
+        "lut" => Some(IoRequest::ImportLut { path, position }),
+        "obj" => Some(IoRequest::ImportMesh { path, position }),
+        "pen" => Some(IoRequest::LoadProject { path }),
+        "svg" => Some(IoRequest::ImportSvg { path, position }),
+        // Tiled maps
+        "tmj" | "tmx" => Some(IoRequest::ImportTiled { path, position }),
+        _ => None,
+    }
+}
+
+// Lottie animations have a "v" version and "layers", GeoJSON files a "type", and the files of
+// the editor a save header, or no header at all for the oldest ones
+fn json_request(contents: &[u8], path: PathBuf, position: Vec2) -> Option<IoRequest> {
+    let value: Value = serde_json::from_slice(contents).ok()?;
+    if value.get("v").is_some() && value.get("layers").is_some() {
+        return Some(IoRequest::ImportLottie { path, position });
+    }
+    if value.get("type").map_or(false, Value::is_string) {
+        return Some(IoRequest::ImportGeoJson { path, position });
+    }
+
+    let data = match (value.get("version"), value.get("data")) {
+        (Some(_), Some(data)) => data,
+        _ => &value,
+    };
+    if data.is_array() {
+        Some(IoRequest::ImportGroup { path, position })
+    } else if data.get("lut").is_some() && data.get("path_length").is_some() {
+        Some(IoRequest::ImportLut { path, position })
+    } else {
+        None
+    }
+}
@@ -0,0 +1,307 @@
+//! Background grid and guide lines. Both are drawn as sprites over the part of the canvas that
+//! the camera sees, one pixel thick at any zoom, and both are snap targets for the anchors.
+//! Guides are pulled from the left and top edges of the window, moved by dragging them, and
+//! removed by dropping them back onto the edge they came from.
+
+use crate::reference::pick_reference_images;
+
+use bevy::prelude::*;
+use bevy::render::camera::OrthographicProjection;
+use bevy_pen_tool_model::inputs::{Action, Cursor};
+use bevy_pen_tool_model::model::{Globals, Grid, Guide, GuideAxis};
+
+/// Width of the band along the edges of the window that guides are pulled from, and of the
+/// band around a guide that grabs it, in pixels.
+const GRAB_PIXELS: f32 = 12.0;
+/// Lines of the grid closer than this, in pixels, are not drawn.
+const MIN_LINE_GAP_PIXELS: f32 = 4.0;
+/// Anchors are moved onto a guide or a grid line closer than this, in pixels.
+pub const SNAP_PIXELS: f32 = 8.0;
+
+const MAIN_LINE_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+const SUBDIVISION_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.12);
+const GUIDE_COLOR: Color = Color::rgba(0.0, 0.8, 1.0, 0.8);
+
+/// The id of the guide being dragged, if any.
+#[derive(Default)]
+pub struct DraggedGuide(pub Option<u64>);
+
+#[derive(Component)]
+pub struct GridLine;
+
+/// The part of the canvas seen by the camera, in canvas units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasView {
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Size of a pixel of the window.
+    pub pixel: f32,
+}
+
+impl CanvasView {
+    pub fn new(
+        windows: &Windows,
+        camera: Option<(&Transform, &OrthographicProjection)>,
+        globals: &Globals,
+    ) -> Option<Self> {
+        let window = windows.get_primary()?;
+        let (transform, projection) = camera?;
+
+        let half_size = Vec2::new(window.width(), window.height()) / 2.0 * projection.scale;
+        let center = transform.translation.truncate();
+        Some(Self {
+            min: (center - half_size) / globals.scale,
+            max: (center + half_size) / globals.scale,
+            pixel: projection.scale / globals.scale,
+        })
+    }
+
+    /// Center and size of a one pixel thick line across the view, at the y of a horizontal
+    /// line or the x of a vertical one.
+    pub fn line_across(&self, axis: GuideAxis, position: f32) -> (Vec2, Vec2) {
+        let center = (self.min + self.max) / 2.0;
+        let size = self.max - self.min;
+        match axis {
+            GuideAxis::Vertical => (Vec2::new(position, center.y), Vec2::new(self.pixel, size.y)),
+            GuideAxis::Horizontal => (Vec2::new(center.x, position), Vec2::new(size.x, self.pixel)),
+        }
+    }
+}
+
+pub(crate) struct GuidePlugin;
+
+impl Plugin for GuidePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Grid>()
+            .init_resource::<DraggedGuide>()
+            .add_system(toggle_grid.before(draw_grid))
+            .add_system(draw_grid)
+            .add_system(draw_guides)
+            .add_system(move_dragged_guide)
+            .add_system(release_dragged_guide.after(move_dragged_guide))
+            // a click on a guide does not move the reference image under it
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                pick_guides.before(pick_reference_images),
+            );
+    }
+}
+
+/// Spawns the sprite of a guide. Its transform is set by [`draw_guides`].
+pub fn spawn_guide(commands: &mut Commands, guide: Guide) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: GUIDE_COLOR,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(guide);
+}
+
+/// Shows or hides the grid on [`Action::ToggleGrid`].
+pub fn toggle_grid(mut action_reader: EventReader<Action>, mut grid: ResMut<Grid>) {
+    if action_reader
+        .iter()
+        .any(|action| action == &Action::ToggleGrid)
+    {
+        grid.visible = !grid.visible;
+    }
+}
+
+/// Center, size and color of the lines of the grid that are drawn over the view, in canvas
+/// units. Subdivisions are left out when they get too dense, and so is the whole grid.
+pub fn grid_lines(grid: &Grid, view: &CanvasView) -> Vec<(Vec2, Vec2, Color)> {
+    if !grid.visible || grid.spacing <= 0.0 {
+        return Vec::new();
+    }
+
+    let subdivisions = grid.subdivisions.max(1);
+    let (step, subdivisions) = if grid.step() >= MIN_LINE_GAP_PIXELS * view.pixel {
+        (grid.step(), subdivisions)
+    } else if grid.spacing >= MIN_LINE_GAP_PIXELS * view.pixel {
+        (grid.spacing, 1)
+    } else {
+        return Vec::new();
+    };
+
+    let mut lines = Vec::new();
+    for axis in [GuideAxis::Vertical, GuideAxis::Horizontal] {
+        let (min, max) = match axis {
+            GuideAxis::Vertical => (view.min.x, view.max.x),
+            GuideAxis::Horizontal => (view.min.y, view.max.y),
+        };
+
+        for k in (min / step).ceil() as i64..=(max / step).floor() as i64 {
+            let color = if k % subdivisions as i64 == 0 {
+                MAIN_LINE_COLOR
+            } else {
+                SUBDIVISION_COLOR
+            };
+            let (position, line_size) = view.line_across(axis, k as f32 * step);
+            lines.push((position, line_size, color));
+        }
+    }
+    lines
+}
+
+// whenever the grid or the view changes, the line sprites are moved to the new lines, more are
+// spawned if needed, and the ones left over are hidden until the next change
+pub fn draw_grid(
+    mut commands: Commands,
+    grid: Res<Grid>,
+    globals: Res<Globals>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Transform, &OrthographicProjection), Without<GridLine>>,
+    mut line_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<GridLine>>,
+    mut last_view: Local<Option<CanvasView>>,
+) {
+    let view = CanvasView::new(&windows, camera_query.iter().next(), &globals);
+    if !grid.is_changed() && *last_view == view {
+        return;
+    }
+    *last_view = view;
+
+    let lines = view.map_or_else(Vec::new, |view| grid_lines(&grid, &view));
+    let mut lines = lines.into_iter();
+
+    for (mut transform, mut sprite, mut visibility) in line_query.iter_mut() {
+        match lines.next() {
+            Some((position, line_size, color)) => {
+                transform.translation = (position * globals.scale).extend(globals.z_pos.grid);
+                sprite.color = color;
+                sprite.custom_size = Some(line_size * globals.scale);
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+
+    for (position, line_size, color) in lines {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(line_size * globals.scale),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(
+                    (position * globals.scale).extend(globals.z_pos.grid),
+                ),
+                ..Default::default()
+            })
+            .insert(GridLine);
+    }
+}
+
+// guides span the whole view, wherever the camera goes
+pub fn draw_guides(
+    mut guide_query: Query<(&Guide, &mut Transform, &mut Sprite)>,
+    globals: Res<Globals>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Transform, &OrthographicProjection), Without<Guide>>,
+) {
+    let view = match CanvasView::new(&windows, camera_query.iter().next(), &globals) {
+        Some(view) => view,
+        None => return,
+    };
+
+    for (guide, mut transform, mut sprite) in guide_query.iter_mut() {
+        let (position, line_size) = view.line_across(guide.axis, guide.position);
+        transform.translation = (position * globals.scale).extend(globals.z_pos.guides);
+        sprite.custom_size = Some(line_size * globals.scale);
+    }
+}
+
+/// A click on an empty spot of the canvas grabs the guide under the cursor, or pulls a new
+/// guide out of the left or top edge of the window.
+pub fn pick_guides(
+    mut commands: Commands,
+    cursor: Res<Cursor>,
+    guide_query: Query<&Guide>,
+    globals: Res<Globals>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Transform, &OrthographicProjection)>,
+    mut dragged: ResMut<DraggedGuide>,
+    mut actions: EventReader<Action>,
+) {
+    if !actions.iter().any(|action| action == &Action::Unselect) {
+        return;
+    }
+    let view = match CanvasView::new(&windows, camera_query.iter().next(), &globals) {
+        Some(view) => view,
+        None => return,
+    };
+    let grab_distance = GRAB_PIXELS * view.pixel;
+
+    let closest = guide_query
+        .iter()
+        .map(|guide| (guide, guide.distance(cursor.position)))
+        .filter(|(_, distance)| *distance <= grab_distance / 2.0)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((guide, _)) = closest {
+        dragged.0 = Some(guide.id);
+        return;
+    }
+
+    let guide = if cursor.position.x - view.min.x <= grab_distance {
+        Guide::new(GuideAxis::Vertical, cursor.position.x)
+    } else if view.max.y - cursor.position.y <= grab_distance {
+        Guide::new(GuideAxis::Horizontal, cursor.position.y)
+    } else {
+        return;
+    };
+    dragged.0 = Some(guide.id);
+    spawn_guide(&mut commands, guide);
+}
+
+pub fn move_dragged_guide(
+    cursor: Res<Cursor>,
+    dragged: Res<DraggedGuide>,
+    mut guide_query: Query<&mut Guide>,
+) {
+    for mut guide in guide_query.iter_mut() {
+        if Some(guide.id) == dragged.0 {
+            guide.position = match guide.axis {
+                GuideAxis::Horizontal => cursor.position.y,
+                GuideAxis::Vertical => cursor.position.x,
+            };
+        }
+    }
+}
+
+pub fn release_dragged_guide(
+    mut commands: Commands,
+    mouse_button_input: Res<Input<MouseButton>>,
+    cursor: Res<Cursor>,
+    guide_query: Query<(Entity, &Guide)>,
+    globals: Res<Globals>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Transform, &OrthographicProjection)>,
+    mut dragged: ResMut<DraggedGuide>,
+) {
+    if !mouse_button_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let id = match dragged.0.take() {
+        Some(id) => id,
+        None => return,
+    };
+    let view = match CanvasView::new(&windows, camera_query.iter().next(), &globals) {
+        Some(view) => view,
+        None => return,
+    };
+
+    // dropped back onto its edge
+    let grab_distance = GRAB_PIXELS * view.pixel;
+    for (entity, guide) in guide_query.iter() {
+        let is_on_edge = match guide.axis {
+            GuideAxis::Vertical => cursor.position.x - view.min.x <= grab_distance,
+            GuideAxis::Horizontal => view.max.y - cursor.position.y <= grab_distance,
+        };
+        if guide.id == id && is_on_edge {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,458 +1,1353 @@
-use bevy_pen_tool_model::inputs::Action;
+//! Path-based saving and loading. Game code, tests and tools send [`IoRequest`] events and
+//! receive an [`IoResponse`] for each of them. The file dialogs in [`crate::dialogs`] are only
+//! one front end on top of this API. Files are read and written through the [`PenStorage`]
+//! resource.
+
+use crate::guides::spawn_guide;
+use crate::reference::ReferenceRequest;
+use crate::storage::PenStorage;
+
 use bevy_pen_tool_model::materials::*;
 
 use bevy::{
+    ecs::system::SystemParam,
     prelude::*,
+    render::texture::{CompressedImageFormats, ImageType},
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
 
+use bevy_pen_tool_model::formats::*;
 use bevy_pen_tool_model::mesh::*;
 use bevy_pen_tool_model::model::*;
-use bevy_pen_tool_model::spawn_bezier;
-
-use serde::Deserialize;
-use serde::Serialize;
+use bevy_pen_tool_model::{register_scene_types, spawn_bezier};
+use bevy_pen_tool_model::{PenFill, PenFollower, PenRoad};
 
 use std::collections::HashMap;
 use std::collections::HashSet;
-
-use std::fs::File;
-use std::io::Read;
-use std::io::Write;
+use std::fmt;
+use std::path::PathBuf;
 
 use rand::{thread_rng, Rng};
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
-struct MeshMeta {
-    center_of_mass: Vec2,
-    position: Vec2,
-    bounding_box: (Vec2, Vec2),
-    color: Vec4,
+/// A request to save or load editor content at a given path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IoRequest {
+    /// Save the group as a `.group` file.
+    SaveGroup { group_id: GroupId, path: PathBuf },
+    /// Compute the look-up table of the group and save it as a `.lut` file.
+    ExportLut { group_id: GroupId, path: PathBuf },
+    /// Compute the look-up table of the group and save it as a Rust module with `const`
+    /// arrays, to be compiled into a game. The curves of the group are added as `CURVES`
+    /// when `include_curves` is set.
+    ExportRust {
+        group_id: GroupId,
+        path: PathBuf,
+        include_curves: bool,
+    },
+    /// Replace the curves on the canvas by the groups saved in a `.group` file.
+    LoadGroup { path: PathBuf },
+    /// Save every group and reference image, the grid and the guides as a `.pen` project
+    /// file.
+    SaveProject { path: PathBuf },
+    /// Replace the curves, reference images, grid and guides on the canvas by those of a
+    /// `.pen` project file.
+    LoadProject { path: PathBuf },
+    /// Save a fill mesh as an `.obj` file, along with its `.meta` side-car file.
+    SaveMesh { mesh_id: MeshId, path: PathBuf },
+    /// Spawn the mesh saved in an `.obj` file and its `.meta` side-car file.
+    LoadMesh { path: PathBuf },
+    /// Add the groups of a `.group` file to the canvas, centered on `position`.
+    ImportGroup { path: PathBuf, position: Vec2 },
+    /// Fit curves through the points of a `.lut` file and add them to the canvas as one
+    /// group, centered on `position`.
+    ImportLut { path: PathBuf, position: Vec2 },
+    /// Spawn the mesh saved in an `.obj` file with its center of mass at `position`.
+    ImportMesh { path: PathBuf, position: Vec2 },
+    /// Turn the paths and shapes of an `.svg` file into groups, centered on `position`.
+    ImportSvg { path: PathBuf, position: Vec2 },
+    /// Save every group as a `<path>` of an `.svg` file. Groups with a fill mesh are filled
+    /// with the color of the mesh.
+    ExportSvg { path: PathBuf },
+    /// Turn the paths of the shape layers of a Lottie `.json` file into groups, centered on
+    /// `position`.
+    ImportLottie { path: PathBuf, position: Vec2 },
+    /// Save every group as a shape layer of a Lottie `.json` file, filled like in
+    /// [`IoRequest::ExportSvg`]. With an `animation`, a dot moves along each group.
+    ExportLottie {
+        path: PathBuf,
+        animation: Option<LottieAnimation>,
+    },
+    /// Turn the SPLINE, LWPOLYLINE, POLYLINE and LINE entities of a `.dxf` file into groups,
+    /// centered on `position`. Touching entities of a layer make up a single group.
+    ImportDxf { path: PathBuf, position: Vec2 },
+    /// Save every group on its own layer of a `.dxf` file, as an exact SPLINE or as a
+    /// POLYLINE through the points of its look-up table.
+    ExportDxf { path: PathBuf, curves: DxfCurves },
+    /// Turn the LineString and Polygon features of a `.geojson` file into groups, centered on
+    /// `position`. Coordinates are projected with [`Globals::geojson_projection`].
+    ImportGeoJson { path: PathBuf, position: Vec2 },
+    /// Save every group as a LineString, or a Polygon if it is closed, sampled from its
+    /// look-up table. The metadata of the curves become the properties of the features.
+    ExportGeoJson { path: PathBuf },
+    /// Turn the polyline and polygon objects of a Tiled `.tmx` or `.tmj` map into groups of
+    /// straight curves, centered on `position`.
+    ImportTiled { path: PathBuf, position: Vec2 },
+    /// Save every group as a polyline or polygon object of a Tiled map, with a point every
+    /// `spacing` canvas units along its look-up table. The map is a `.tmx` file if the path
+    /// has that extension, and a `.tmj` file otherwise.
+    ExportTiled {
+        path: PathBuf,
+        spacing: f32,
+        tile_size: UVec2,
+    },
+    /// Turn a text into closed groups, one per contour of every glyph, centered on `position`.
+    /// The `font` is a `.ttf` or `.otf` file of the assets directory.
+    ImportText {
+        font: PathBuf,
+        text: String,
+        layout: TextLayout,
+        position: Vec2,
+    },
+    /// Trace the pixels of an image of the assets directory, such as an 8-bit or 16-bit
+    /// `.png` file, into closed groups, holes included, centered on `position`.
+    ImportImage {
+        path: PathBuf,
+        options: TraceOptions,
+        position: Vec2,
+    },
+    /// Save a fill or road mesh as a `.gltf` file, or as a binary `.glb` file if the path has
+    /// that extension.
+    ExportGltf { mesh_id: MeshId, path: PathBuf },
+    /// Save every fill and road mesh as the nodes of a single `.gltf` or `.glb` file.
+    ExportSceneGltf { path: PathBuf },
+    /// Save every fill and road mesh as a Bevy `.scn.ron` scene, which games load with
+    /// `bevy_pen_tool_runtime` alone. With a `follower_speed`, every group also gets an
+    /// entity that moves along it at that speed.
+    ExportScene {
+        path: PathBuf,
+        follower_speed: Option<f32>,
+    },
+}
+
+/// Largest distance between the points of an imported look-up table and the fitted curves.
+const LUT_FIT_MAX_ERROR: f64 = 1.0;
+
+#[derive(Debug)]
+pub enum IoError {
+    File(std::io::Error),
+    Format(SaveFormatError),
+    UnknownGroup(GroupId),
+    UnknownMesh(MeshId),
+    InvalidMesh(String),
+    Conversion(ConversionError),
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(e) => write!(f, "{}", e),
+            Self::Format(e) => write!(f, "{}", e),
+            Self::UnknownGroup(id) => write!(f, "no group with id {:?}", id),
+            Self::UnknownMesh(id) => write!(f, "no fill mesh with id {}", id),
+            Self::InvalidMesh(reason) => write!(f, "invalid mesh: {}", reason),
+            Self::Conversion(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for IoError {}
+
+impl From<std::io::Error> for IoError {
+    fn from(e: std::io::Error) -> Self {
+        Self::File(e)
+    }
 }
 
-pub fn save(
+impl From<SaveFormatError> for IoError {
+    fn from(e: SaveFormatError) -> Self {
+        Self::Format(e)
+    }
+}
+
+impl From<ConversionError> for IoError {
+    fn from(e: ConversionError) -> Self {
+        Self::Conversion(e)
+    }
+}
+
+/// Sent once for every [`IoRequest`], after it has been handled.
+#[derive(Debug)]
+pub enum IoResponse {
+    Success(IoRequest),
+    Error(IoRequest, IoError),
+}
+
+impl IoResponse {
+    fn from_result(request: &IoRequest, result: Result<(), IoError>) -> Self {
+        match result {
+            Ok(()) => {
+                info!("{:?} succeeded", request);
+                Self::Success(request.clone())
+            }
+            Err(e) => {
+                info!("{:?} failed: {}", request, e);
+                Self::Error(request.clone(), e)
+            }
+        }
+    }
+}
+
+pub(crate) struct IoPlugin;
+
+impl Plugin for IoPlugin {
+    fn build(&self, app: &mut App) {
+        // keep the backend chosen by the user, if any
+        if !app.world.contains_resource::<PenStorage>() {
+            app.insert_resource(PenStorage::default());
+        }
+
+        // the exported scenes only hold registered components
+        register_scene_types(app);
+
+        app.add_event::<IoRequest>()
+            .add_event::<IoResponse>()
+            .add_system(save_groups)
+            .add_system(save_meshes)
+            .add_system(save_projects)
+            .add_system(export_paths)
+            .add_system(export_gltfs)
+            .add_system(export_scenes)
+            .add_system(load_meshes)
+            .add_system_set(
+                SystemSet::on_update("ModelViewController")
+                    .with_system(load_groups)
+                    .after("model"),
+            );
+    }
+}
+
+pub fn save_groups(
     bezier_curves: Res<Assets<Bezier>>,
-    // group_query: Query<&Handle<Group>, With<GroupParent>>,
-    selection: Res<Selection>,
-    mesh_query: Query<(&Mesh2dHandle, &Handle<FillMesh2dMaterial>)>,
-    fill_mats: Res<Assets<FillMesh2dMaterial>>,
-    // road_mesh_query: Query<(&Handle<Mesh>, &RoadMesh)>,
     mut groups: ResMut<Assets<Group>>,
-    meshes: Res<Assets<Mesh>>,
-    globals: ResMut<Globals>,
-    mut action_event_reader: EventReader<Action>,
+    globals: Res<Globals>,
     maps: Res<Maps>,
+    mut storage: ResMut<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
 ) {
-    if action_event_reader.iter().any(|x| x == &Action::Save) {
-        //
-
-        //
-        // ////////////// start.  Save individual Bezier curves
-        // let mut vec: Vec<Bezier> = Vec::new();
-        // for bezier_handle in query.iter() {
-        //     let bezier = bezier_curves.get(bezier_handle).unwrap();
-        //     let mut bezier_clone = bezier.clone();
-        //     bezier_clone.lut = Vec::new();
-        //     vec.push(bezier_clone);
-        // }
-
-        // let serialized = serde_json::to_string_pretty(&vec).unwrap();
-
-        // let path = "curves.txt";
-        // let mut output = File::create(path).unwrap();
-        // let _result = output.write(serialized.as_bytes());
-        // ////////////// end.  Save individual Bezier curves
-        //
-
-        for selected in selection.selected.iter() {
-            match selected {
-                SelectionChoice::CurveSet(bezier_ids) => {
-                    ////////////// start. Save Group and save Group look-up table
-
-                    // collect all the different groups
-                    let mut group_ids: HashSet<GroupId> = HashSet::new();
-                    for bezier_id in bezier_ids.iter() {
-                        let handle = maps.bezier_map.get(bezier_id).unwrap();
-                        let bezier = bezier_curves.get(&handle.handle).unwrap();
-                        group_ids.insert(bezier.group);
-                    }
+    for request in io_requests.iter() {
+        let result = match request {
+            IoRequest::SaveGroup { group_id, path } => save_group(
+                *group_id,
+                path,
+                &bezier_curves,
+                &mut groups,
+                &globals,
+                &maps,
+                &mut storage,
+            ),
+            IoRequest::ExportLut { group_id, path } => export_lut(
+                *group_id,
+                path,
+                &bezier_curves,
+                &mut groups,
+                &globals,
+                &maps,
+                &mut storage,
+            ),
+            IoRequest::ExportRust {
+                group_id,
+                path,
+                include_curves,
+            } => export_rust(
+                *group_id,
+                path,
+                *include_curves,
+                &bezier_curves,
+                &mut groups,
+                &globals,
+                &maps,
+                &mut storage,
+            ),
+            _ => continue,
+        };
+        io_responses.send(IoResponse::from_result(request, result));
+    }
+}
 
-                    for group_id in group_ids.iter() {
-                        let group_handle = maps.group_map.get(group_id).unwrap();
-                        let group = groups.get_mut(&group_handle).unwrap();
-                        let mut group_vec = Vec::new();
-
-                        // for group_handle in group_query.iter() {
-
-                        // let group = groups.get_mut(group_handle).unwrap();
-                        //
-                        ////////////// start. Save Group look-up table
-                        let lut_dialog_result =
-                            open_file_dialog("my_group", "look_up_tables", ".lut");
-                        if let Some(lut_path) = lut_dialog_result {
-                            let bezier_assets =
-                                bezier_curves
-                                    .iter()
-                                    .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
-
-                            group.compute_standalone_lut(
-                                &bezier_assets,
-                                globals.group_lut_num_points,
-                            );
-                            let lut_serialized =
-                                serde_json::to_string_pretty(&group.standalone_lut).unwrap();
-                            // let lut_path = "assets/lut/my_group_lut.txt";
-                            let mut lut_output = File::create(&lut_path).unwrap();
-                            let _lut_write_result = lut_output.write(lut_serialized.as_bytes());
-                        }
+fn save_group(
+    group_id: GroupId,
+    path: &PathBuf,
+    bezier_curves: &Res<Assets<Bezier>>,
+    groups: &mut ResMut<Assets<Group>>,
+    globals: &Res<Globals>,
+    maps: &Res<Maps>,
+    storage: &mut PenStorage,
+) -> Result<(), IoError> {
+    let group = maps
+        .group_map
+        .get(&group_id)
+        .and_then(|handle| groups.get_mut(handle))
+        .ok_or(IoError::UnknownGroup(group_id))?;
+
+    let group_vec = vec![prepared_group_save(group, bezier_curves, maps)];
+    storage.write(path, &group_vec.to_versioned_bytes(globals.save_encoding)?)?;
+    Ok(())
+}
+
+// the ends and the traversal order of a group are only known once its look-up table is
+// computed, which latching curves leaves to a later frame
+fn prepared_group_save(
+    group: &mut Group,
+    bezier_curves: &Res<Assets<Bezier>>,
+    maps: &Maps,
+) -> GroupSaveLoad {
+    let bezier_assets = bezier_curves
+        .iter()
+        .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
+
+    group.find_connected_ends(&bezier_assets, maps.bezier_map.clone());
+    group.group_lut(&bezier_assets, maps.bezier_map.clone());
+    group.into_group_save(bezier_curves)
+}
 
-                        ////////////// start. Save Group
-                        let group_dialog_result = open_file_dialog("my_group", "groups", ".group");
-                        if let Some(group_path) = group_dialog_result {
-                            group_vec.push(group.into_group_save(&bezier_curves).clone());
-                            // }
+fn export_lut(
+    group_id: GroupId,
+    path: &PathBuf,
+    bezier_curves: &Res<Assets<Bezier>>,
+    groups: &mut ResMut<Assets<Group>>,
+    globals: &Res<Globals>,
+    maps: &Res<Maps>,
+    storage: &mut PenStorage,
+) -> Result<(), IoError> {
+    let group = maps
+        .group_map
+        .get(&group_id)
+        .and_then(|handle| groups.get_mut(handle))
+        .ok_or(IoError::UnknownGroup(group_id))?;
+
+    let bezier_assets = bezier_curves
+        .iter()
+        .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
+
+    group.compute_standalone_lut(&bezier_assets, globals.group_lut_num_points);
+
+    let lut_serialized = group
+        .standalone_lut
+        .to_versioned_bytes(globals.save_encoding)?;
+    storage.write(path, &lut_serialized)?;
+    Ok(())
+}
 
-                            let serialized = serde_json::to_string_pretty(&group_vec).unwrap();
+fn export_rust(
+    group_id: GroupId,
+    path: &PathBuf,
+    include_curves: bool,
+    bezier_curves: &Res<Assets<Bezier>>,
+    groups: &mut ResMut<Assets<Group>>,
+    globals: &Res<Globals>,
+    maps: &Res<Maps>,
+    storage: &mut PenStorage,
+) -> Result<(), IoError> {
+    let group = maps
+        .group_map
+        .get(&group_id)
+        .and_then(|handle| groups.get_mut(handle))
+        .ok_or(IoError::UnknownGroup(group_id))?;
+
+    let bezier_assets = bezier_curves
+        .iter()
+        .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
+
+    group.compute_standalone_lut(&bezier_assets, globals.group_lut_num_points);
+
+    let curves = if include_curves {
+        let group_save = group.into_group_save(bezier_curves);
+        Some(
+            group_save
+                .path_curves()
+                .into_iter()
+                .map(CurvePositions::from)
+                .collect::<Vec<CurvePositions>>(),
+        )
+    } else {
+        None
+    };
+
+    let mut source = String::from("// Generated by bevy_pen_tool, do not edit.\n\n");
+    source.push_str(&lut_to_rust(&group.standalone_lut, curves.as_deref()));
+    storage.write(path, source.as_bytes())?;
+    Ok(())
+}
 
-                            // let path = "curve_groups.txt";
-                            let mut output = File::create(group_path).unwrap();
-                            let _group_write_result = output.write(serialized.as_bytes());
-                        }
-                    }
+pub fn save_projects(
+    reference_query: Query<&ReferenceImage>,
+    guide_query: Query<&Guide>,
+    grid: Res<Grid>,
+    bezier_curves: Res<Assets<Bezier>>,
+    mut groups: ResMut<Assets<Group>>,
+    maps: Res<Maps>,
+    globals: Res<Globals>,
+    mut storage: ResMut<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
+) {
+    for request in io_requests.iter() {
+        let path = match request {
+            IoRequest::SaveProject { path } => path,
+            _ => continue,
+        };
+
+        let mut project = ProjectSaveLoad::default();
+        for group_handle in maps.group_map.values() {
+            if let Some(group) = groups.get_mut(group_handle) {
+                let group_save = prepared_group_save(group, &bezier_curves, &maps);
+                if !group_save.lut.is_empty() {
+                    project.groups.push(group_save);
                 }
-                SelectionChoice::Mesh(PenMesh { id, bounding_box }, position) => {
-                    //
-                    let mesh_entity = maps.mesh_map.get(id).unwrap();
-                    let (mesh_handle, fill_material_handle) = mesh_query.get(*mesh_entity).unwrap();
-                    let fill_mat = fill_mats.get(fill_material_handle).unwrap();
+            }
+        }
+        project.references = reference_query.iter().cloned().collect();
+        project.grid = grid.clone();
+        project.guides = guide_query.iter().cloned().collect();
+
+        let result = project
+            .to_versioned_bytes(globals.save_encoding)
+            .map_err(IoError::from)
+            .and_then(|bytes| storage.write(path, &bytes).map_err(IoError::from));
+        io_responses.send(IoResponse::from_result(request, result));
+    }
+}
 
-                    let mut default_path = std::env::current_dir().unwrap();
-                    default_path.push("assets");
-                    // default_path.push("meshes");
+pub fn export_paths(
+    fill_query: Query<(&SourceGroup, &Handle<FillMesh2dMaterial>)>,
+    fill_mats: Res<Assets<FillMesh2dMaterial>>,
+    bezier_curves: Res<Assets<Bezier>>,
+    mut groups: ResMut<Assets<Group>>,
+    maps: Res<Maps>,
+    globals: Res<Globals>,
+    mut storage: ResMut<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
+) {
+    for request in io_requests.iter() {
+        let path = match request {
+            IoRequest::ExportSvg { path }
+            | IoRequest::ExportLottie { path, .. }
+            | IoRequest::ExportDxf { path, .. }
+            | IoRequest::ExportGeoJson { path }
+            | IoRequest::ExportTiled { path, .. } => path,
+            _ => continue,
+        };
 
-                    println!("saving messhh");
+        let fills = fill_query
+            .iter()
+            .filter_map(|(source, handle)| {
+                let color = fill_mats.get(handle)?.color;
+                Some((
+                    source.0,
+                    Color::rgba_linear(color.x, color.y, color.z, color.w),
+                ))
+            })
+            .collect::<HashMap<GroupId, Color>>();
+
+        let mut saved_groups = Vec::new();
+        for (group_id, group_handle) in maps.group_map.iter() {
+            if let Some(group) = groups.get_mut(group_handle) {
+                let group_save = prepared_group_save(group, &bezier_curves, &maps);
+                if !group_save.lut.is_empty() {
+                    saved_groups.push((group_save, fills.get(group_id).copied()));
+                }
+            }
+        }
 
-                    if let Some(path) = open_file_dialog("my_mesh", "meshes", ".obj") {
-                        save_mesh(&mesh_handle.0, &meshes, path.clone());
+        let contents = match request {
+            IoRequest::ExportLottie { animation, .. } => {
+                let paths = saved_groups
+                    .iter()
+                    .map(|(group, fill)| LottieExportPath::from_group(group, *fill))
+                    .collect::<Vec<LottieExportPath>>();
+                write_lottie(&paths, *animation)
+            }
+            IoRequest::ExportDxf { curves, .. } => {
+                let paths = saved_groups
+                    .iter()
+                    .enumerate()
+                    .map(|(k, (group, _))| DxfExportPath::from_group(group, &format!("group{}", k)))
+                    .collect::<Vec<DxfExportPath>>();
+                write_dxf(&paths, *curves)
+            }
+            IoRequest::ExportGeoJson { .. } => {
+                let paths = saved_groups
+                    .iter()
+                    .map(|(group, _)| GeoJsonExportPath::from_group(group))
+                    .collect::<Vec<GeoJsonExportPath>>();
+                write_geojson(&paths, &globals.geojson_projection)
+            }
+            IoRequest::ExportTiled {
+                spacing, tile_size, ..
+            } => {
+                let paths = saved_groups
+                    .iter()
+                    .enumerate()
+                    .map(|(k, (group, _))| TiledExportPath::from_group(group, k, *spacing))
+                    .collect::<Vec<TiledExportPath>>();
+                let format = match path.extension().and_then(|e| e.to_str()) {
+                    Some("tmx") => TiledFormat::Tmx,
+                    _ => TiledFormat::Json,
+                };
+                write_tiled(&paths, format, *tile_size)
+            }
+            _ => {
+                let paths = saved_groups
+                    .iter()
+                    .map(|(group, fill)| SvgExportPath::from_group(group, *fill))
+                    .collect::<Vec<SvgExportPath>>();
+                write_svg(&paths)
+            }
+        };
 
-                        let mesh_info = MeshMeta {
-                            center_of_mass: fill_mat.center_of_mass,
-                            position: *position,
-                            bounding_box: *bounding_box,
+        let result = storage
+            .write(path, contents.as_bytes())
+            .map_err(IoError::from);
+        io_responses.send(IoResponse::from_result(request, result));
+    }
+}
+
+pub fn export_gltfs(
+    mesh_query: Query<(
+        &Mesh2dHandle,
+        &Transform,
+        Option<&Handle<FillMesh2dMaterial>>,
+        Option<&Handle<RoadMesh2dMaterial>>,
+    )>,
+    meshes: Res<Assets<Mesh>>,
+    fill_mats: Res<Assets<FillMesh2dMaterial>>,
+    road_mats: Res<Assets<RoadMesh2dMaterial>>,
+    asset_server: Res<AssetServer>,
+    maps: Res<Maps>,
+    mut storage: ResMut<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
+) {
+    for request in io_requests.iter() {
+        let (mesh_ids, path) = match request {
+            IoRequest::ExportGltf { mesh_id, path } => (vec![*mesh_id], path),
+            IoRequest::ExportSceneGltf { path } => {
+                let mut mesh_ids = maps.mesh_map.keys().copied().collect::<Vec<MeshId>>();
+                mesh_ids.sort();
+                (mesh_ids, path)
+            }
+            _ => continue,
+        };
+
+        let result = mesh_ids
+            .iter()
+            .map(|mesh_id| {
+                let (mesh_handle, transform, fill_handle, road_handle) = maps
+                    .mesh_map
+                    .get(mesh_id)
+                    .and_then(|entity| mesh_query.get(*entity).ok())
+                    .ok_or(IoError::UnknownMesh(*mesh_id))?;
+                let mesh = meshes
+                    .get(&mesh_handle.0)
+                    .ok_or(IoError::UnknownMesh(*mesh_id))?;
+
+                let (name, material) = if let Some(fill_mat) =
+                    fill_handle.and_then(|handle| fill_mats.get(handle))
+                {
+                    (
+                        format!("fill{}", mesh_id),
+                        GltfMaterial::Color(fill_mat.color),
+                    )
+                } else if let Some(road_mat) = road_handle.and_then(|handle| road_mats.get(handle))
+                {
+                    (
+                        format!("road{}", mesh_id),
+                        road_texture(&road_mat.road_texture, &asset_server)?,
+                    )
+                } else {
+                    return Err(IoError::UnknownMesh(*mesh_id));
+                };
+
+                Ok(GltfMesh::from_mesh(&name, mesh, *transform, material)?)
+            })
+            .collect::<Result<Vec<GltfMesh>, IoError>>()
+            .and_then(|gltf_meshes| {
+                let is_binary = path
+                    .extension()
+                    .map_or(false, |extension| extension == "glb");
+                let bytes = if is_binary {
+                    write_glb(&gltf_meshes)?
+                } else {
+                    write_gltf(&gltf_meshes)?.into_bytes()
+                };
+                storage.write(path, &bytes)?;
+                Ok(())
+            });
+
+        io_responses.send(IoResponse::from_result(request, result));
+    }
+}
+
+/// Texture of the roads of exported scenes that were drawn with a texture without a file.
+const DEFAULT_ROAD_TEXTURE: &str = "textures/single_lane_road.png";
+
+pub fn export_scenes(
+    mesh_query: Query<(
+        &Mesh2dHandle,
+        &Transform,
+        &PenMesh,
+        Option<&Handle<FillMesh2dMaterial>>,
+        Option<&Handle<RoadMesh2dMaterial>>,
+    )>,
+    meshes: Res<Assets<Mesh>>,
+    fill_mats: Res<Assets<FillMesh2dMaterial>>,
+    road_mats: Res<Assets<RoadMesh2dMaterial>>,
+    bezier_curves: Res<Assets<Bezier>>,
+    mut groups: ResMut<Assets<Group>>,
+    asset_server: Res<AssetServer>,
+    type_registry: Res<bevy::reflect::TypeRegistry>,
+    maps: Res<Maps>,
+    globals: Res<Globals>,
+    mut storage: ResMut<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
+) {
+    for request in io_requests.iter() {
+        let (path, follower_speed) = match request {
+            IoRequest::ExportScene {
+                path,
+                follower_speed,
+            } => (path, follower_speed),
+            _ => continue,
+        };
+
+        let mut mesh_ids = maps.mesh_map.keys().copied().collect::<Vec<MeshId>>();
+        mesh_ids.sort();
+
+        let mut scene_world = World::new();
+        let result = mesh_ids
+            .iter()
+            .map(|mesh_id| {
+                let (mesh_handle, transform, pen_mesh, fill_handle, road_handle) = maps
+                    .mesh_map
+                    .get(mesh_id)
+                    .and_then(|entity| mesh_query.get(*entity).ok())
+                    .ok_or(IoError::UnknownMesh(*mesh_id))?;
+                let mesh = meshes
+                    .get(&mesh_handle.0)
+                    .ok_or(IoError::UnknownMesh(*mesh_id))?;
+
+                let data = pen_mesh_data(mesh)?;
+
+                let mut entity = scene_world.spawn();
+                entity.insert_bundle((*transform, pen_mesh.clone(), data));
+
+                if let Some(fill_mat) = fill_handle.and_then(|handle| fill_mats.get(handle)) {
+                    entity.insert_bundle((
+                        Name::new(format!("fill{}", mesh_id)),
+                        PenFill {
                             color: fill_mat.color,
+                        },
+                    ));
+                } else if let Some(road_mat) = road_handle.and_then(|handle| road_mats.get(handle))
+                {
+                    let texture = asset_server
+                        .get_handle_path(&road_mat.road_texture)
+                        .map(|asset_path| asset_path.path().to_string_lossy().into_owned())
+                        .unwrap_or_else(|| DEFAULT_ROAD_TEXTURE.to_string());
+                    entity.insert_bundle((
+                        Name::new(format!("road{}", mesh_id)),
+                        PenRoad { texture },
+                    ));
+                } else {
+                    return Err(IoError::UnknownMesh(*mesh_id));
+                }
+                Ok(())
+            })
+            .collect::<Result<(), IoError>>()
+            .and_then(|()| {
+                if let Some(speed) = follower_speed {
+                    let bezier_assets = bezier_curves
+                        .iter()
+                        .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
+                    let mut group_ids = maps.group_map.keys().copied().collect::<Vec<GroupId>>();
+                    group_ids.sort_by_key(|id| id.0);
+
+                    for (k, group_id) in group_ids.iter().enumerate() {
+                        let group = match groups.get_mut(&maps.group_map[group_id]) {
+                            Some(group) => group,
+                            None => continue,
                         };
+                        group.find_connected_ends(&bezier_assets, maps.bezier_map.clone());
+                        group.group_lut(&bezier_assets, maps.bezier_map.clone());
 
-                        let serialized = serde_json::to_string_pretty(&mesh_info).unwrap();
-                        let position_file_path = path.with_extension("meta");
-                        let mut output = File::create(position_file_path).unwrap();
-                        let _group_write_result = output.write(serialized.as_bytes());
+                        let lut = &group.standalone_lut;
+                        if lut.lut.is_empty() {
+                            continue;
+                        }
+                        let start = lut.lut[0].extend(globals.z_pos.heli);
+                        scene_world.spawn().insert_bundle((
+                            Name::new(format!("follower{}", k)),
+                            Transform::from_translation(start),
+                            PenFollower {
+                                lut: lut.lut.clone(),
+                                path_length: lut.path_length,
+                                speed: *speed,
+                                looping: true,
+                                rotate: true,
+                            },
+                        ));
                     }
                 }
-                _ => (),
-            }
-        }
-        ////////////// end. Save group and look-up table
-        //
 
-        // ////////////// start. Save mesh in obj format
-        // if let Some((mesh_handle, GroupMesh(_color))) = mesh_query.iter().next() {
-        //     let mesh_dialog_result = open_file_dialog("my_mesh", "meshes", ".obj");
-        //     save_mesh(mesh_handle, &meshes, mesh_dialog_result);
+                let scene = DynamicScene::from_world(&scene_world, &type_registry);
+                let contents = scene.serialize_ron(&type_registry).map_err(|e| {
+                    IoError::Conversion(ConversionError::Unsupported(e.to_string()))
+                })?;
+                storage.write(path, contents.as_bytes())?;
+                Ok(())
+            });
 
-        //     ////////////// end. Save mesh in obj format
-        // }
+        io_responses.send(IoResponse::from_result(request, result));
+    }
+}
 
-        // ////////////// start. Save road in obj format
-        // if let Some((road_mesh_handle, RoadMesh(_color))) = road_mesh_query.iter().next() {
-        //     let road_dialog_result = open_file_dialog("my_road", "meshes", ".obj");
-        //     save_mesh(road_mesh_handle, &meshes, road_dialog_result);
+// the encoded image file is embedded as it is, the decoded Image asset is not re-encoded
+fn road_texture(
+    texture: &Handle<Image>,
+    asset_server: &AssetServer,
+) -> Result<GltfMaterial, IoError> {
+    let (texture_path, bytes) = texture_file(texture, asset_server)?;
+
+    let mime_type = texture_path
+        .extension()
+        .and_then(|extension| image_mime_type(&extension.to_string_lossy()))
+        .ok_or_else(|| {
+            IoError::InvalidMesh(format!(
+                "{} cannot be used as a glTF texture",
+                texture_path.display()
+            ))
+        })?;
+
+    Ok(GltfMaterial::Texture {
+        mime_type: mime_type.to_string(),
+        bytes,
+    })
+}
 
-        //     ////////////// end. Save road in obj format
-        // }
-    }
+/// Path and contents of the image file a texture was loaded from.
+fn texture_file(
+    texture: &Handle<Image>,
+    asset_server: &AssetServer,
+) -> Result<(PathBuf, Vec<u8>), IoError> {
+    let asset_path = asset_server
+        .get_handle_path(texture)
+        .ok_or_else(|| IoError::InvalidMesh("the road texture has no file".to_string()))?;
+    let texture_path = asset_path.path().to_path_buf();
+
+    let bytes = futures_lite::future::block_on(asset_server.asset_io().load_path(&texture_path))
+        .map_err(|e| IoError::InvalidMesh(e.to_string()))?;
+
+    Ok((texture_path, bytes))
 }
 
-// only loads groups
-pub fn load_mesh(
-    mut commands: Commands,
-    // mut meshes: ResMut<Assets<Mesh>>,
+pub fn save_meshes(
+    mesh_query: Query<(
+        &Mesh2dHandle,
+        &PenMesh,
+        &Transform,
+        Option<&Handle<FillMesh2dMaterial>>,
+        Option<&Handle<RoadMesh2dMaterial>>,
+    )>,
+    fill_mats: Res<Assets<FillMesh2dMaterial>>,
+    road_mats: Res<Assets<RoadMesh2dMaterial>>,
+    meshes: Res<Assets<Mesh>>,
     asset_server: Res<AssetServer>,
-    // globals: Res<Globals>,
-    mut fill_materials: ResMut<Assets<FillMesh2dMaterial>>,
-    mut maps: ResMut<Maps>,
-    mut action_event_reader: EventReader<Action>,
+    maps: Res<Maps>,
+    mut storage: ResMut<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
 ) {
-    if action_event_reader.iter().any(|x| x == &Action::Load) {
-        let mut default_path = std::env::current_dir().unwrap();
-        default_path.push("assets");
-        default_path.push("meshes");
-
-        if let Some(res) = rfd::FileDialog::new()
-            .add_filter("text", &["obj"])
-            .set_directory(&default_path)
-            .pick_files()
-        {
-            if let Some(path) = res.get(0) {
-                let mesh_handle: Handle<Mesh> = asset_server.load(path.to_str().unwrap());
-                let mut rng = thread_rng();
-                let id = rng.gen::<u64>();
-
-                // get mesh info using the .meta extension
-                let meta_path = path.with_extension("meta");
-                let mut file = std::fs::File::open(meta_path).unwrap();
-                let mut contents = String::new();
-                file.read_to_string(&mut contents).unwrap();
-                let loaded_mesh_params: MeshMeta = serde_json::from_str(&contents).unwrap();
-
-                // Useless at the moment, but here for future use
-                let mat_handle = fill_materials.add(FillMesh2dMaterial {
-                    color: loaded_mesh_params.color.into(),
-                    center_of_mass: loaded_mesh_params.center_of_mass, // center_of_mass, // is this Ok?
-                    show_com: 0.0,                                     // show center of mass
-                });
-
-                let entity = commands
-                    .spawn_bundle(MaterialMesh2dBundle {
-                        mesh: Mesh2dHandle(mesh_handle),
-                        material: mat_handle,
-                        transform: Transform::default(),
-                        ..default()
-                    })
-                    .insert(PenMesh {
-                        id,
-                        bounding_box: loaded_mesh_params.bounding_box, // bounding box relative to center of mass
-                    })
-                    .id();
-
-                maps.mesh_map.insert(id, entity);
-
-                // meshes.add(mesh_handle);
-            }
-            // let mesh_handle = asset_server.load("example.obj");
+    for request in io_requests.iter() {
+        if let IoRequest::SaveMesh { mesh_id, path } = request {
+            let result = maps
+                .mesh_map
+                .get(mesh_id)
+                .and_then(|entity| mesh_query.get(*entity).ok())
+                .ok_or(IoError::UnknownMesh(*mesh_id))
+                .and_then(
+                    |(mesh_handle, pen_mesh, transform, fill_handle, road_handle)| {
+                        let mesh = meshes
+                            .get(&mesh_handle.0)
+                            .ok_or(IoError::UnknownMesh(*mesh_id))?;
+                        let name = path.file_stem().map_or("my_mesh".to_string(), |stem| {
+                            stem.to_string_lossy().to_string()
+                        });
+
+                        let (material, center_of_mass, color) = if let Some(fill_mat) =
+                            fill_handle.and_then(|handle| fill_mats.get(handle))
+                        {
+                            let color = fill_mat.color;
+                            let material = ObjMaterial {
+                                name: name.clone(),
+                                color: Color::rgba_linear(color.x, color.y, color.z, color.w),
+                                texture: None,
+                            };
+                            (material, fill_mat.center_of_mass, color)
+                        } else if let Some(road_mat) =
+                            road_handle.and_then(|handle| road_mats.get(handle))
+                        {
+                            // the texture is copied next to the material library
+                            let (texture_path, bytes) =
+                                texture_file(&road_mat.road_texture, &asset_server)?;
+                            let texture_name = texture_path
+                                .file_name()
+                                .map_or("road.png".to_string(), |name| {
+                                    name.to_string_lossy().to_string()
+                                });
+                            storage.write(&path.with_file_name(&texture_name), &bytes)?;
+
+                            let material = ObjMaterial {
+                                name: name.clone(),
+                                color: Color::WHITE,
+                                texture: Some(texture_name),
+                            };
+                            (material, road_mat.center_of_mass, Vec4::ONE)
+                        } else {
+                            return Err(IoError::UnknownMesh(*mesh_id));
+                        };
+
+                        save_mesh(mesh, &name, &material, path, &mut storage)?;
+
+                        let mesh_info = MeshMeta {
+                            center_of_mass,
+                            position: transform.translation.truncate(),
+                            bounding_box: pen_mesh.bounding_box,
+                            color,
+                        };
+
+                        storage.write(
+                            &path.with_extension("meta"),
+                            mesh_info.to_versioned_string()?.as_bytes(),
+                        )?;
+                        Ok(())
+                    },
+                );
+
+            io_responses.send(IoResponse::from_result(request, result));
         }
     }
 }
 
-pub fn load(
-    query: Query<Entity, Or<(With<BezierParent>, With<GroupParent>)>>,
-    mut bezier_curves: ResMut<Assets<Bezier>>,
-    // mut groups: ResMut<Assets<Group>>,
+pub fn load_meshes(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    // mut my_shader_params: ResMut<Assets<BezierMat>>,
-    clearcolor_struct: Res<ClearColor>,
-    mut globals: ResMut<Globals>,
-    mut selection: ResMut<Selection>,
+    mut fill_materials: ResMut<Assets<FillMesh2dMaterial>>,
+    mut road_materials: ResMut<Assets<RoadMesh2dMaterial>>,
+    mut images: ResMut<Assets<Image>>,
     mut maps: ResMut<Maps>,
-    mut action_event_reader: EventReader<Action>,
-    mut loaded_event_writer: EventWriter<Loaded>,
-    mut selection_params: ResMut<Assets<SelectionMat>>,
-    mut controls_params: ResMut<Assets<BezierControlsMat>>,
-    mut ends_params: ResMut<Assets<BezierEndsMat>>,
-    // mut mid_params: ResMut<Assets<BezierMidMat>>,
-    mut add_to_history_event_writer: EventWriter<HistoryAction>,
+    globals: Res<Globals>,
+    storage: Res<PenStorage>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
 ) {
-    if action_event_reader.iter().any(|x| x == &Action::Load) {
-        let mut default_path = std::env::current_dir().unwrap();
-        default_path.push("saved");
-        default_path.push("groups");
-
-        let res = rfd::FileDialog::new()
-            .add_filter("text", &["group"])
-            .set_directory(&default_path)
-            .pick_files();
-
-        // cancel loading if user cancelled the file dialog
-        let path: std::path::PathBuf;
-        if let Some(chosen_path) = res.clone() {
-            let path_some = chosen_path.get(0);
-            if let Some(path_local) = path_some {
-                path = path_local.clone();
-            } else {
-                return ();
-            }
-        } else {
-            return ();
-        }
+    for request in io_requests.iter() {
+        let (path, position) = match request {
+            IoRequest::LoadMesh { path } => (path, Vec2::ZERO),
+            IoRequest::ImportMesh { path, position } => (path, *position),
+            _ => continue,
+        };
 
-        let clearcolor = clearcolor_struct.0;
+        let result = load_mesh(
+            path,
+            position,
+            &mut commands,
+            &mut meshes,
+            &mut fill_materials,
+            &mut road_materials,
+            &mut images,
+            &mut maps,
+            &globals,
+            &storage,
+        );
+        io_responses.send(IoResponse::from_result(request, result));
+    }
+}
 
-        // delete all current groups and curves before spawning the saved ones
-        for entity in query.iter() {
-            commands.entity(entity).despawn_recursive();
-        }
+// a missing side-car file is not an error, for .obj files that come from other tools
+fn read_optional(storage: &PenStorage, path: &PathBuf) -> Result<Option<String>, IoError> {
+    match storage.read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
 
-        globals.do_hide_anchors = false;
-        globals.do_hide_bounding_boxes = true;
+fn load_mesh(
+    path: &PathBuf,
+    position: Vec2,
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    fill_materials: &mut ResMut<Assets<FillMesh2dMaterial>>,
+    road_materials: &mut ResMut<Assets<RoadMesh2dMaterial>>,
+    images: &mut ResMut<Assets<Image>>,
+    maps: &mut ResMut<Maps>,
+    globals: &Globals,
+    storage: &PenStorage,
+) -> Result<(), IoError> {
+    let ObjFile {
+        mut mesh,
+        material_library,
+        material_name,
+    } = parse_obj(&storage.read_to_string(path)?)?;
+
+    // get mesh info using the .meta extension
+    let mesh_meta = read_optional(storage, &path.with_extension("meta"))?
+        .map(|contents| MeshMeta::from_versioned_str(&contents))
+        .transpose()?;
+
+    let mut material = None;
+    if let Some(material_library) = material_library {
+        if let Some(contents) = read_optional(storage, &path.with_file_name(material_library))? {
+            let mut library = parse_mtl(&contents)?;
+            material = match material_name {
+                Some(name) => library.remove(&name),
+                None => library.into_values().next(),
+            };
+        }
+    }
 
-        let mut file = std::fs::File::open(path).unwrap();
+    let color = match (&material, &mesh_meta) {
+        (Some(material), _) => material.color,
+        (None, Some(meta)) => {
+            Color::rgba_linear(meta.color.x, meta.color.y, meta.color.z, meta.color.w)
+        }
+        (None, None) => Color::WHITE,
+    };
+
+    let num_vertices = mesh.count_vertices();
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_COLOR,
+        vec![color.as_rgba_f32(); num_vertices],
+    );
+
+    let (center_of_mass, bounding_box) = match &mesh_meta {
+        Some(meta) => (meta.center_of_mass, meta.bounding_box),
+        None => {
+            let mut mins_maxes = MinsMaxes::default();
+            if let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(positions)) =
+                mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            {
+                for [x, y, _] in positions.iter() {
+                    mins_maxes.update(Vec2::new(*x, *y));
+                }
+            }
+            (Vec2::ZERO, mins_maxes.to_vec2_pair())
+        }
+    };
+
+    let mesh_handle = meshes.add(mesh);
+    let mut rng = thread_rng();
+    let id = rng.gen::<u64>();
+
+    let texture = material
+        .as_ref()
+        .and_then(|material| material.texture.clone());
+    let mut entity_commands = if let Some(texture) = texture {
+        let texture_path = path.with_file_name(&texture);
+        let extension = texture_path.extension().map_or(String::new(), |extension| {
+            extension.to_string_lossy().to_string()
+        });
+        let image = Image::from_buffer(
+            &storage.read(&texture_path)?,
+            ImageType::Extension(&extension),
+            CompressedImageFormats::NONE,
+            true,
+        )
+        .map_err(|e| IoError::InvalidMesh(format!("{}: {}", texture, e)))?;
+
+        let mat_handle = road_materials.add(RoadMesh2dMaterial {
+            road_texture: images.add(image),
+            center_of_mass,
+            show_com: 0.0,
+        });
+
+        commands.spawn_bundle(MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(mesh_handle),
+            material: mat_handle,
+            transform: Transform::from_translation(position.extend(globals.z_pos.road)),
+            ..default()
+        })
+    } else {
+        // Useless at the moment, but here for future use
+        let mat_handle = fill_materials.add(FillMesh2dMaterial {
+            color: color.into(),
+            center_of_mass, // is this Ok?
+            show_com: 0.0,  // show center of mass
+        });
+
+        commands.spawn_bundle(MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(mesh_handle),
+            material: mat_handle,
+            transform: Transform::from_translation(position.extend(globals.z_pos.fill)),
+            ..default()
+        })
+    };
+
+    let entity = entity_commands
+        .insert(PenMesh {
+            id,
+            bounding_box, // bounding box relative to center of mass
+        })
+        .id();
 
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
+    maps.mesh_map.insert(id, entity);
 
-        let loaded_groups_vec: Vec<GroupSaveLoad> = serde_json::from_str(&contents).unwrap();
+    Ok(())
+}
 
-        let id: GroupId = GroupId::default();
+/// Everything needed to spawn saved or imported groups on the canvas.
+#[derive(SystemParam)]
+pub struct GroupSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    bezier_curves: ResMut<'w, Assets<Bezier>>,
+    groups: ResMut<'w, Assets<Group>>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    clearcolor: Res<'w, ClearColor>,
+    globals: ResMut<'w, Globals>,
+    maps: ResMut<'w, Maps>,
+    selection_params: ResMut<'w, Assets<SelectionMat>>,
+    controls_params: ResMut<'w, Assets<BezierControlsMat>>,
+    ends_params: ResMut<'w, Assets<BezierEndsMat>>,
+    add_to_history_event_writer: EventWriter<'w, 's, HistoryAction>,
+    group_event_writer: EventWriter<'w, 's, Handle<Group>>,
+    loaded_event_writer: EventWriter<'w, 's, Loaded>,
+}
 
-        let mut group = Group {
-            group: HashSet::new(),
-            bezier_handles: HashSet::new(),
-            lut: Vec::new(),
-            ends: None,
-            standalone_lut: StandaloneLut {
-                path_length: 0.0,
-                lut: Vec::new(),
-            },
-            id,
-            entity: None,
-        };
+impl<'w, 's> GroupSpawner<'w, 's> {
+    /// Spawns the curves of every group and registers the groups. Returns the ids of the
+    /// spawned curves.
+    pub fn spawn_groups(&mut self, mut loaded_groups: Vec<GroupSaveLoad>) -> HashSet<BezierId> {
+        // the same file can be loaded more than once
+        renew_ids(&mut loaded_groups);
 
+        let clearcolor = self.clearcolor.0;
         let mut curve_set: HashSet<BezierId> = HashSet::new();
 
-        for group_load_save in loaded_groups_vec {
+        for group_load_save in loaded_groups {
+            let mut group = Group {
+                standalone_lut: group_load_save.standalone_lut,
+                ..Default::default()
+            };
+
             for (mut bezier, anchor, t_ends, local_lut) in group_load_save.lut {
+                group.id = bezier.group;
+
                 let (entity, handle) = spawn_bezier(
                     &mut bezier,
-                    &mut bezier_curves,
-                    &mut commands,
-                    &mut meshes,
-                    &mut selection_params,
-                    &mut controls_params,
-                    &mut ends_params,
-                    // &mut mid_params,
+                    &mut self.bezier_curves,
+                    &mut self.commands,
+                    &mut self.meshes,
+                    &mut self.selection_params,
+                    &mut self.controls_params,
+                    &mut self.ends_params,
                     clearcolor,
-                    &mut globals,
-                    &mut maps,
-                    &mut add_to_history_event_writer,
+                    &mut self.globals,
+                    &mut self.maps,
+                    &mut self.add_to_history_event_writer,
                     &None, // does not have handle information
                     true,  // do send to history
                     false, // do not follow mouse
                 );
-                group.group.insert((entity.clone(), handle.clone()));
-                group.bezier_handles.insert(handle.clone());
-                group.standalone_lut = group_load_save.standalone_lut.clone();
+                group.add_curve(entity, handle.clone());
                 group.lut.push((handle.clone(), anchor, t_ends, local_lut));
 
                 curve_set.insert(handle.id.into());
             }
+
+            if group.lut.is_empty() {
+                continue;
+            }
+
+            let bezier_assets = self
+                .bezier_curves
+                .iter()
+                .collect::<HashMap<bevy::asset::HandleId, &Bezier>>();
+            group.find_connected_ends(&bezier_assets, self.maps.bezier_map.clone());
+
+            // same registration as for a freshly drawn curve
+            let mut group_handle: Handle<Group> = Handle::weak(group.id.0);
+            group_handle.make_strong(&self.groups);
+            let group_id = group.id;
+            self.loaded_event_writer.send(Loaded(group.clone()));
+            let strong_handle = self.groups.set(group_handle, group);
+            self.maps.group_map.insert(group_id, strong_handle.clone());
+            self.group_event_writer.send(strong_handle);
         }
-        selection.selected = vec![SelectionChoice::CurveSet(curve_set)];
 
-        // to create a group: select all the curves programmatically, and send a UiButton::Group event
-        loaded_event_writer.send(Loaded(group));
-        println!("{:?}", "loaded groups");
+        curve_set
     }
 }
 
-use std::path::PathBuf;
-pub fn open_file_dialog(save_name: &str, folder: &str, extension: &str) -> Option<PathBuf> {
-    let mut k = 0;
+pub fn load_groups(
+    query: Query<Entity, Or<(With<BezierParent>, With<GroupParent>)>>,
+    reference_query: Query<&ReferenceImage>,
+    guide_query: Query<Entity, With<Guide>>,
+    mut grid: ResMut<Grid>,
+    mut spawner: GroupSpawner,
+    mut selection: ResMut<Selection>,
+    storage: Res<PenStorage>,
+    asset_server: Res<AssetServer>,
+    mut io_requests: EventReader<IoRequest>,
+    mut io_responses: EventWriter<IoResponse>,
+    mut reference_requests: EventWriter<ReferenceRequest>,
+) {
+    for request in io_requests.iter() {
+        let (loaded_groups, position) = match request {
+            IoRequest::LoadGroup { path } => (read_groups(path, &storage), None),
+            IoRequest::LoadProject { path } => {
+                let project = read_project(path, &storage);
+                if let Ok(project) = &project {
+                    for reference in reference_query.iter() {
+                        reference_requests.send(ReferenceRequest::Remove(reference.id));
+                    }
+                    for reference in project.references.iter() {
+                        reference_requests.send(ReferenceRequest::Set(reference.clone()));
+                    }
+                    for entity in guide_query.iter() {
+                        spawner.commands.entity(entity).despawn();
+                    }
+                    for guide in project.guides.iter() {
+                        spawn_guide(&mut spawner.commands, guide.clone());
+                    }
+                    *grid = project.grid.clone();
+                }
+                (project.map(|project| project.groups), None)
+            }
+            IoRequest::ImportGroup { path, position } => {
+                (read_groups(path, &storage), Some(*position))
+            }
+            IoRequest::ImportLut { path, position } => (
+                read_lut_as_group(path, &storage, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportSvg { path, position } => (
+                read_svg_as_groups(path, &storage, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportLottie { path, position } => (
+                read_lottie_as_groups(path, &storage, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportDxf { path, position } => (
+                read_dxf_as_groups(path, &storage, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportGeoJson { path, position } => (
+                read_geojson_as_groups(path, &storage, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportTiled { path, position } => (
+                read_tiled_as_groups(path, &storage, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportText {
+                font,
+                text,
+                layout,
+                position,
+            } => (
+                read_text_as_groups(font, text, layout, &asset_server, &spawner.globals),
+                Some(*position),
+            ),
+            IoRequest::ImportImage {
+                path,
+                options,
+                position,
+            } => (
+                read_image_as_groups(path, options, &asset_server, &spawner.globals),
+                Some(*position),
+            ),
+            _ => continue,
+        };
 
-    let mut default_path = std::env::current_dir().unwrap();
-    default_path.push("saved");
-    default_path.push(folder.to_string());
-    let mut default_name: String;
+        let mut loaded_groups = match loaded_groups {
+            Ok(groups) => groups,
+            Err(e) => {
+                io_responses.send(IoResponse::from_result(request, Err(e)));
+                continue;
+            }
+        };
 
-    loop {
-        default_name = save_name.to_string();
-        default_name.push_str(&(k.to_string()));
-        default_name.push_str(extension);
+        if let Some(position) = position {
+            center_groups_on(&mut loaded_groups, position);
+        } else {
+            // delete all current groups and curves before spawning the saved ones
+            for entity in query.iter() {
+                spawner.commands.entity(entity).despawn_recursive();
+            }
+        }
 
-        default_path.push(&default_name);
+        spawner.globals.do_hide_anchors = false;
+        spawner.globals.do_hide_bounding_boxes = true;
 
-        if !default_path.exists() {
-            break;
-        }
-        default_path.pop();
+        let curve_set = spawner.spawn_groups(loaded_groups);
+        selection.selected = vec![SelectionChoice::CurveSet(curve_set)];
 
-        k += 1;
+        io_responses.send(IoResponse::from_result(request, Ok(())));
     }
+}
 
-    let res = rfd::FileDialog::new()
-        .set_file_name(&default_name)
-        .set_directory(&default_path)
-        .save_file();
-    println!("The user chose: {:#?}", &res);
+// older files are migrated to the current format before being deserialized
+fn read_groups(path: &PathBuf, storage: &PenStorage) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read(path)?;
+    Ok(Vec::<GroupSaveLoad>::from_versioned_bytes(&contents)?)
+}
 
-    return res;
+fn read_project(path: &PathBuf, storage: &PenStorage) -> Result<ProjectSaveLoad, IoError> {
+    let contents = storage.read(path)?;
+    Ok(ProjectSaveLoad::from_versioned_bytes(&contents)?)
 }
 
-pub fn save_mesh(mesh_handle: &Handle<Mesh>, meshes: &Res<Assets<Mesh>>, path: PathBuf) {
-    let mesh = meshes.get(mesh_handle).unwrap();
-    let vertex_attributes = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap();
-    let indices_u32 = mesh.indices().unwrap();
+fn read_lut_as_group(
+    path: &PathBuf,
+    storage: &PenStorage,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read(path)?;
+    let lut = StandaloneLut::from_versioned_bytes(&contents)?;
+
+    let curves = lut.fit_curves(LUT_FIT_MAX_ERROR);
+    if curves.is_empty() {
+        return Err(IoError::Format(SaveFormatError::Malformed(
+            "the look-up table has too few points".to_string(),
+        )));
+    }
 
-    match (vertex_attributes, indices_u32) {
-        (
-            bevy::render::mesh::VertexAttributeValues::Float32x3(vertices),
-            bevy::render::mesh::Indices::U32(indices),
-        ) => {
-            let obj_vertices = vertices
-                .clone()
-                .iter()
-                .map(|arr| obj_exporter::Vertex {
-                    x: arr[0] as f64,
-                    y: arr[1] as f64,
-                    z: arr[2] as f64,
-                })
-                .collect::<Vec<obj_exporter::Vertex>>();
-
-            // let mut obj_inds_vecs: Vec<Vec<u32>> =
-            // indices.chunks(3).map(|x| x.to_vec()).collect();
-            let obj_inds_vecs: Vec<(usize, usize, usize)> = indices
-                .chunks_exact(3)
-                .map(|z| {
-                    let mut x = z.iter();
-                    return (
-                        *x.next().unwrap() as usize,
-                        *x.next().unwrap() as usize,
-                        *x.next().unwrap() as usize,
-                    );
-                })
-                .collect();
-
-            let normals = vec![obj_exporter::Vertex {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-            }];
-            // let olen = obj_vertices.len();
-            let olen = 1;
-
-            // let tvert =
-
-            let set = obj_exporter::ObjSet {
-                material_library: None,
-                objects: vec![obj_exporter::Object {
-                    name: "My_mesh".to_owned(),
-                    vertices: obj_vertices,
-                    tex_vertices: vec![(0.0, 0.0); olen]
-                        .into_iter()
-                        .map(|(u, v)| obj_exporter::TVertex { u, v, w: 0.0 })
-                        .collect(),
-                    normals,
-                    geometry: vec![obj_exporter::Geometry {
-                        material_name: None,
-                        shapes: obj_inds_vecs
-                            .into_iter()
-                            .map(|(x, y, z)| obj_exporter::Shape {
-                                primitive: obj_exporter::Primitive::Triangle(
-                                    (x, None, None),
-                                    (y, None, None),
-                                    (z, None, None),
-                                ),
-                                groups: vec![],
-                                smoothing_groups: vec![],
-                            })
-                            .collect(),
-                    }],
-                }],
-            };
+    Ok(vec![GroupSaveLoad::from_chain(
+        &curves,
+        globals.picked_color,
+        globals.group_lut_num_points,
+    )?])
+}
 
-            obj_exporter::export_to_file(&set, path).unwrap();
-        }
-        _ => {}
-    }
+fn read_svg_as_groups(
+    path: &PathBuf,
+    storage: &PenStorage,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read_to_string(path)?;
+    Ok(svg_to_groups(&contents, globals.group_lut_num_points)?)
+}
+
+fn read_lottie_as_groups(
+    path: &PathBuf,
+    storage: &PenStorage,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read_to_string(path)?;
+    Ok(lottie_to_groups(&contents, globals.group_lut_num_points)?)
+}
+
+fn read_dxf_as_groups(
+    path: &PathBuf,
+    storage: &PenStorage,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read_to_string(path)?;
+    Ok(dxf_to_groups(&contents, globals.group_lut_num_points)?)
+}
+
+fn read_geojson_as_groups(
+    path: &PathBuf,
+    storage: &PenStorage,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read_to_string(path)?;
+    Ok(geojson_to_groups(
+        &contents,
+        &globals.geojson_projection,
+        globals.group_lut_num_points,
+    )?)
+}
+
+fn read_tiled_as_groups(
+    path: &PathBuf,
+    storage: &PenStorage,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let contents = storage.read_to_string(path)?;
+    Ok(tiled_to_groups(
+        &contents,
+        None,
+        globals.group_lut_num_points,
+    )?)
+}
+
+// fonts and traced images are assets, like the road texture, rather than documents of the
+// storage backend
+fn read_asset(path: &PathBuf, asset_server: &AssetServer) -> Result<Vec<u8>, IoError> {
+    futures_lite::future::block_on(asset_server.asset_io().load_path(path)).map_err(|e| {
+        IoError::File(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            e.to_string(),
+        ))
+    })
+}
+
+fn read_text_as_groups(
+    font: &PathBuf,
+    text: &str,
+    layout: &TextLayout,
+    asset_server: &AssetServer,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let bytes = read_asset(font, asset_server)?;
+    Ok(text_to_groups(
+        &bytes,
+        text,
+        layout,
+        globals.group_lut_num_points,
+    )?)
+}
+
+fn read_image_as_groups(
+    path: &PathBuf,
+    options: &TraceOptions,
+    asset_server: &AssetServer,
+    globals: &Globals,
+) -> Result<Vec<GroupSaveLoad>, IoError> {
+    let extension = path.extension().map_or(String::new(), |extension| {
+        extension.to_string_lossy().to_string()
+    });
+    let image = Image::from_buffer(
+        &read_asset(path, asset_server)?,
+        ImageType::Extension(&extension),
+        CompressedImageFormats::NONE,
+        true,
+    )
+    .map_err(|e| ConversionError::Syntax(e.to_string()))?;
+    Ok(trace_image(&image, options, globals.group_lut_num_points)?)
+}
+
+/// Writes the mesh as an `.obj` file, and its material as an `.mtl` file next to it.
+pub fn save_mesh(
+    mesh: &Mesh,
+    name: &str,
+    material: &ObjMaterial,
+    path: &PathBuf,
+    storage: &mut PenStorage,
+) -> Result<(), IoError> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+
+    storage.write(path, &write_obj(mesh, name, &mtl_name, material)?)?;
+    storage.write(&mtl_path, write_mtl(material).as_bytes())?;
+    Ok(())
 }
 
 // use obj::{Geometry, ObjSet, Object, Primitive, Shape, TVertex, Vertex};
@@ -1,14 +1,26 @@
 pub mod actions;
+pub mod camera;
+pub mod dialogs;
+pub mod drop;
+pub mod guides;
 pub mod io;
 pub mod moves;
 pub mod pen;
 pub mod plugin;
+pub mod reference;
+pub mod storage;
 pub mod undo;
 
 pub use actions::*;
 pub use bevy_pen_tool_model::*;
+pub use camera::*;
+pub use dialogs::*;
+pub use drop::*;
+pub use guides::*;
 pub use io::*;
 pub use moves::*;
 pub use pen::*;
 pub use plugin::*;
+pub use reference::*;
+pub use storage::*;
 pub use undo::*;
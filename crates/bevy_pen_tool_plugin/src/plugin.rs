@@ -1,7 +1,12 @@
 use crate::actions::*;
-use crate::io::{load, load_mesh, save};
+use crate::camera::CameraPlugin;
+use crate::dialogs::DialogPlugin;
+use crate::drop::import_dropped_files;
+use crate::guides::GuidePlugin;
+use crate::io::IoPlugin;
 use crate::moves::*;
 use crate::pen::*;
+use crate::reference::ReferencePlugin;
 use crate::undo::*;
 
 use bevy::prelude::*;
@@ -38,8 +43,14 @@ pub struct BevyPenToolPlugin;
 impl Plugin for BevyPenToolPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(PenApiPlugin)
+            .add_plugin(IoPlugin)
+            .add_plugin(DialogPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(ReferencePlugin)
+            .add_plugin(GuidePlugin)
             .add_plugin(ObjPlugin)
             .add_plugin(SpawnerPlugin)
+            .add_plugin(PenAssetsPlugin)
             .add_event::<RemoveMovingQuadEvent>()
             .add_event::<GroupBoxEvent>()
             .add_event::<SpawningCurve>()
@@ -53,7 +64,7 @@ impl Plugin for BevyPenToolPlugin {
             .add_system(check_mouse_on_meshes)
             .add_system(unlatchy)
             .add_system(compute_group_lut)
-            .add_system(load_mesh)
+            .add_system(import_dropped_files)
             //
             // Update model
             .add_system_set(
@@ -76,12 +87,6 @@ impl Plugin for BevyPenToolPlugin {
                     .with_system(update_anchors.exclusive_system().at_end())
                     .label("model"),
             )
-            .add_system_set(
-                SystemSet::on_update("ModelViewController")
-                    .with_system(load)
-                    .with_system(save)
-                    .after("model"),
-            )
             //
             // Update view
             .add_system_set(
@@ -170,7 +175,7 @@ impl BezierPrint {
             color: None,
             do_compute_lut: false,
             id: bezier.id,
-            latches: bezier.latches.clone(),
+            latches: bezier.latches.0.clone(),
             potential_latch: None,
             grouped: false,
         }
@@ -194,9 +199,9 @@ impl GroupPrint {
     #[allow(dead_code)]
     pub fn from_group(group: &Group) -> Self {
         Self {
-            group: group.group.clone(),
-            bezier_handles: group.bezier_handles.clone(),
-            ends: group.ends.clone(),
+            group: group.group.0.clone(),
+            bezier_handles: group.bezier_handles.0.clone(),
+            ends: group.ends.0.clone(),
         }
     }
 }
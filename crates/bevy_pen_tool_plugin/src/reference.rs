@@ -0,0 +1,224 @@
+//! Reference images drawn under the curves. Game code, tests and tools send
+//! [`ReferenceRequest`] events to show, change and remove them. Unlocked images are moved by
+//! dragging them from an empty spot of the canvas, and locked ones ignore the mouse. The image
+//! under the cursor, locked or not, is scaled, rotated, faded and locked with the reference
+//! [`Action`]s.
+
+use crate::guides::DraggedGuide;
+
+use bevy::prelude::*;
+use bevy_pen_tool_model::inputs::{Action, Cursor};
+use bevy_pen_tool_model::mesh::StartMovingMesh;
+use bevy_pen_tool_model::model::{Globals, ReferenceImage};
+
+/// Factor applied to the scale of a reference image by [`Action::ScaleReferenceUp`].
+pub const REFERENCE_SCALE_STEP: f32 = 1.1;
+/// Rotation of a reference image by [`Action::RotateReferenceLeft`], in radians.
+pub const REFERENCE_ROTATION_STEP: f32 = std::f32::consts::PI / 12.0;
+/// Opacity added to a reference image by [`Action::ReferenceOpacityUp`].
+pub const REFERENCE_OPACITY_STEP: f32 = 0.1;
+
+/// A request to show, change or remove a reference image.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferenceRequest {
+    /// Show the image, or replace the image that has the same id.
+    Set(ReferenceImage),
+    /// Remove the image with this id.
+    Remove(u64),
+}
+
+#[derive(Component)]
+pub struct StartMovingReference {
+    pub start_position: Vec2,
+}
+
+pub(crate) struct ReferencePlugin;
+
+impl Plugin for ReferencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReferenceRequest>()
+            .add_system(edit_reference_images.before(set_reference_images))
+            .add_system(set_reference_images)
+            .add_system(update_reference_sprites.after(set_reference_images))
+            .add_system(move_reference_images)
+            .add_system(release_reference_images)
+            // meshes take the click first, once their StartMovingMesh has been inserted
+            .add_system_to_stage(CoreStage::PostUpdate, pick_reference_images);
+    }
+}
+
+pub fn set_reference_images(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ReferenceImage, &mut Handle<Image>)>,
+    asset_server: Res<AssetServer>,
+    globals: Res<Globals>,
+    mut requests: EventReader<ReferenceRequest>,
+) {
+    // despawning is deferred, so removed images must not be found again by a later request
+    let mut removed = Vec::new();
+    for request in requests.iter() {
+        match request {
+            ReferenceRequest::Set(reference) => {
+                let existing = query.iter_mut().find(|(entity, image, _)| {
+                    image.id == reference.id && !removed.contains(entity)
+                });
+
+                if let Some((_, mut image, mut texture)) = existing {
+                    if image.path != reference.path {
+                        *texture = asset_server.load(reference.path.clone());
+                    }
+                    *image = reference.clone();
+                } else {
+                    commands
+                        .spawn_bundle(SpriteBundle {
+                            texture: asset_server.load(reference.path.clone()),
+                            transform: reference_transform(reference, &globals),
+                            ..Default::default()
+                        })
+                        .insert(reference.clone());
+                }
+            }
+            ReferenceRequest::Remove(id) => {
+                for (entity, image, _) in query.iter() {
+                    if image.id == *id && !removed.contains(&entity) {
+                        commands.entity(entity).despawn();
+                        removed.push(entity);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The reference image as changed by one of the reference [`Action`]s, or `None` for the other
+/// actions. Locking is the only change made to a locked image.
+pub fn edit_reference(reference: &ReferenceImage, action: &Action) -> Option<ReferenceImage> {
+    let mut edited = reference.clone();
+    match action {
+        Action::ToggleReferenceLock => edited.locked = !reference.locked,
+        _ if reference.locked => return None,
+        Action::ScaleReferenceUp => edited.scale *= REFERENCE_SCALE_STEP,
+        Action::ScaleReferenceDown => edited.scale /= REFERENCE_SCALE_STEP,
+        Action::RotateReferenceLeft => edited.rotation += REFERENCE_ROTATION_STEP,
+        Action::RotateReferenceRight => edited.rotation -= REFERENCE_ROTATION_STEP,
+        Action::ReferenceOpacityUp => {
+            edited.opacity = (reference.opacity + REFERENCE_OPACITY_STEP).min(1.0)
+        }
+        Action::ReferenceOpacityDown => {
+            edited.opacity = (reference.opacity - REFERENCE_OPACITY_STEP).max(0.0)
+        }
+        _ => return None,
+    }
+    Some(edited)
+}
+
+/// Applies the reference [`Action`]s to the topmost image under the cursor, through
+/// [`ReferenceRequest::Set`].
+pub fn edit_reference_images(
+    cursor: Res<Cursor>,
+    query: Query<(&ReferenceImage, &Handle<Image>)>,
+    images: Res<Assets<Image>>,
+    mut actions: EventReader<Action>,
+    mut requests: EventWriter<ReferenceRequest>,
+) {
+    for action in actions.iter() {
+        // overlapping images share the same depth, the last one found is picked, like a click
+        let hovered = query
+            .iter()
+            .filter(|(reference, texture)| {
+                images.get(texture).map_or(false, |image| {
+                    reference.contains(cursor.position, image.size())
+                })
+            })
+            .last();
+
+        if let Some(edited) = hovered.and_then(|(reference, _)| edit_reference(reference, action)) {
+            requests.send(ReferenceRequest::Set(edited));
+        }
+    }
+}
+
+// the canvas is drawn at Globals::scale, like the curves
+fn reference_transform(reference: &ReferenceImage, globals: &Globals) -> Transform {
+    let scale = reference.scale * globals.scale;
+    Transform {
+        translation: (reference.position * globals.scale).extend(globals.z_pos.reference),
+        rotation: Quat::from_rotation_z(reference.rotation),
+        scale: Vec3::new(scale, scale, 1.0),
+    }
+}
+
+pub fn update_reference_sprites(
+    mut query: Query<(
+        &ReferenceImage,
+        ChangeTrackers<ReferenceImage>,
+        &mut Transform,
+        &mut Sprite,
+    )>,
+    globals: Res<Globals>,
+) {
+    for (reference, tracker, mut transform, mut sprite) in query.iter_mut() {
+        if globals.is_changed() || tracker.is_changed() {
+            *transform = reference_transform(reference, &globals);
+            sprite.color.set_a(reference.opacity);
+        }
+    }
+}
+
+/// A click on an empty spot of the canvas, which unselects the curves, also picks the topmost
+/// unlocked reference image under the cursor, unless it grabbed a guide.
+pub fn pick_reference_images(
+    mut commands: Commands,
+    cursor: Res<Cursor>,
+    query: Query<(Entity, &ReferenceImage, &Handle<Image>)>,
+    moving_mesh_query: Query<(), With<StartMovingMesh>>,
+    dragged_guide: Res<DraggedGuide>,
+    images: Res<Assets<Image>>,
+    mut actions: EventReader<Action>,
+) {
+    let clicked_canvas = actions.iter().any(|action| action == &Action::Unselect);
+    if !clicked_canvas || !moving_mesh_query.is_empty() || dragged_guide.0.is_some() {
+        return;
+    }
+
+    // overlapping images share the same depth, the last one found is picked
+    let picked = query
+        .iter()
+        .filter(|(_, reference, _)| !reference.locked)
+        .filter(|(_, reference, texture)| {
+            images.get(texture).map_or(false, |image| {
+                reference.contains(cursor.position, image.size())
+            })
+        })
+        .last();
+
+    if let Some((entity, reference, _)) = picked {
+        commands.entity(entity).insert(StartMovingReference {
+            start_position: reference.position,
+        });
+    }
+}
+
+pub fn move_reference_images(
+    cursor: Res<Cursor>,
+    mut query: Query<(&mut ReferenceImage, &StartMovingReference)>,
+) {
+    for (mut reference, start_move) in query.iter_mut() {
+        // the image may have been locked during the drag
+        if !reference.locked {
+            reference.position = start_move.start_position + cursor.pos_relative_to_click;
+        }
+    }
+}
+
+pub fn release_reference_images(
+    mut commands: Commands,
+    mouse_button_input: Res<Input<MouseButton>>,
+    query: Query<Entity, With<StartMovingReference>>,
+) {
+    if mouse_button_input.just_released(MouseButton::Left) {
+        for entity in query.iter() {
+            commands.entity(entity).remove::<StartMovingReference>();
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Storage backends used by every save, load and export in [`crate::io`].
+//!
+//! Paths given to a backend are logical: `groups/track.group` is resolved against the root
+//! of a [`FileSystemStorage`], and is simply a key for an [`InMemoryStorage`]. Absolute paths,
+//! which file dialogs pick, leave the root of a [`FileSystemStorage`] aside. The backend is
+//! chosen by inserting a [`PenStorage`] resource before adding the plugin, otherwise files go
+//! to `./saved`.
+
+use std::collections::HashMap;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+pub trait StorageBackend: Send + Sync + 'static {
+    /// Lists the files directly inside a folder.
+    fn list(&self, folder: &Path) -> io::Result<Vec<PathBuf>>;
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&mut self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn delete(&mut self, path: &Path) -> io::Result<()>;
+
+    fn exists(&self, path: &Path) -> bool {
+        let folder = path.parent().unwrap_or_else(|| Path::new(""));
+        self.list(folder)
+            .map_or(false, |paths| paths.iter().any(|listed| listed == path))
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Reads and writes files under a root directory. Absolute paths, such as the ones picked in
+/// a file dialog, are used as they are and can point outside of the root.
+pub struct FileSystemStorage {
+    pub root: PathBuf,
+}
+
+impl FileSystemStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The file a path points to, under the root unless the path is absolute.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        }
+    }
+}
+
+// a relative root follows the working directory, and failing to read it is reported by the
+// file operations rather than when the storage is created
+impl Default for FileSystemStorage {
+    fn default() -> Self {
+        Self::new("saved")
+    }
+}
+
+impl StorageBackend for FileSystemStorage {
+    fn list(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(self.resolve(folder))? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                paths.push(folder.join(entry.file_name()));
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.resolve(path))
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let path = self.resolve(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)
+    }
+
+    fn delete(&mut self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(self.resolve(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.resolve(path).is_file()
+    }
+}
+
+/// Keeps every file in memory. Useful for tests and tools that should not touch the disk.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    pub files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl StorageBackend for InMemoryStorage {
+    fn list(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut paths = self
+            .files
+            .keys()
+            .filter(|path| path.parent() == Some(folder))
+            .cloned()
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files.insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &Path) -> io::Result<()> {
+        self.files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} is not in storage", path.display()),
+    )
+}
+
+/// The storage backend used by the plugin.
+pub struct PenStorage(pub Box<dyn StorageBackend>);
+
+impl PenStorage {
+    pub fn new(backend: impl StorageBackend) -> Self {
+        Self(Box::new(backend))
+    }
+
+    pub fn file_system(root: impl Into<PathBuf>) -> Self {
+        Self::new(FileSystemStorage::new(root))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::new(InMemoryStorage::default())
+    }
+}
+
+impl Default for PenStorage {
+    fn default() -> Self {
+        Self::new(FileSystemStorage::default())
+    }
+}
+
+impl Deref for PenStorage {
+    type Target = dyn StorageBackend;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl DerefMut for PenStorage {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut()
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy_pen_tool_plugin::*;
+
+fn assert_close(a: Vec2, b: Vec2) {
+    assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn converts_the_cursor_at_every_zoom_level() {
+    let window_size = Vec2::new(800.0, 600.0);
+    let camera = Transform::from_xyz(100.0, -50.0, 999.0);
+
+    // the center of the window is under the camera
+    let center = cursor_to_canvas(Vec2::new(400.0, 300.0), window_size, &camera, 3.0, 1.0);
+    assert_close(center, Vec2::new(100.0, -50.0));
+
+    // the lower left corner is half a window away, times the zoom
+    for zoom in [0.5, 1.0, 4.0] {
+        let corner = cursor_to_canvas(Vec2::ZERO, window_size, &camera, zoom, 1.0);
+        assert_close(
+            corner,
+            Vec2::new(100.0 - 400.0 * zoom, -50.0 - 300.0 * zoom),
+        );
+    }
+
+    // canvas coordinates are world ones divided by the global scale
+    let corner = cursor_to_canvas(Vec2::ZERO, window_size, &camera, 2.0, 0.5);
+    assert_close(corner, Vec2::new(-1400.0, -1300.0));
+
+    // a rotated camera turns the screen offset with it
+    let rotated = Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+    let right = cursor_to_canvas(Vec2::new(500.0, 300.0), window_size, &rotated, 1.0, 1.0);
+    assert_close(right, Vec2::new(0.0, 100.0));
+}
+
+#[test]
+fn frames_boxes_with_a_margin() {
+    let camera = PenCamera::default();
+    let window_size = Vec2::new(1000.0, 500.0);
+
+    // the box is twice as wide as it is tall, like the window, and fills 80% of it
+    let (center, scale) = frame_box(
+        (Vec2::new(-100.0, 0.0), Vec2::new(300.0, 200.0)),
+        window_size,
+        &camera,
+    );
+    assert_close(center, Vec2::new(100.0, 100.0));
+    assert!((scale - 0.5).abs() < 1e-5);
+
+    // a tall box is fitted by its height
+    let (_, scale) = frame_box((Vec2::ZERO, Vec2::new(10.0, 800.0)), window_size, &camera);
+    assert!((scale - 2.0).abs() < 1e-5);
+
+    // a single point is framed at the closest zoom allowed
+    let (center, scale) = frame_box((Vec2::ONE, Vec2::ONE), window_size, &camera);
+    assert_close(center, Vec2::ONE);
+    assert_eq!(scale, camera.min_scale);
+}
+
+#[test]
+fn scales_touchpad_scrolls_down_to_notches() {
+    let line = MouseWheel {
+        unit: MouseScrollUnit::Line,
+        x: 0.0,
+        y: -1.0,
+    };
+    assert_eq!(wheel_notches(&line), -1.0);
+
+    // a touchpad sends many small pixel scrolls, which add up to a notch
+    let pixels = MouseWheel {
+        unit: MouseScrollUnit::Pixel,
+        x: 0.0,
+        y: 5.0,
+    };
+    let notches = (0..10).map(|_| wheel_notches(&pixels)).sum::<f32>();
+    assert!((notches - 1.0).abs() < 1e-5);
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_pen_tool_plugin::*;
+
+use std::path::PathBuf;
+
+// the requests sent once the given dialogs close
+fn requests_after(closed: Vec<FileDialogClosed>) -> Vec<IoRequest> {
+    let mut app = App::new();
+    app.add_event::<FileDialogClosed>()
+        .add_event::<IoRequest>()
+        .insert_resource(PenStorage::in_memory())
+        .add_system(send_dialog_requests);
+
+    let mut events = app.world.resource_mut::<Events<FileDialogClosed>>();
+    for closed in closed {
+        events.send(closed);
+    }
+    app.update();
+
+    let requests = app.world.resource::<Events<IoRequest>>();
+    let mut reader = requests.get_reader();
+    reader.iter(requests).cloned().collect()
+}
+
+#[test]
+fn saves_and_loads_projects_from_dialogs() {
+    let path = PathBuf::from("saved/projects/my_project0.pen");
+    let requests = requests_after(vec![
+        FileDialogClosed {
+            purpose: DialogPurpose::SaveProject,
+            path: Some(path.clone()),
+        },
+        FileDialogClosed {
+            purpose: DialogPurpose::LoadProject,
+            path: Some(path.clone()),
+        },
+    ]);
+
+    assert_eq!(
+        requests,
+        vec![
+            IoRequest::SaveProject { path: path.clone() },
+            IoRequest::LoadProject { path },
+        ]
+    );
+}
+
+#[test]
+fn ignores_cancelled_dialogs() {
+    let requests = requests_after(vec![
+        FileDialogClosed {
+            purpose: DialogPurpose::SaveProject,
+            path: None,
+        },
+        FileDialogClosed {
+            purpose: DialogPurpose::LoadProject,
+            path: None,
+        },
+    ]);
+
+    assert!(requests.is_empty());
+}
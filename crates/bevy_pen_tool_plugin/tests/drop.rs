@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use bevy_pen_tool_plugin::*;
+
+use std::path::{Path, PathBuf};
+
+#[test]
+fn routes_dropped_files_by_extension() {
+    let storage = InMemoryStorage::default();
+    let position = Vec2::new(10.0, -20.0);
+
+    match dropped_file_request(&storage, PathBuf::from("maps/level.TMX"), position) {
+        Some(IoRequest::ImportTiled { path, position: at }) => {
+            assert_eq!(path, PathBuf::from("maps/level.TMX"));
+            assert_eq!(at, position);
+        }
+        other => panic!("a Tiled map should be imported, not {:?}", other),
+    }
+    assert!(matches!(
+        dropped_file_request(&storage, PathBuf::from("shapes.svg"), position),
+        Some(IoRequest::ImportSvg { .. })
+    ));
+    assert!(matches!(
+        dropped_file_request(&storage, PathBuf::from("track.lut"), position),
+        Some(IoRequest::ImportLut { .. })
+    ));
+
+    // a project replaces the canvas rather than being placed under the cursor
+    assert_eq!(
+        dropped_file_request(&storage, PathBuf::from("levels/level1.pen"), position),
+        Some(IoRequest::LoadProject {
+            path: PathBuf::from("levels/level1.pen")
+        })
+    );
+}
+
+#[test]
+fn rejects_unsupported_files() {
+    let storage = InMemoryStorage::default();
+    for path in ["notes.txt", "Makefile", ".svg", "archive.svg.gz"] {
+        assert!(
+            dropped_file_request(&storage, PathBuf::from(path), Vec2::ZERO).is_none(),
+            "{} should not be imported",
+            path
+        );
+    }
+}
+
+#[test]
+fn routes_json_files_by_content() {
+    let mut storage = InMemoryStorage::default();
+    let files = [
+        ("intro.json", "{\"v\": \"5.7.4\", \"layers\": []}"),
+        (
+            "roads.json",
+            "{\"type\": \"FeatureCollection\", \"features\": []}",
+        ),
+        ("track.json", "{\"version\": 3, \"data\": []}"),
+        ("legacy.json", "[]"),
+        (
+            "track_lut.json",
+            "{\"version\": 3, \"data\": {\"path_length\": 1.0, \"lut\": []}}",
+        ),
+        ("settings.json", "{\"volume\": 0.5}"),
+        ("broken.json", "{\"v\": "),
+    ];
+    for (name, contents) in files {
+        storage.write(Path::new(name), contents.as_bytes()).unwrap();
+    }
+    let request = |name: &str| dropped_file_request(&storage, PathBuf::from(name), Vec2::ZERO);
+
+    assert!(matches!(
+        request("intro.json"),
+        Some(IoRequest::ImportLottie { .. })
+    ));
+    assert!(matches!(
+        request("roads.json"),
+        Some(IoRequest::ImportGeoJson { .. })
+    ));
+    assert!(matches!(
+        request("track.json"),
+        Some(IoRequest::ImportGroup { .. })
+    ));
+    assert!(matches!(
+        request("legacy.json"),
+        Some(IoRequest::ImportGroup { .. })
+    ));
+    assert!(matches!(
+        request("track_lut.json"),
+        Some(IoRequest::ImportLut { .. })
+    ));
+
+    // unknown, malformed and missing files are not imported
+    for name in ["settings.json", "broken.json", "missing.json"] {
+        assert!(request(name).is_none(), "{} should not be imported", name);
+    }
+}
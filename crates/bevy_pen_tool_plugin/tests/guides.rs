@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use bevy_pen_tool_plugin::*;
+
+fn view(pixel: f32) -> CanvasView {
+    CanvasView {
+        min: Vec2::new(-100.0, -50.0),
+        max: Vec2::new(100.0, 50.0),
+        pixel,
+    }
+}
+
+#[test]
+fn lists_the_grid_lines_in_view() {
+    let grid = Grid {
+        visible: true,
+        ..Default::default()
+    };
+
+    // a line every 25 units, with a brighter one every 100 units
+    let lines = grid_lines(&grid, &view(1.0));
+    assert_eq!(lines.len(), 9 + 5);
+    let main_color = lines[0].2;
+    let main_lines = lines
+        .iter()
+        .filter(|(_, _, color)| *color == main_color)
+        .count();
+    assert_eq!(main_lines, 3 + 1);
+
+    // lines cross the whole view, one pixel thick
+    let (position, size, _) = lines[0];
+    assert_eq!(position, Vec2::new(-100.0, 0.0));
+    assert_eq!(size, Vec2::new(1.0, 100.0));
+
+    // zoomed out, only the main lines are left, and then none
+    assert_eq!(grid_lines(&grid, &view(10.0)).len(), 3 + 1);
+    assert!(grid_lines(&grid, &view(30.0)).is_empty());
+
+    assert!(grid_lines(&Grid::default(), &view(1.0)).is_empty());
+}
@@ -0,0 +1,104 @@
+use bevy::asset::AssetPlugin;
+use bevy::prelude::*;
+use bevy_pen_tool_plugin::*;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn square() -> Vec<BezierPositions> {
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(100.0, 100.0),
+        Vec2::new(0.0, 100.0),
+    ];
+    (0..4)
+        .map(|k| {
+            let (start, end) = (corners[k], corners[(k + 1) % 4]);
+            BezierPositions {
+                start,
+                end,
+                control_start: start + (end - start) / 3.0,
+                control_end: end - (end - start) / 3.0,
+            }
+        })
+        .collect()
+}
+
+// the resources that save_groups reads, with the curves of the group in the assets and maps
+fn io_app(offline: OfflineGroup) -> (App, GroupId) {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(AssetPlugin)
+        .add_asset::<Bezier>()
+        .add_asset::<Group>()
+        .add_event::<IoRequest>()
+        .add_event::<IoResponse>()
+        .insert_resource(Globals::default())
+        .insert_resource(PenStorage::in_memory())
+        .add_system(save_groups);
+
+    let OfflineGroup {
+        mut group, curves, ..
+    } = offline;
+    let mut maps = Maps::default();
+    {
+        let mut bezier_assets = app.world.resource_mut::<Assets<Bezier>>();
+        for (entity, handle) in group.group.iter() {
+            let bezier = curves[&handle.id].clone();
+            maps.bezier_map.insert(
+                bezier.id,
+                BezierHandleEntity {
+                    handle: handle.clone(),
+                    entity: *entity,
+                    anchor_entities: HashMap::new(),
+                },
+            );
+            bezier_assets.set_untracked(handle.id, bezier);
+        }
+    }
+
+    // a freshly latched group, whose ends and look-up table are computed in a later frame
+    *group.ends = None;
+    group.lut.clear();
+
+    let group_id = group.id;
+    let handle = app.world.resource_mut::<Assets<Group>>().add(group);
+    maps.group_map.insert(group_id, handle);
+    app.insert_resource(maps);
+
+    (app, group_id)
+}
+
+#[test]
+fn saves_freshly_latched_groups() {
+    let saved = GroupSaveLoad::from_chain(&square(), None, 100).unwrap();
+    let (mut app, group_id) = io_app(OfflineGroup::from_save(&saved));
+
+    let path = PathBuf::from("groups/square.group");
+    app.world
+        .resource_mut::<Events<IoRequest>>()
+        .send(IoRequest::SaveGroup {
+            group_id,
+            path: path.clone(),
+        });
+    app.update();
+
+    let responses = app.world.resource::<Events<IoResponse>>();
+    let mut reader = responses.get_reader();
+    assert!(matches!(
+        reader.iter(responses).next(),
+        Some(IoResponse::Success(_))
+    ));
+
+    let storage = app.world.resource::<PenStorage>();
+    let bytes = storage.read(&path).unwrap();
+    let loaded = Vec::<GroupSaveLoad>::from_versioned_bytes(&bytes).unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].lut.len(), 4);
+    assert!(loaded[0].is_closed());
+    assert!(loaded[0]
+        .lut
+        .iter()
+        .all(|(_, _, _, distances)| !distances.is_empty()));
+}
@@ -0,0 +1,61 @@
+use bevy_pen_tool_plugin::*;
+
+#[test]
+fn edits_reference_images() {
+    let reference = ReferenceImage::new("images/level.png");
+
+    let scaled = edit_reference(&reference, &Action::ScaleReferenceUp).unwrap();
+    assert!((scaled.scale - REFERENCE_SCALE_STEP).abs() < 1e-6);
+    let scaled = edit_reference(&scaled, &Action::ScaleReferenceDown).unwrap();
+    assert!((scaled.scale - 1.0).abs() < 1e-6);
+
+    let rotated = edit_reference(&reference, &Action::RotateReferenceLeft).unwrap();
+    assert!((rotated.rotation - REFERENCE_ROTATION_STEP).abs() < 1e-6);
+    let rotated = edit_reference(&reference, &Action::RotateReferenceRight).unwrap();
+    assert!((rotated.rotation + REFERENCE_ROTATION_STEP).abs() < 1e-6);
+
+    // the opacity stays between 0 and 1
+    let mut faded = reference.clone();
+    for _ in 0..20 {
+        faded = edit_reference(&faded, &Action::ReferenceOpacityDown).unwrap();
+    }
+    assert_eq!(faded.opacity, 0.0);
+    for _ in 0..20 {
+        faded = edit_reference(&faded, &Action::ReferenceOpacityUp).unwrap();
+    }
+    assert_eq!(faded.opacity, 1.0);
+
+    // only the edited property changes
+    assert_eq!(faded.id, reference.id);
+    assert_eq!(faded.scale, reference.scale);
+    assert_eq!(faded.position, reference.position);
+
+    assert!(edit_reference(&reference, &Action::Undo).is_none());
+}
+
+#[test]
+fn only_unlocks_locked_reference_images() {
+    let locked = edit_reference(
+        &ReferenceImage::new("images/level.png"),
+        &Action::ToggleReferenceLock,
+    )
+    .unwrap();
+    assert!(locked.locked);
+
+    for action in [
+        Action::ScaleReferenceUp,
+        Action::RotateReferenceLeft,
+        Action::ReferenceOpacityUp,
+    ] {
+        assert!(edit_reference(&locked, &action).is_none());
+    }
+
+    let unlocked = edit_reference(&locked, &Action::ToggleReferenceLock).unwrap();
+    assert_eq!(
+        unlocked,
+        ReferenceImage {
+            locked: false,
+            ..locked
+        }
+    );
+}
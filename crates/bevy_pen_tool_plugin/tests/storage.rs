@@ -0,0 +1,98 @@
+use bevy_pen_tool_plugin::*;
+
+use std::path::{Path, PathBuf};
+
+// a folder of its own for each test, removed at the end
+fn temp_root(name: &str) -> PathBuf {
+    let mut root = std::env::temp_dir();
+    root.push(format!("bevy_pen_tool_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    root
+}
+
+// every backend has to behave the same way
+fn assert_reads_back_writes(storage: &mut dyn StorageBackend) {
+    let path = Path::new("groups/track.group");
+    assert!(!storage.exists(path));
+    assert!(storage.read(path).is_err());
+
+    storage.write(path, b"first").unwrap();
+    storage.write(path, b"second").unwrap();
+    storage
+        .write(Path::new("groups/track.lut"), b"lut")
+        .unwrap();
+    storage.write(Path::new("meshes/road.obj"), b"obj").unwrap();
+
+    assert!(storage.exists(path));
+    assert_eq!(storage.read_to_string(path).unwrap(), "second");
+    assert_eq!(
+        storage.list(Path::new("groups")).unwrap(),
+        vec![
+            PathBuf::from("groups/track.group"),
+            PathBuf::from("groups/track.lut")
+        ]
+    );
+
+    storage.delete(path).unwrap();
+    assert!(!storage.exists(path));
+    assert!(storage.delete(path).is_err());
+    assert_eq!(storage.list(Path::new("groups")).unwrap().len(), 1);
+}
+
+#[test]
+fn in_memory_storage_reads_back_writes() {
+    let mut storage = InMemoryStorage::default();
+    assert_reads_back_writes(&mut storage);
+
+    // nothing reached the disk
+    assert!(!Path::new("groups/track.lut").exists());
+}
+
+#[test]
+fn file_system_storage_reads_back_writes() {
+    let root = temp_root("storage");
+    let mut storage = FileSystemStorage::new(&root);
+    assert_reads_back_writes(&mut storage);
+
+    assert!(root.join("groups/track.lut").is_file());
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn file_system_storage_keeps_absolute_paths() {
+    let root = temp_root("absolute");
+    let storage = FileSystemStorage::new(&root);
+    assert_eq!(
+        storage.resolve(Path::new("groups/track.group")),
+        root.join("groups/track.group")
+    );
+
+    // a path picked in a dialog is used as it is, outside of the root
+    let picked = std::env::temp_dir().join("track.group");
+    assert!(picked.is_absolute());
+    assert_eq!(storage.resolve(&picked), picked);
+
+    // the default root follows the working directory
+    assert_eq!(FileSystemStorage::default().root, PathBuf::from("saved"));
+}
+
+#[test]
+fn finds_available_names_in_the_storage() {
+    let mut storage = InMemoryStorage::default();
+    let folder = Path::new("groups");
+    assert_eq!(
+        available_name(&storage, folder, "my_group", &["group"]),
+        "my_group0"
+    );
+
+    storage.write(&folder.join("my_group0.group"), b"").unwrap();
+    storage.write(&folder.join("my_group1.lut"), b"").unwrap();
+    assert_eq!(
+        available_name(&storage, folder, "my_group", &["group"]),
+        "my_group1"
+    );
+    assert_eq!(
+        available_name(&storage, folder, "my_group", &["group", "lut"]),
+        "my_group2"
+    );
+}
@@ -0,0 +1,222 @@
+//! Bakes look-up tables into Rust source, for builds that should not read files at runtime.
+//!
+//! The generated items only use `f32` arrays and have no dependencies, so they compile in any
+//! crate. They can be written once from the editor, or generated by a build script:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     let out_file = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("paths.rs");
+//!     for path in bevy_pen_tool_runtime::luts_to_rust("assets/lut", &out_file).unwrap() {
+//!         println!("cargo:rerun-if-changed={}", path.display());
+//!     }
+//!     println!("cargo:rerun-if-changed=assets/lut");
+//! }
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/paths.rs"));
+//! let [x, y] = my_group0::position(0.5);
+//! ```
+
+use crate::group::*;
+use crate::lut::*;
+
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// sampling code shared by every generated module, same as the runtime sampling: the tests
+// compile a generated module and compare the two
+const ACCESSORS: &str = r#"
+/// Position on the path, where `t` goes from 0 at the start to 1 at the end. The speed along
+/// the path is constant.
+pub fn position(t: f32) -> [f32; 2] {
+    if LUT.len() < 2 {
+        return LUT.first().copied().unwrap_or([0.0, 0.0]);
+    }
+    let (idx, rem) = segment(t);
+    let [x0, y0] = LUT[idx];
+    let [x1, y1] = LUT[idx + 1];
+    [x0 + rem * (x1 - x0), y0 + rem * (y1 - y0)]
+}
+
+/// Unit vector pointing in the direction of travel, where `t` goes from 0 to 1.
+pub fn tangent(t: f32) -> [f32; 2] {
+    if LUT.len() < 2 {
+        return [0.0, 0.0];
+    }
+    let (idx, _) = segment(t);
+    let direction = |from: [f32; 2], to: [f32; 2]| [to[0] - from[0], to[1] - from[1]];
+    (idx + 1..LUT.len())
+        .map(|k| direction(LUT[idx], LUT[k]))
+        .chain((0..idx).rev().map(|k| direction(LUT[k], LUT[idx + 1])))
+        .find(|[x, y]| x * x + y * y > f32::EPSILON)
+        .map_or([0.0, 0.0], |[x, y]| {
+            let length = (x * x + y * y).sqrt();
+            [x / length, y / length]
+        })
+}
+
+fn segment(t: f32) -> (usize, f32) {
+    let idx_f32 = t.clamp(0.0, 1.0) * (LUT.len() - 1) as f32;
+    let idx = (idx_f32 as usize).min(LUT.len() - 2);
+    (idx, idx_f32 - idx as f32)
+}
+"#;
+
+/// Writes the items of a module that holds `lut` as `const` arrays, along with `position`
+/// and `tangent` functions. When `curves` are given, they are written as `CURVES`, each one
+/// as `[start, control_start, control_end, end]`.
+pub fn lut_to_rust(lut: &StandaloneLut, curves: Option<&[CurvePositions]>) -> String {
+    let mut source = String::new();
+
+    writeln!(source, "/// Length of the path.").unwrap();
+    writeln!(source, "#[allow(clippy::excessive_precision)]").unwrap();
+    writeln!(
+        source,
+        "pub const PATH_LENGTH: f32 = {};",
+        literal(lut.path_length)
+    )
+    .unwrap();
+    writeln!(source).unwrap();
+
+    writeln!(source, "/// Evenly spaced points along the path.").unwrap();
+    writeln!(source, "#[allow(clippy::excessive_precision)]").unwrap();
+    writeln!(source, "pub const LUT: [[f32; 2]; {}] = [", lut.lut.len()).unwrap();
+    for point in lut.lut.iter() {
+        writeln!(source, "    [{}, {}],", literal(point.x), literal(point.y)).unwrap();
+    }
+    writeln!(source, "];").unwrap();
+
+    if let Some(curves) = curves {
+        writeln!(source).unwrap();
+        writeln!(
+            source,
+            "/// Curves of the path in the order of travel: start, control start, control end, end."
+        )
+        .unwrap();
+        writeln!(source, "#[allow(clippy::excessive_precision)]").unwrap();
+        writeln!(
+            source,
+            "pub const CURVES: [[[f32; 2]; 4]; {}] = [",
+            curves.len()
+        )
+        .unwrap();
+        for curve in curves {
+            let points = [
+                curve.start,
+                curve.control_start,
+                curve.control_end,
+                curve.end,
+            ]
+            .iter()
+            .map(|p| format!("[{}, {}]", literal(p.x), literal(p.y)))
+            .collect::<Vec<String>>();
+            writeln!(source, "    [{}],", points.join(", ")).unwrap();
+        }
+        writeln!(source, "];").unwrap();
+    }
+
+    source.push_str(ACCESSORS);
+    source
+}
+
+/// Converts every `.lut` file of `folder` into a module of `out_file`, named after the file.
+/// Returns the files that were read, so that a build script can ask to be rerun when they
+/// change.
+pub fn luts_to_rust(
+    folder: impl AsRef<Path>,
+    out_file: impl AsRef<Path>,
+) -> io::Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(folder.as_ref())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "lut")
+        })
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    let mut source = String::new();
+    writeln!(
+        source,
+        "// Generated by bevy_pen_tool_runtime, do not edit."
+    )
+    .unwrap();
+
+    for path in paths.iter() {
+        let bytes = std::fs::read(path)?;
+        let lut = StandaloneLut::from_saved_bytes(&bytes).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })?;
+
+        let stem = path
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+
+        writeln!(source).unwrap();
+        writeln!(source, "/// Generated from `{}`.", path.display()).unwrap();
+        writeln!(source, "pub mod {} {{", module_name(&stem)).unwrap();
+        for line in lut_to_rust(&lut, None).lines() {
+            if line.is_empty() {
+                writeln!(source).unwrap();
+            } else {
+                writeln!(source, "    {}", line).unwrap();
+            }
+        }
+        writeln!(source, "}}").unwrap();
+    }
+
+    std::fs::write(out_file, source)?;
+    Ok(paths)
+}
+
+/// Turns a file name into a valid module name: `my track-2` becomes `my_track_2`.
+pub fn module_name(name: &str) -> String {
+    let mut module = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    if module.is_empty() || module.starts_with(|c: char| c.is_ascii_digit()) {
+        module.insert_str(0, "lut_");
+    }
+
+    if KEYWORDS.contains(&module.as_str()) {
+        module.push('_');
+    }
+
+    module
+}
+
+const KEYWORDS: &[&str] = &[
+    "_", "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+// debug formatting gives the shortest literal that reads back as the same f32
+fn literal(value: f32) -> String {
+    if value.is_nan() {
+        "f32::NAN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 {
+            "f32::INFINITY".to_string()
+        } else {
+            "f32::NEG_INFINITY".to_string()
+        }
+    } else {
+        format!("{:?}", value)
+    }
+}
@@ -0,0 +1,150 @@
+//! The encodings of `.lut` and `.group` files. The editor writes json by default, and can
+//! also write RON, which diffs well, or a compact binary encoding that loads fast. Loading
+//! detects the encoding from the first bytes of the file.
+//!
+//! Reading json files needs the `json` feature of this crate, which is on by default, and
+//! reading RON and binary files needs the `ron` and `bincode` features.
+
+use serde::de::DeserializeOwned;
+#[cfg(any(feature = "json", feature = "ron"))]
+use serde::Deserialize;
+#[cfg(feature = "json")]
+use serde_json::Value;
+
+use std::fmt;
+
+/// Written at the start of binary files, followed by the format version as a little-endian
+/// `u32` and the bincode payload.
+pub const BINARY_MAGIC: [u8; 4] = *b"PENB";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveEncoding {
+    /// Pretty-printed json, `{ "version": N, "data": ... }`.
+    Json,
+    /// `(version: N, data: ...)`.
+    Ron,
+    /// [`BINARY_MAGIC`], the version, then bincode.
+    Bincode,
+}
+
+impl Default for SaveEncoding {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+impl SaveEncoding {
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&BINARY_MAGIC) {
+            return Self::Bincode;
+        }
+
+        // json files are objects, or arrays for the oldest .group files
+        match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'{') | Some(b'[') | None => Self::Json,
+            Some(_) => Self::Ron,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SavedFileError {
+    Json(String),
+    Ron(String),
+    Bincode(String),
+    /// The file uses an encoding that this build cannot read.
+    Unsupported(SaveEncoding),
+}
+
+impl fmt::Display for SavedFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "invalid json: {}", e),
+            Self::Ron(reason) => write!(f, "invalid ron: {}", reason),
+            Self::Bincode(reason) => write!(f, "invalid binary file: {}", reason),
+            Self::Unsupported(encoding) => write!(f, "cannot read {:?} files", encoding),
+        }
+    }
+}
+
+impl std::error::Error for SavedFileError {}
+
+// the version is only read from json files, RON files are read as they are
+#[cfg(any(feature = "json", feature = "ron"))]
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+#[derive(Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+/// Splits the `{ "version": N, "data": ... }` header of a json file from its payload. Files
+/// saved before the header existed are version 0.
+#[cfg(feature = "json")]
+pub fn split_header(value: Value) -> Result<(u32, Value), serde_json::Error> {
+    if value.get("version").is_some() && value.get("data").is_some() {
+        let versioned: Versioned<Value> = serde_json::from_value(value)?;
+        Ok((versioned.version, versioned.data))
+    } else {
+        Ok((0, value))
+    }
+}
+
+/// Splits the version of a binary file from its bincode payload, or `None` when the file does
+/// not start with a complete header.
+pub fn split_binary_header(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let header_len = BINARY_MAGIC.len() + 4;
+    if !bytes.starts_with(&BINARY_MAGIC) || bytes.len() < header_len {
+        return None;
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&bytes[BINARY_MAGIC.len()..header_len]);
+    Some((u32::from_le_bytes(version), &bytes[header_len..]))
+}
+
+/// Reads a file written by the editor in any encoding. There is no migration here: only the
+/// fields that kept their meaning across versions are read by the runtime types.
+pub(crate) fn read_saved_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SavedFileError> {
+    match SaveEncoding::detect(bytes) {
+        SaveEncoding::Json => read_json(bytes),
+        SaveEncoding::Ron => read_ron(bytes),
+        SaveEncoding::Bincode => read_bincode(bytes),
+    }
+}
+
+#[cfg(feature = "json")]
+fn read_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SavedFileError> {
+    let json_error = |e: serde_json::Error| SavedFileError::Json(e.to_string());
+    let (_, data) =
+        split_header(serde_json::from_slice(bytes).map_err(json_error)?).map_err(json_error)?;
+    serde_json::from_value(data).map_err(json_error)
+}
+
+#[cfg(not(feature = "json"))]
+fn read_json<T: DeserializeOwned>(_bytes: &[u8]) -> Result<T, SavedFileError> {
+    Err(SavedFileError::Unsupported(SaveEncoding::Json))
+}
+
+#[cfg(feature = "ron")]
+fn read_ron<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SavedFileError> {
+    ron::de::from_bytes::<Versioned<T>>(bytes)
+        .map(|versioned| versioned.data)
+        .map_err(|e| SavedFileError::Ron(e.to_string()))
+}
+
+#[cfg(not(feature = "ron"))]
+fn read_ron<T: DeserializeOwned>(_bytes: &[u8]) -> Result<T, SavedFileError> {
+    Err(SavedFileError::Unsupported(SaveEncoding::Ron))
+}
+
+#[cfg(feature = "bincode")]
+fn read_bincode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SavedFileError> {
+    let (_, payload) = split_binary_header(bytes)
+        .ok_or_else(|| SavedFileError::Bincode("the header is cut short".to_string()))?;
+    bincode::deserialize(payload).map_err(|e| SavedFileError::Bincode(e.to_string()))
+}
+
+#[cfg(not(feature = "bincode"))]
+fn read_bincode<T: DeserializeOwned>(_bytes: &[u8]) -> Result<T, SavedFileError> {
+    Err(SavedFileError::Unsupported(SaveEncoding::Bincode))
+}
@@ -0,0 +1,60 @@
+use crate::lut::*;
+
+use bevy::prelude::*;
+
+/// Moves the entity along a look-up table at constant speed.
+#[derive(Component, Debug, Clone)]
+pub struct FollowPath {
+    pub lut: Handle<StandaloneLut>,
+    /// Distance travelled per second, in the units of the look-up table.
+    pub speed: f32,
+    /// Current position on the path, from 0 at the start to 1 at the end.
+    pub t: f32,
+    /// Start over once the end is reached, instead of stopping there.
+    pub looping: bool,
+    /// Turn the entity so that its x axis points in the direction of travel.
+    pub rotate: bool,
+}
+
+impl FollowPath {
+    pub fn new(lut: Handle<StandaloneLut>, speed: f32) -> Self {
+        Self {
+            lut,
+            speed,
+            t: 0.0,
+            looping: true,
+            rotate: false,
+        }
+    }
+}
+
+pub fn follow_path(
+    time: Res<Time>,
+    luts: Res<Assets<StandaloneLut>>,
+    mut query: Query<(&mut FollowPath, &mut Transform)>,
+) {
+    for (mut follow, mut transform) in query.iter_mut() {
+        // the look-up table may still be loading
+        let lut = match luts.get(&follow.lut) {
+            Some(lut) if lut.path_length > 0.0 => lut,
+            _ => continue,
+        };
+
+        let mut t = follow.t + follow.speed * time.delta_seconds() / lut.path_length;
+        t = if follow.looping {
+            t.rem_euclid(1.0)
+        } else {
+            t.clamp(0.0, 1.0)
+        };
+        follow.t = t;
+
+        transform.translation = lut.position(t).extend(transform.translation.z);
+
+        if follow.rotate {
+            let tangent = lut.tangent(t);
+            if tangent != Vec2::ZERO {
+                transform.rotation = Quat::from_rotation_z(tangent.y.atan2(tangent.x));
+            }
+        }
+    }
+}
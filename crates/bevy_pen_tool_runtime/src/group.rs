@@ -0,0 +1,87 @@
+//! Read-only view of the `.group` files saved by the editor. Only what is needed to play
+//! back a path is kept: the editor stores a lot more with each curve, which is ignored here.
+
+use crate::encoding::*;
+use crate::lut::*;
+
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+/// Either the start point or the end point of a curve.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum AnchorEdge {
+    Start,
+    End,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CurvePositions {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub control_start: Vec2,
+    pub control_end: Vec2,
+}
+
+impl CurvePositions {
+    /// Position on the cubic curve, `t` going from 0 at the start to 1 at the end. Unlike the
+    /// look-up table, `t` is not proportional to the distance along the curve.
+    pub fn point_at(&self, t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        self.start * s * s * s
+            + self.control_start * 3.0 * s * s * t
+            + self.control_end * 3.0 * s * t * t
+            + self.end * t * t * t
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SavedCurve {
+    pub positions: CurvePositions,
+}
+
+/// A group of latched curves, in the order in which they are traversed. This is the part of
+/// the editor's `GroupSaveLoad` that plays the path back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupPath {
+    // the AnchorEdge is the edge where the traversal leaves the curve, and the t range is the
+    // part of the whole path covered by the curve
+    pub lut: Vec<(SavedCurve, AnchorEdge, (f64, f64), Vec<f64>)>,
+    pub standalone_lut: StandaloneLut,
+}
+
+impl GroupPath {
+    /// Reads every group of a `.group` file saved as json or RON. Binary files store every
+    /// field of the editor's curves, which cannot be skipped, so only the editor reads them.
+    pub fn from_saved_bytes(bytes: &[u8]) -> Result<Vec<Self>, SavedFileError> {
+        match SaveEncoding::detect(bytes) {
+            SaveEncoding::Bincode => Err(SavedFileError::Unsupported(SaveEncoding::Bincode)),
+            _ => read_saved_bytes(bytes),
+        }
+    }
+
+    /// Positions of the curves in the order in which the group is traversed, each one
+    /// pointing in the direction of travel.
+    pub fn path_curves(&self) -> Vec<CurvePositions> {
+        self.lut
+            .iter()
+            .map(|(curve, anchor, _, _)| match anchor {
+                AnchorEdge::End => curve.positions,
+                AnchorEdge::Start => CurvePositions {
+                    start: curve.positions.end,
+                    end: curve.positions.start,
+                    control_start: curve.positions.control_end,
+                    control_end: curve.positions.control_start,
+                },
+            })
+            .collect()
+    }
+
+    pub fn position(&self, t: f32) -> Vec2 {
+        self.standalone_lut.position(t)
+    }
+
+    pub fn tangent(&self, t: f32) -> Vec2 {
+        self.standalone_lut.tangent(t)
+    }
+}
@@ -0,0 +1,36 @@
+//! Everything a game needs to play back the paths drawn with bevy_pen_tool: the look-up
+//! table and group types, their asset loaders, position and tangent sampling, and a
+//! [`FollowPath`] component. Only bevy and serde are pulled in, the editor is not needed.
+//! Paths can also be baked into the binary as Rust source, see [`codegen`], and the followers
+//! of the scenes exported by the editor are set up by the systems of [`scene`].
+
+pub mod codegen;
+pub mod encoding;
+pub mod follow;
+pub mod group;
+pub mod loaders;
+pub mod lut;
+pub mod scene;
+
+pub use codegen::*;
+pub use encoding::*;
+pub use follow::*;
+pub use group::*;
+pub use loaders::*;
+pub use lut::*;
+pub use scene::*;
+
+use bevy::prelude::*;
+
+/// Loads `.lut` and `.group` files, moves the entities that have a [`FollowPath`], and sets
+/// up the followers of scenes exported by the editor.
+pub struct PenRuntimePlugin;
+
+impl Plugin for PenRuntimePlugin {
+    fn build(&self, app: &mut App) {
+        register_scene_types(app);
+        app.add_plugin(PenAssetsPlugin)
+            .add_system(follow_path)
+            .add_system(spawn_scene_followers);
+    }
+}
@@ -0,0 +1,75 @@
+//! Asset loaders for `.group` and `.lut` files, so that games can load what was drawn in the
+//! editor with `asset_server.load("paths/track.lut")`. With `watch_for_changes` turned on in
+//! the `AssetServerSettings`, saving a file again from the editor replaces the asset in the
+//! running game, and an `AssetEvent::Modified` is sent.
+
+use crate::group::*;
+use crate::lut::*;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+/// Every group saved in a `.group` file.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "7d1e5a90-3c6b-4f28-a4e2-0b9d8c7f1e35"]
+pub struct GroupFile {
+    pub groups: Vec<GroupPath>,
+}
+
+#[derive(Default)]
+pub struct LutLoader;
+
+impl AssetLoader for LutLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let lut = StandaloneLut::from_saved_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(lut));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lut"]
+    }
+}
+
+#[derive(Default)]
+pub struct GroupLoader;
+
+impl AssetLoader for GroupLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let groups = GroupPath::from_saved_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(GroupFile { groups }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["group"]
+    }
+}
+
+/// Registers the [`StandaloneLut`] and [`GroupFile`] assets and their loaders.
+pub struct PenAssetsPlugin;
+
+impl Plugin for PenAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<StandaloneLut>()
+            .add_asset::<GroupFile>()
+            .init_asset_loader::<LutLoader>()
+            .init_asset_loader::<GroupLoader>();
+    }
+}
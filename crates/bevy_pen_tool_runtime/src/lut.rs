@@ -0,0 +1,73 @@
+use crate::encoding::*;
+
+use bevy::{
+    prelude::*,
+    reflect::{FromReflect, TypeUuid},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Evenly spaced points along a group of curves. The distance between two consecutive points
+/// is the same everywhere, so that sampling with a `t` that grows linearly moves at constant
+/// speed along the path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypeUuid, Reflect, FromReflect)]
+#[reflect(PartialEq, Serialize, Deserialize)]
+#[uuid = "2b3c7f4e-91a5-4d0e-b7c1-5f0e8a3d6c21"]
+pub struct StandaloneLut {
+    pub path_length: f32,
+    pub lut: Vec<Vec2>,
+}
+
+impl StandaloneLut {
+    /// Reads a `.lut` file in any of the encodings written by the editor.
+    pub fn from_saved_bytes(bytes: &[u8]) -> Result<Self, SavedFileError> {
+        read_saved_bytes(bytes)
+    }
+
+    /// Position on the path, where `t` goes from 0 at the start to 1 at the end.
+    pub fn position(&self, t: f32) -> Vec2 {
+        compute_position_with_lut(t, &self.lut)
+    }
+
+    /// Unit vector pointing in the direction of travel, where `t` goes from 0 to 1.
+    pub fn tangent(&self, t: f32) -> Vec2 {
+        compute_tangent_with_lut(t, &self.lut)
+    }
+}
+
+/// Interpolates between the two points of the look-up table that surround `t`, which is
+/// clamped between 0 and 1.
+pub fn compute_position_with_lut(t: f32, lut: &[Vec2]) -> Vec2 {
+    match lut.len() {
+        0 => Vec2::ZERO,
+        1 => lut[0],
+        _ => {
+            let (idx, rem) = segment(t, lut.len());
+            lut[idx] + rem * (lut[idx + 1] - lut[idx])
+        }
+    }
+}
+
+/// Direction of the segment of the look-up table that holds `t`. Repeated points are
+/// skipped, and a path with no length has no tangent.
+pub fn compute_tangent_with_lut(t: f32, lut: &[Vec2]) -> Vec2 {
+    if lut.len() < 2 {
+        return Vec2::ZERO;
+    }
+
+    let (idx, _) = segment(t, lut.len());
+
+    // look forward first, then backward, for two points that are not on top of each other
+    (idx + 1..lut.len())
+        .map(|k| lut[k] - lut[idx])
+        .chain((0..idx).rev().map(|k| lut[idx + 1] - lut[k]))
+        .find(|direction| direction.length_squared() > f32::EPSILON)
+        .map_or(Vec2::ZERO, |direction| direction.normalize())
+}
+
+// index of the first point of the segment that holds t, and the position of t in it
+fn segment(t: f32, len: usize) -> (usize, f32) {
+    let idx_f32 = t.clamp(0.0, 1.0) * (len - 1) as f32;
+    let idx = (idx_f32 as usize).min(len - 2);
+    (idx, idx_f32 - idx as f32)
+}
@@ -0,0 +1,88 @@
+//! Components of the `.scn.ron` scenes exported by the editor. A scene holds the fill and road
+//! meshes as plain vertex data, and the path followers with their look-up tables, so that it
+//! only references types of this crate. Once the scene is spawned, [`spawn_scene_followers`]
+//! sets up a [`FollowPath`] for every follower. Turning the mesh data into 2d meshes needs the
+//! renderer, and is done by the `PenSceneMeshPlugin` of `bevy_pen_tool_model`.
+
+use crate::follow::*;
+use crate::lut::*;
+
+use bevy::prelude::*;
+
+pub type MeshId = u64;
+
+/// A fill or road mesh made by the editor.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct PenMesh {
+    pub id: MeshId,
+    /// Corners of the mesh, relative to its center of mass.
+    pub bounding_box: (Vec2, Vec2),
+}
+
+/// Vertices of a triangle list, in the local space of the entity.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct PenMeshData {
+    pub positions: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub indices: Vec<u32>,
+}
+
+/// Material of a fill mesh.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct PenFill {
+    /// Linear RGBA color.
+    pub color: Vec4,
+}
+
+/// Material of a road mesh.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct PenRoad {
+    /// Asset path of the texture that repeats along the road.
+    pub texture: String,
+}
+
+/// An entity that moves along a path, whose look-up table is kept in the scene.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct PenFollower {
+    pub lut: Vec<Vec2>,
+    pub path_length: f32,
+    /// Distance travelled per second.
+    pub speed: f32,
+    pub looping: bool,
+    pub rotate: bool,
+}
+
+/// Registers the components of exported scenes, which is needed to load them.
+pub fn register_scene_types(app: &mut App) {
+    app.register_type::<PenMesh>()
+        .register_type::<PenMeshData>()
+        .register_type::<PenFill>()
+        .register_type::<PenRoad>()
+        .register_type::<PenFollower>();
+}
+
+/// Adds the look-up table of every new follower to the assets, and makes it follow the path.
+pub fn spawn_scene_followers(
+    mut commands: Commands,
+    query: Query<(Entity, &PenFollower), Added<PenFollower>>,
+    mut luts: ResMut<Assets<StandaloneLut>>,
+) {
+    for (entity, follower) in query.iter() {
+        let lut = luts.add(StandaloneLut {
+            path_length: follower.path_length,
+            lut: follower.lut.clone(),
+        });
+        commands.entity(entity).insert(FollowPath {
+            lut,
+            speed: follower.speed,
+            t: 0.0,
+            looping: follower.looping,
+            rotate: follower.rotate,
+        });
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_pen_tool_runtime::*;
+
+use std::path::PathBuf;
+
+// the fixture is the output of lut_to_rust for the path below, compiled into the test so that
+// the generated sampling can be checked against the runtime
+mod generated {
+    include!("fixtures/generated.rs");
+}
+
+// a repeated point, so that tangents have to skip it
+fn lut() -> StandaloneLut {
+    StandaloneLut {
+        path_length: 32.75,
+        lut: vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.5),
+            Vec2::new(-2.25, 10.5),
+        ],
+    }
+}
+
+fn curves() -> Vec<CurvePositions> {
+    vec![CurvePositions {
+        start: Vec2::new(0.0, 0.0),
+        control_start: Vec2::new(5.0, 0.0),
+        control_end: Vec2::new(10.0, 5.0),
+        end: Vec2::new(10.0, 10.5),
+    }]
+}
+
+fn assert_near(a: [f32; 2], b: Vec2) {
+    assert!(Vec2::from(a).distance(b) < 1e-5, "{:?} is not {}", a, b);
+}
+
+#[test]
+fn generates_the_fixture() {
+    assert_eq!(
+        lut_to_rust(&lut(), Some(&curves())),
+        include_str!("fixtures/generated.rs")
+    );
+}
+
+#[test]
+fn generated_code_samples_like_the_runtime() {
+    let lut = lut();
+    assert_eq!(generated::PATH_LENGTH, lut.path_length);
+    assert_eq!(
+        generated::LUT
+            .iter()
+            .map(|p| Vec2::from(*p))
+            .collect::<Vec<Vec2>>(),
+        lut.lut
+    );
+
+    let curve = curves()[0];
+    assert_eq!(
+        generated::CURVES[0].map(Vec2::from),
+        [
+            curve.start,
+            curve.control_start,
+            curve.control_end,
+            curve.end
+        ]
+    );
+
+    for k in -10..=110 {
+        let t = k as f32 / 100.0;
+        assert_near(generated::position(t), lut.position(t));
+        assert_near(generated::tangent(t), lut.tangent(t));
+    }
+}
+
+// the .lut files are written as json
+#[cfg(feature = "json")]
+#[test]
+fn converts_folders_of_look_up_tables() {
+    let mut folder = std::env::temp_dir();
+    folder.push(format!("bevy_pen_tool_codegen_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+
+    let contents = serde_json::to_string(&lut()).unwrap();
+    std::fs::write(folder.join("my track-2.lut"), &contents).unwrap();
+    std::fs::write(folder.join("fn.lut"), &contents).unwrap();
+    std::fs::write(folder.join("notes.txt"), "not a path").unwrap();
+
+    let out_file = folder.join("paths.rs");
+    let paths = luts_to_rust(&folder, &out_file).unwrap();
+    assert_eq!(
+        paths,
+        vec![folder.join("fn.lut"), folder.join("my track-2.lut")]
+    );
+
+    let source = std::fs::read_to_string(&out_file).unwrap();
+    assert!(source.starts_with("// Generated by bevy_pen_tool_runtime, do not edit."));
+    assert!(source.contains("pub mod fn_ {"));
+    assert!(source.contains("pub mod my_track_2 {"));
+    assert!(source.contains("    pub const LUT: [[f32; 2]; 5] = ["));
+    assert!(!source.contains("CURVES"));
+
+    std::fs::write(folder.join("broken.lut"), "{").unwrap();
+    let error = luts_to_rust(&folder, &out_file).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    std::fs::remove_dir_all(folder).unwrap();
+    assert!(luts_to_rust(PathBuf::from("no/such/folder"), "paths.rs").is_err());
+}
+
+#[test]
+fn names_modules_after_files() {
+    assert_eq!(module_name("my track-2"), "my_track_2");
+    assert_eq!(module_name("Track"), "track");
+    assert_eq!(module_name("2d"), "lut_2d");
+    assert_eq!(module_name(""), "lut_");
+    assert_eq!(module_name("fn"), "fn_");
+    assert_eq!(module_name("self"), "self_");
+}
@@ -0,0 +1,54 @@
+/// Length of the path.
+#[allow(clippy::excessive_precision)]
+pub const PATH_LENGTH: f32 = 32.75;
+
+/// Evenly spaced points along the path.
+#[allow(clippy::excessive_precision)]
+pub const LUT: [[f32; 2]; 5] = [
+    [0.0, 0.0],
+    [10.0, 0.0],
+    [10.0, 0.0],
+    [10.0, 10.5],
+    [-2.25, 10.5],
+];
+
+/// Curves of the path in the order of travel: start, control start, control end, end.
+#[allow(clippy::excessive_precision)]
+pub const CURVES: [[[f32; 2]; 4]; 1] = [
+    [[0.0, 0.0], [5.0, 0.0], [10.0, 5.0], [10.0, 10.5]],
+];
+
+/// Position on the path, where `t` goes from 0 at the start to 1 at the end. The speed along
+/// the path is constant.
+pub fn position(t: f32) -> [f32; 2] {
+    if LUT.len() < 2 {
+        return LUT.first().copied().unwrap_or([0.0, 0.0]);
+    }
+    let (idx, rem) = segment(t);
+    let [x0, y0] = LUT[idx];
+    let [x1, y1] = LUT[idx + 1];
+    [x0 + rem * (x1 - x0), y0 + rem * (y1 - y0)]
+}
+
+/// Unit vector pointing in the direction of travel, where `t` goes from 0 to 1.
+pub fn tangent(t: f32) -> [f32; 2] {
+    if LUT.len() < 2 {
+        return [0.0, 0.0];
+    }
+    let (idx, _) = segment(t);
+    let direction = |from: [f32; 2], to: [f32; 2]| [to[0] - from[0], to[1] - from[1]];
+    (idx + 1..LUT.len())
+        .map(|k| direction(LUT[idx], LUT[k]))
+        .chain((0..idx).rev().map(|k| direction(LUT[k], LUT[idx + 1])))
+        .find(|[x, y]| x * x + y * y > f32::EPSILON)
+        .map_or([0.0, 0.0], |[x, y]| {
+            let length = (x * x + y * y).sqrt();
+            [x / length, y / length]
+        })
+}
+
+fn segment(t: f32) -> (usize, f32) {
+    let idx_f32 = t.clamp(0.0, 1.0) * (LUT.len() - 1) as f32;
+    let idx = (idx_f32 as usize).min(LUT.len() - 2);
+    (idx, idx_f32 - idx as f32)
+}
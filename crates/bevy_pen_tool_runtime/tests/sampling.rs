@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use bevy_pen_tool_runtime::*;
+
+use serde_json::json;
+
+fn assert_near(a: Vec2, b: Vec2) {
+    assert!(a.distance(b) < 1e-5, "{} is not {}", a, b);
+}
+
+fn straight_lut() -> StandaloneLut {
+    StandaloneLut {
+        path_length: 30.0,
+        lut: vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(30.0, 0.0),
+        ],
+    }
+}
+
+#[test]
+fn interpolates_positions_between_points() {
+    let lut = straight_lut();
+    assert_near(lut.position(0.0), Vec2::new(0.0, 0.0));
+    assert_near(lut.position(0.5), Vec2::new(15.0, 0.0));
+    assert_near(lut.position(0.9), Vec2::new(27.0, 0.0));
+    assert_near(lut.position(1.0), Vec2::new(30.0, 0.0));
+
+    // t is clamped to the path
+    assert_near(lut.position(-1.0), Vec2::new(0.0, 0.0));
+    assert_near(lut.position(2.0), Vec2::new(30.0, 0.0));
+
+    assert_eq!(compute_position_with_lut(0.5, &[]), Vec2::ZERO);
+    assert_eq!(
+        compute_position_with_lut(0.5, &[Vec2::new(3.0, 4.0)]),
+        Vec2::new(3.0, 4.0)
+    );
+}
+
+#[test]
+fn points_tangents_along_the_path() {
+    let corner = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(10.0, 0.0),
+        Vec2::new(10.0, 10.0),
+    ];
+    assert_near(compute_tangent_with_lut(0.25, &corner), Vec2::X);
+    assert_near(compute_tangent_with_lut(0.75, &corner), Vec2::Y);
+    assert_near(compute_tangent_with_lut(1.0, &corner), Vec2::Y);
+    assert_near(straight_lut().tangent(0.3), Vec2::X);
+
+    // repeated points are skipped, forward first and then backward
+    let repeated_start = [Vec2::ZERO, Vec2::ZERO, Vec2::ZERO, Vec2::new(10.0, 0.0)];
+    assert_near(compute_tangent_with_lut(0.0, &repeated_start), Vec2::X);
+    let repeated_end = [Vec2::ZERO, Vec2::new(0.0, 10.0), Vec2::new(0.0, 10.0)];
+    assert_near(compute_tangent_with_lut(1.0, &repeated_end), Vec2::Y);
+
+    // a path with no length has no tangent
+    assert_eq!(compute_tangent_with_lut(0.5, &[Vec2::ONE; 3]), Vec2::ZERO);
+    assert_eq!(compute_tangent_with_lut(0.5, &[Vec2::ONE]), Vec2::ZERO);
+}
+
+#[test]
+fn samples_cubic_curves() {
+    let curve = CurvePositions {
+        start: Vec2::new(0.0, 0.0),
+        end: Vec2::new(30.0, 0.0),
+        control_start: Vec2::new(0.0, 40.0),
+        control_end: Vec2::new(30.0, 40.0),
+    };
+    assert_near(curve.point_at(0.0), curve.start);
+    assert_near(curve.point_at(1.0), curve.end);
+    assert_near(curve.point_at(0.5), Vec2::new(15.0, 30.0));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn reads_groups_in_their_direction_of_travel() {
+    let positions = |start: [f32; 2], end: [f32; 2]| {
+        json!({
+            "start": start,
+            "end": end,
+            "control_start": start,
+            "control_end": end,
+        })
+    };
+    // the editor saves more fields with every curve, which are skipped
+    let contents = json!({
+        "version": 2,
+        "data": [{
+            "lut": [
+                [{ "positions": positions([0.0, 0.0], [10.0, 0.0]), "color": null },
+                    "End", [0.0, 0.5], [0.0, 1.0]],
+                [{ "positions": positions([10.0, 10.0], [10.0, 0.0]), "color": null },
+                    "Start", [0.5, 1.0], [0.0, 1.0]],
+            ],
+            "standalone_lut": {
+                "path_length": 20.0,
+                "lut": [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0]],
+            },
+        }],
+    });
+    let groups = GroupPath::from_saved_bytes(contents.to_string().as_bytes()).unwrap();
+    assert_eq!(groups.len(), 1);
+
+    let curves = groups[0].path_curves();
+    assert_near(curves[0].end, Vec2::new(10.0, 0.0));
+    // the second curve is traversed from its end
+    assert_near(curves[1].start, Vec2::new(10.0, 0.0));
+    assert_near(curves[1].end, Vec2::new(10.0, 10.0));
+    assert_near(curves[1].control_start, Vec2::new(10.0, 0.0));
+
+    assert_near(groups[0].position(0.75), Vec2::new(10.0, 5.0));
+    assert_near(groups[0].tangent(0.75), Vec2::Y);
+
+    let mut binary = BINARY_MAGIC.to_vec();
+    binary.extend_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(
+        GroupPath::from_saved_bytes(&binary),
+        Err(SavedFileError::Unsupported(SaveEncoding::Bincode))
+    ));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn splits_the_headers_of_saved_files() {
+    let lut = json!({ "path_length": 1.0, "lut": [[0.0, 0.0], [1.0, 0.0]] });
+
+    let (version, data) = split_header(json!({ "version": 2, "data": lut })).unwrap();
+    assert_eq!((version, &data), (2, &lut));
+
+    // files saved before the header existed are version 0, whatever their fields
+    assert_eq!(split_header(lut.clone()).unwrap(), (0, lut.clone()));
+    let (version, _) = split_header(json!({ "version": 2, "lut": [] })).unwrap();
+    assert_eq!(version, 0);
+    assert!(split_header(json!({ "version": "two", "data": lut })).is_err());
+
+    let headered = StandaloneLut::from_saved_bytes(
+        json!({ "version": 1, "data": lut }).to_string().as_bytes(),
+    )
+    .unwrap();
+    let headerless = StandaloneLut::from_saved_bytes(lut.to_string().as_bytes()).unwrap();
+    assert_eq!(headered, headerless);
+    assert_eq!(headered.lut.len(), 2);
+
+    let mut binary = BINARY_MAGIC.to_vec();
+    binary.extend_from_slice(&7u32.to_le_bytes());
+    binary.push(42);
+    assert_eq!(split_binary_header(&binary), Some((7, [42].as_slice())));
+    assert_eq!(split_binary_header(&binary[..6]), None);
+    assert_eq!(split_binary_header(b"{\"version\": 7}"), None);
+}
+
+#[cfg(not(feature = "json"))]
+#[test]
+fn reports_json_files_as_unsupported() {
+    let contents = json!({ "version": 2, "data": straight_lut() }).to_string();
+    assert!(matches!(
+        StandaloneLut::from_saved_bytes(contents.as_bytes()),
+        Err(SavedFileError::Unsupported(SaveEncoding::Json))
+    ));
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn reads_binary_look_up_tables() {
+    let mut bytes = BINARY_MAGIC.to_vec();
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(&straight_lut()).unwrap());
+    assert_eq!(
+        StandaloneLut::from_saved_bytes(&bytes).unwrap(),
+        straight_lut()
+    );
+
+    assert!(matches!(
+        StandaloneLut::from_saved_bytes(&bytes[..6]),
+        Err(SavedFileError::Bincode(_))
+    ));
+}
+
+#[cfg(not(feature = "bincode"))]
+#[test]
+fn reports_binary_look_up_tables_as_unsupported() {
+    let mut bytes = BINARY_MAGIC.to_vec();
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(
+        StandaloneLut::from_saved_bytes(&bytes),
+        Err(SavedFileError::Unsupported(SaveEncoding::Bincode))
+    ));
+}
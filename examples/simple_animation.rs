@@ -1,45 +1,47 @@
-use bevy::{prelude::*, render::camera::OrthographicProjection};
+use bevy::{asset::AssetServerSettings, prelude::*, render::camera::OrthographicProjection};
 
-use serde::Deserialize;
-use std::io::Read;
+use bevy_pen_tool_runtime::{FollowPath, PenRuntimePlugin, StandaloneLut};
 
 //
 //
 //
 // This example shows how to load a look-up table created with bevy_pen_tool
-// and how to run the corresponding animation totally independently of bevy_pen_tool
+// and how to run the corresponding animation totally independently of bevy_pen_tool.
+// Only the lightweight bevy_pen_tool_runtime crate is used.
 //
+// The look-up table is an asset: saving my_group0.lut again from the editor updates the
+// animation while the example is running. The example watches saved/look_up_tables, or the
+// folder given as first argument, such as the folder where the editor saves groups:
+// cargo run --example simple_animation -- saved/groups
 //
 //
 
-// data structure for the look-up table that will be deserialized (read from disk)
-#[derive(Deserialize)]
-struct Lut {
-    path_length: f32,
-    lut: Vec<Vec2>,
-}
-
-impl Lut {
-    // loads a look-up table that was saved in assets/lut using bevy_pen_tool
-    fn load() -> Lut {
-        let lut_path = "saved/look_up_tables/my_group0.lut";
-        let mut file = std::fs::File::open(lut_path).unwrap();
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
-        let loaded_lut: Lut = serde_json::from_str(&contents).unwrap();
-        return loaded_lut;
-    }
-}
+// handle to the look-up table, which is loaded in the background
+struct Lut(Handle<StandaloneLut>);
 
 fn main() {
+    // an absolute path, as the asset server resolves relative ones against the manifest
+    // folder instead of the working directory of the editor
+    let asset_folder = std::env::current_dir().unwrap().join(
+        std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "saved/look_up_tables".to_string()),
+    );
+
     App::new()
+        // reload assets when their file changes
+        .insert_resource(AssetServerSettings {
+            asset_folder: asset_folder.to_string_lossy().to_string(),
+            watch_for_changes: true,
+        })
         .add_plugins(DefaultPlugins)
         //
-        // insert the look-up table as a resource.
-        .insert_resource(Lut::load())
+        // registers the .lut and .group asset loaders, and moves the FollowPath entities
+        .add_plugin(PenRuntimePlugin)
         .add_startup_system(camera_setup)
+        .add_startup_system(load_lut)
         .add_startup_system(spawn_quad)
-        .add_system(follow_path)
+        .add_system(show_lut_points)
         .run();
 }
 
@@ -59,14 +61,18 @@ fn camera_setup(mut commands: Commands) {
     });
 }
 
+// loads a look-up table that was saved in the watched folder using bevy_pen_tool
+fn load_lut(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Lut(asset_server.load("my_group0.lut")));
+}
+
 #[derive(Component)]
-struct Animation;
+struct LutPoint;
 
-fn spawn_quad(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, lut: Res<Lut>) {
+fn spawn_quad(mut commands: Commands, asset_server: Res<AssetServer>) {
     // spawn sprite that will be animated
     commands
         .spawn_bundle(SpriteBundle {
-            // material: materials.add(Color::rgb(0.5, 0.5, 1.0).into()),
             transform: Transform::from_xyz(0.0, -0.0, 0.0),
             sprite: Sprite {
                 color: Color::BLACK,
@@ -76,45 +82,46 @@ fn spawn_quad(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial
 
             ..Default::default()
         })
-        // needed so that follow_path() can query the Sprite and animate it
-        .insert(Animation);
-
-    // show points from look-up table
-    for position in lut.lut.iter() {
-        commands.spawn_bundle(SpriteBundle {
-            // material: materials.add(Color::rgb(0.7, 0.5, 1.0).into()),
-            transform: Transform::from_translation(position.extend(-50.0)),
-            // sprite: Sprite::new(Vec2::new(1.0, 1.0)),
-            sprite: Sprite {
-                color: Color::GREEN,
-                custom_size: Some(Vec2::new(1.0, 1.0)),
-                ..Default::default()
-            },
-
-            ..Default::default()
-        });
-    }
+        // moves the sprite along the path, at 40 units per second
+        .insert(FollowPath::new(asset_server.load("my_group0.lut"), 40.0));
 }
 
-fn compute_position_with_lut(t: f32, lut: &Lut) -> Vec2 {
-    let lut = lut.lut.clone();
-
-    // indexing
-    let idx_f64 = t * (lut.len() - 1) as f32;
-    let p1 = lut[(idx_f64 as usize)];
-    let p2 = lut[idx_f64 as usize + 1];
-    let rem = idx_f64 % 1.0;
-
-    // interpolation
-    let position = p1 + rem * (p2 - p1); //interpolate_vec2(p1, p2, rem);
-    return position;
-}
-
-fn follow_path(mut query: Query<(&mut Transform, &Animation)>, time: Res<Time>, lut: Res<Lut>) {
-    let t_time = (time.seconds_since_startup() * 0.1) % 1.0;
-    let pos = compute_position_with_lut(t_time as f32, lut.as_ref());
-
-    for (mut transform, _bezier_animation) in query.iter_mut() {
-        transform.translation = pos.extend(transform.translation.z);
+// show points from look-up table, again every time the file is reloaded
+fn show_lut_points(
+    mut commands: Commands,
+    mut lut_events: EventReader<AssetEvent<StandaloneLut>>,
+    points: Query<Entity, With<LutPoint>>,
+    luts: Res<Assets<StandaloneLut>>,
+    lut: Res<Lut>,
+) {
+    for event in lut_events.iter() {
+        let changed = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle == &lut.0,
+            AssetEvent::Removed { .. } => false,
+        };
+        if !changed {
+            continue;
+        }
+
+        for entity in points.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        if let Some(standalone_lut) = luts.get(&lut.0) {
+            for position in standalone_lut.lut.iter() {
+                commands
+                    .spawn_bundle(SpriteBundle {
+                        transform: Transform::from_translation(position.extend(-50.0)),
+                        sprite: Sprite {
+                            color: Color::GREEN,
+                            custom_size: Some(Vec2::new(1.0, 1.0)),
+                            ..Default::default()
+                        },
+
+                        ..Default::default()
+                    })
+                    .insert(LutPoint);
+            }
+        }
     }
 }
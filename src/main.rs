@@ -1,7 +1,4 @@
-mod cam;
-
-use bevy_pen_tool_plugin::{Bezier, Globals, PenPlugin};
-use cam::{Cam, CamPlugin};
+use bevy_pen_tool_plugin::{Bezier, Globals, PenCamera, PenPlugin};
 
 use bevy::{prelude::*, render::camera::OrthographicProjection};
 
@@ -25,7 +22,6 @@ fn main() {
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
-        .add_plugin(CamPlugin)
         .add_startup_system(camera_setup)
         .add_plugin(PenPlugin)
         .add_system(test)
@@ -47,7 +43,7 @@ fn camera_setup(mut commands: Commands, mut globals: ResMut<Globals>) {
             },
             ..Default::default()
         })
-        .insert(Cam::default());
+        .insert(PenCamera::default());
 
     // sets the number of rows in the animation position look-up table. More points will
     // make an animation smoother, but will take more space in memory